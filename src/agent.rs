@@ -0,0 +1,290 @@
+// Agente de desbloqueo: proceso en segundo plano que guarda en memoria la clave maestra
+// de las bóvedas desbloqueadas para que vecla-cli no la pida en cada orden, como
+// ssh-agent. Escucha en un socket Unix accesible solo por el usuario, rechaza las
+// conexiones de otros usuarios y olvida cada clave tras un tiempo sin usarla.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+// Orden enviada al agente, una por línea en JSON
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "orden", rename_all = "snake_case")]
+pub enum Request {
+    Unlock { vault: String, key: String, timeout: u64 },
+    GetKey { vault: String },
+    Lock { vault: Option<String> }, // sin bóveda se bloquean todas
+    Status,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub vaults: Vec<VaultStatus>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub vault: String,
+    pub expira_en: u64, // segundos hasta que se olvide la clave
+}
+
+struct Sesion {
+    key: String,
+    timeout: Duration,
+    ultimo_uso: Instant,
+}
+
+impl Sesion {
+    fn restante(&self) -> Duration {
+        self.timeout.saturating_sub(self.ultimo_uso.elapsed())
+    }
+}
+
+impl Drop for Sesion {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+type Sesiones = Arc<Mutex<HashMap<String, Sesion>>>;
+
+// Directorio para los sockets: $XDG_RUNTIME_DIR/vecla o /tmp/vecla-<uid>
+pub(crate) fn runtime_dir() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("vecla"),
+        None => std::env::temp_dir().join(format!("vecla-{}", unsafe { libc::getuid() })),
+    }
+}
+
+// $VECLA_AGENT_SOCK o agent.sock en runtime_dir()
+pub fn socket_path() -> PathBuf {
+    match std::env::var_os("VECLA_AGENT_SOCK") {
+        Some(ruta) => PathBuf::from(ruta),
+        None => runtime_dir().join("agent.sock"),
+    }
+}
+
+// Crea el directorio del socket con permisos 0700, o comprueba que el existente
+// pertenece al usuario y no es accesible por nadie más
+pub(crate) fn prepare_dir(dir: &Path) -> Result<(), String> {
+    if !dir.exists() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700)).map_err(|e| e.to_string())?;
+    }
+    let meta = std::fs::metadata(dir).map_err(|e| e.to_string())?;
+    if meta.uid() != unsafe { libc::getuid() } || meta.mode() & 0o077 != 0 {
+        return Err(format!(
+            "{} debe pertenecer al usuario y no ser accesible por otros (chmod 700)",
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+// Antes de enviar la clave maestra se comprueba que el socket es del usuario y solo él
+// puede usarlo: VECLA_AGENT_SOCK podría apuntar a un socket de otro
+fn check_socket(ruta: &Path) -> Result<(), String> {
+    let meta = std::fs::metadata(ruta).map_err(|_| "El agente no está en marcha".to_string())?;
+    if !meta.file_type().is_socket() {
+        return Err(format!("{} no es un socket", ruta.display()));
+    }
+    if meta.uid() != unsafe { libc::getuid() } || meta.mode() & 0o077 != 0 {
+        return Err(format!(
+            "El socket del agente {} debe pertenecer al usuario y no ser accesible por otros (chmod 600)",
+            ruta.display()
+        ));
+    }
+    Ok(())
+}
+
+// uid del proceso al otro lado de la conexión
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn peer_uid(stream: &UnixStream) -> Result<u32, String> {
+    let mut credenciales = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut longitud = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let resultado = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credenciales as *mut libc::ucred as *mut libc::c_void,
+            &mut longitud,
+        )
+    };
+    if resultado != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(credenciales.uid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn peer_uid(stream: &UnixStream) -> Result<u32, String> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(uid)
+}
+
+// Envía una orden al agente y devuelve su respuesta
+pub fn request(peticion: &Request) -> Result<Response, String> {
+    let ruta = socket_path();
+    check_socket(&ruta)?;
+    let mut stream = UnixStream::connect(&ruta).map_err(|_| "El agente no está en marcha".to_string())?;
+    stream.set_read_timeout(Some(Duration::from_secs(5))).map_err(|e| e.to_string())?;
+    let mut linea = serde_json::to_string(peticion).map_err(|e| e.to_string())?;
+    linea.push('\n');
+    stream.write_all(linea.as_bytes()).map_err(|e| e.to_string())?;
+    linea.zeroize();
+
+    let mut respuesta = String::new();
+    BufReader::new(&stream).read_line(&mut respuesta).map_err(|e| e.to_string())?;
+    let respuesta: Response = serde_json::from_str(&respuesta).map_err(|e| format!("Respuesta del agente inválida: {}", e))?;
+    match respuesta.error {
+        Some(error) if !respuesta.ok => Err(error),
+        _ => Ok(respuesta),
+    }
+}
+
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+// Clave guardada en el agente para esta bóveda, si está desbloqueada
+pub fn cached_key(vault: &Path) -> Option<String> {
+    let vault = vault.to_string_lossy().to_string();
+    request(&Request::GetKey { vault }).ok()?.key
+}
+
+// Bucle principal del agente. Termina con la orden Stop.
+pub fn serve() -> Result<(), String> {
+    let ruta = socket_path();
+    if let Some(dir) = ruta.parent() {
+        prepare_dir(dir)?;
+    }
+    if ruta.exists() {
+        if is_running() {
+            return Err(format!("Ya hay un agente escuchando en {}", ruta.display()));
+        }
+        // Socket abandonado por un agente que no terminó bien
+        std::fs::remove_file(&ruta).map_err(|e| e.to_string())?;
+    }
+    let listener = UnixListener::bind(&ruta).map_err(|e| format!("No se pudo escuchar en {}: {}", ruta.display(), e))?;
+    std::fs::set_permissions(&ruta, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+
+    let sesiones: Sesiones = Arc::new(Mutex::new(HashMap::new()));
+    let parar = Arc::new(AtomicBool::new(false));
+
+    // Olvida las claves que llevan demasiado tiempo sin usarse
+    let caducadas = Arc::clone(&sesiones);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        caducadas.lock().unwrap().retain(|_, s| !s.restante().is_zero());
+    });
+
+    // Cada conexión en su hilo: un cliente que no envía nada no bloquea a los demás
+    for stream in listener.incoming() {
+        if parar.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else { continue };
+        let sesiones = Arc::clone(&sesiones);
+        let parar = Arc::clone(&parar);
+        let ruta = ruta.clone();
+        std::thread::spawn(move || {
+            if serve_client(&stream, &sesiones) {
+                parar.store(true, Ordering::SeqCst);
+                // Despierta el bucle de arriba para que vea la orden de parar
+                let _ = UnixStream::connect(&ruta);
+            }
+        });
+    }
+    sesiones.lock().unwrap().clear();
+    let _ = std::fs::remove_file(&ruta);
+    Ok(())
+}
+
+// Atiende una orden; devuelve true si es la de parar el agente
+fn serve_client(stream: &UnixStream, sesiones: &Sesiones) -> bool {
+    // El permiso del socket ya lo impide; esto cubre un socket con permisos cambiados
+    let propio = unsafe { libc::getuid() };
+    match peer_uid(stream) {
+        Ok(uid) if uid == propio => {}
+        Ok(_) => {
+            responder(stream, &Response { error: Some("Conexión de otro usuario rechazada".to_string()), ..Default::default() });
+            return false;
+        }
+        Err(_) => return false,
+    }
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut linea = String::new();
+    if BufReader::new(stream).read_line(&mut linea).is_err() {
+        return false;
+    }
+    let peticion = serde_json::from_str::<Request>(&linea);
+    linea.zeroize();
+    let (respuesta, parar) = match peticion {
+        Ok(peticion) => handle(sesiones, peticion),
+        Err(e) => (Response { error: Some(format!("Orden inválida: {}", e)), ..Default::default() }, false),
+    };
+    responder(stream, &respuesta);
+    parar
+}
+
+fn responder(mut stream: &UnixStream, respuesta: &Response) {
+    let mut texto = serde_json::to_string(respuesta).unwrap_or_default();
+    texto.push('\n');
+    let _ = stream.write_all(texto.as_bytes());
+    texto.zeroize();
+}
+
+fn handle(sesiones: &Sesiones, peticion: Request) -> (Response, bool) {
+    let mut sesiones = sesiones.lock().unwrap();
+    let correcta = Response { ok: true, ..Default::default() };
+    match peticion {
+        Request::Unlock { vault, key, timeout } => {
+            let timeout = Duration::from_secs(timeout.max(1));
+            sesiones.insert(vault, Sesion { key, timeout, ultimo_uso: Instant::now() });
+            (correcta, false)
+        }
+        Request::GetKey { vault } => match sesiones.get_mut(&vault).filter(|s| !s.restante().is_zero()) {
+            Some(sesion) => {
+                sesion.ultimo_uso = Instant::now();
+                (Response { key: Some(sesion.key.clone()), ..correcta }, false)
+            }
+            None => (Response { error: Some("La bóveda está bloqueada".to_string()), ..Default::default() }, false),
+        },
+        Request::Lock { vault: Some(vault) } => {
+            sesiones.remove(&vault);
+            (correcta, false)
+        }
+        Request::Lock { vault: None } => {
+            sesiones.clear();
+            (correcta, false)
+        }
+        Request::Status => {
+            let mut vaults: Vec<VaultStatus> = sesiones
+                .iter()
+                .map(|(vault, s)| VaultStatus { vault: vault.clone(), expira_en: s.restante().as_secs() })
+                .collect();
+            vaults.sort_by(|a, b| a.vault.cmp(&b.vault));
+            (Response { vaults, ..correcta }, false)
+        }
+        Request::Stop => (correcta, true),
+    }
+}
@@ -0,0 +1,550 @@
+// API HTTP local (vecla-cli serve) para herramientas propias: listar, leer, crear, modificar y
+// borrar entradas. Solo escucha en una dirección de loopback o en un socket Unix, y cada
+// petición lleva un token (Authorization: Bearer) creado con vecla-cli token create.
+//
+// Los tokens se guardan en la bóveda solo como hash SHA-256. Un token puede ser de solo
+// lectura y limitarse a unas carpetas (y sus subcarpetas); fuera de ellas las entradas no
+// existen para él.
+use chrono::Utc;
+use rand::RngCore;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use tiny_http::{Header, Method, Request, Response, Server};
+use uuid::Uuid;
+use crate::config::Config;
+use crate::crypto::{decrypt, encrypt};
+use crate::db::{ApiToken, PasswordEntry, delete_entry, delete_token, get_entry, insert_entry, insert_token, list_entries, list_fields, list_tokens, update_entry};
+
+pub const DIRECCION_POR_DEFECTO: &str = "127.0.0.1:8787";
+const PREFIJO_TOKEN: &str = "vecla_";
+// Cuerpos mayores se rechazan
+const LARGO_MAXIMO: u64 = 1024 * 1024;
+
+pub enum Listen {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+fn hex(datos: &[u8]) -> String {
+    datos.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hash_token(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+impl ApiToken {
+    pub fn folders(&self) -> Vec<&str> {
+        self.carpetas.split(',').map(|c| c.trim().trim_matches('/')).filter(|c| !c.is_empty()).collect()
+    }
+
+    // La entrada está en alguna de las carpetas del token (o el token no tiene límite)
+    pub fn allows(&self, entrada: &PasswordEntry) -> bool {
+        let carpetas = self.folders();
+        carpetas.is_empty() || carpetas.iter().any(|c| entrada.in_folder(c))
+    }
+}
+
+// Crea un token y devuelve también su valor, que no se guarda y solo se muestra esta vez
+pub fn create_token(conn: &Connection, nombre: &str, solo_lectura: bool, carpetas: &[String]) -> Result<(ApiToken, String), String> {
+    let nombre = nombre.trim();
+    if nombre.is_empty() {
+        return Err("El token necesita un nombre".to_string());
+    }
+    if list_tokens(conn).map_err(|e| e.to_string())?.iter().any(|t| t.nombre == nombre) {
+        return Err(format!("Ya existe un token \"{}\"", nombre));
+    }
+    let mut aleatorio = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut aleatorio);
+    let valor = format!("{}{}", PREFIJO_TOKEN, hex(&aleatorio));
+    let carpetas: Vec<&str> = carpetas.iter().map(|c| c.trim().trim_matches('/')).filter(|c| !c.is_empty()).collect();
+    let token = ApiToken {
+        id: Uuid::new_v4().to_string(),
+        nombre: nombre.to_string(),
+        hash: hash_token(&valor),
+        solo_lectura,
+        carpetas: carpetas.join(","),
+        creado: Utc::now().to_rfc3339(),
+    };
+    insert_token(conn, &token).map_err(|e| e.to_string())?;
+    Ok((token, valor))
+}
+
+// Revoca el token con ese nombre o id (basta el principio del id)
+pub fn revoke_token(conn: &Connection, referencia: &str) -> Result<ApiToken, String> {
+    let mut encontrados: Vec<ApiToken> = list_tokens(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|t| t.nombre == referencia || (!referencia.is_empty() && t.id.starts_with(referencia)))
+        .collect();
+    match encontrados.len() {
+        0 => Err(format!("No hay ningún token \"{}\"", referencia)),
+        1 => {
+            let token = encontrados.remove(0);
+            delete_token(conn, &token.id).map_err(|e| e.to_string())?;
+            Ok(token)
+        }
+        _ => Err(format!("\"{}\" coincide con varios tokens; usa el id", referencia)),
+    }
+}
+
+// Error que se devuelve al cliente como {"error": ...}
+struct Fallo {
+    estado: u16,
+    mensaje: String,
+}
+
+fn fallo(estado: u16, mensaje: impl Into<String>) -> Fallo {
+    Fallo { estado, mensaje: mensaje.into() }
+}
+
+impl From<String> for Fallo {
+    fn from(mensaje: String) -> Self {
+        fallo(500, mensaje)
+    }
+}
+
+impl From<rusqlite::Error> for Fallo {
+    fn from(e: rusqlite::Error) -> Self {
+        fallo(500, e.to_string())
+    }
+}
+
+// Campos que se pueden enviar al crear o modificar una entrada; los que faltan no cambian
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Cambios {
+    app: Option<String>,
+    url: Option<String>,
+    usuario: Option<String>,
+    mail: Option<String>,
+    con: Option<String>,
+    notas: Option<String>,
+    carpeta: Option<String>,
+    etiquetas: Option<String>,
+}
+
+fn entry_json(entrada: &PasswordEntry) -> Value {
+    json!({
+        "id": entrada.id,
+        "app": entrada.app,
+        "url": entrada.url,
+        "usuario": entrada.usuario,
+        "mail": entrada.mail,
+        "notas": entrada.notas,
+        "carpeta": entrada.carpeta,
+        "etiquetas": entrada.etiquetas,
+        "fecha": entrada.fecha,
+        "modificado": entrada.modificado,
+    })
+}
+
+// Decodifica %XX y + de la URL
+fn decode_component(texto: &str) -> String {
+    let bytes = texto.as_bytes();
+    let mut salida = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => salida.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3]).map(|h| u8::from_str_radix(h, 16)) {
+                    Ok(Ok(b)) => {
+                        salida.push(b);
+                        i += 2;
+                    }
+                    _ => salida.push(b'%'),
+                }
+            }
+            b => salida.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&salida).to_string()
+}
+
+fn query_param(consulta: &str, nombre: &str) -> Option<String> {
+    consulta
+        .split('&')
+        .filter_map(|par| par.split_once('=').or(Some((par, ""))))
+        .find(|(clave, _)| decode_component(clave) == nombre)
+        .map(|(_, valor)| decode_component(valor))
+}
+
+struct Estado<'a> {
+    conn: &'a Connection,
+    master_key: &'a str,
+    config: &'a Config,
+}
+
+impl Estado<'_> {
+    // Token de la cabecera Authorization, si es válido
+    fn authenticate(&self, peticion: &Request) -> Result<ApiToken, Fallo> {
+        let valor = peticion
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+            .map(|v| v.trim().to_string())
+            .ok_or_else(|| fallo(401, "Falta el token (Authorization: Bearer ...)"))?;
+        let hash = hash_token(&valor);
+        list_tokens(self.conn)?
+            .into_iter()
+            .find(|t| t.hash == hash)
+            .ok_or_else(|| fallo(401, "Token inválido o revocado"))
+    }
+
+    fn check_write(&self, token: &ApiToken) -> Result<(), Fallo> {
+        if token.solo_lectura {
+            return Err(fallo(403, "El token es de solo lectura"));
+        }
+        if self.config.readonly {
+            return Err(fallo(403, "La bóveda está abierta en solo lectura"));
+        }
+        Ok(())
+    }
+
+    fn after_write(&self) -> Result<(), Fallo> {
+        crate::backup::auto_backup(self.conn, self.config)?;
+        Ok(())
+    }
+
+    // Entrada visible para el token; las de otras carpetas se tratan como inexistentes
+    fn entry(&self, token: &ApiToken, id: &str) -> Result<PasswordEntry, Fallo> {
+        get_entry(self.conn, id)?
+            .filter(|e| token.allows(e))
+            .ok_or_else(|| fallo(404, format!("No hay ninguna entrada {}", id)))
+    }
+
+    fn apply(&self, token: &ApiToken, entrada: &mut PasswordEntry, cambios: Cambios) -> Result<(), Fallo> {
+        if let Some(app) = cambios.app {
+            if app.trim().is_empty() {
+                return Err(fallo(400, "El campo app no puede quedar vacío"));
+            }
+            entrada.app = app;
+        }
+        if let Some(url) = cambios.url {
+            entrada.url = url;
+        }
+        if let Some(usuario) = cambios.usuario {
+            entrada.usuario = usuario;
+        }
+        if let Some(mail) = cambios.mail {
+            entrada.mail = mail;
+        }
+        // Si la contraseña no cambia se deja como está y no cuenta como cambio de contraseña
+        if let Some(con) = cambios.con.filter(|c| decrypt(self.master_key, &entrada.con).ok().as_ref() != Some(c)) {
+            entrada.con = encrypt(self.master_key, &con)?;
+        }
+        if let Some(notas) = cambios.notas {
+            entrada.notas = notas;
+        }
+        if let Some(carpeta) = cambios.carpeta {
+            entrada.carpeta = carpeta.trim().trim_matches('/').to_string();
+        }
+        if let Some(etiquetas) = cambios.etiquetas {
+            entrada.etiquetas = etiquetas;
+        }
+        if !token.allows(entrada) {
+            return Err(fallo(403, "La carpeta está fuera del alcance del token"));
+        }
+        Ok(())
+    }
+
+    fn handle(&self, peticion: &mut Request) -> Result<(u16, Option<Value>), Fallo> {
+        let token = self.authenticate(peticion)?;
+        let url = peticion.url().to_string();
+        let (ruta, consulta) = url.split_once('?').unwrap_or((&url, ""));
+        let partes: Vec<String> = ruta.trim_matches('/').split('/').map(decode_component).collect();
+        let partes: Vec<&str> = partes.iter().map(|p| p.as_str()).collect();
+        let metodo = peticion.method().clone();
+
+        match (&metodo, partes.as_slice()) {
+            // GET /entries?folder=&tag= (sin contraseñas)
+            (Method::Get, ["entries"]) => {
+                let carpeta = query_param(consulta, "folder");
+                let etiqueta = query_param(consulta, "tag");
+                let lista: Vec<Value> = list_entries(self.conn)?
+                    .iter()
+                    .filter(|e| token.allows(e))
+                    .filter(|e| carpeta.as_deref().is_none_or(|c| e.in_folder(c)))
+                    .filter(|e| etiqueta.as_deref().is_none_or(|t| e.has_tag(t)))
+                    .map(entry_json)
+                    .collect();
+                Ok((200, Some(Value::Array(lista))))
+            }
+            // GET /entries/{id}: la entrada con su contraseña y campos personalizados
+            (Method::Get, ["entries", id]) => {
+                let entrada = self.entry(&token, id)?;
+                let mut valor = entry_json(&entrada);
+                valor["con"] = json!(decrypt(self.master_key, &entrada.con)?);
+                let mut campos = Vec::new();
+                for campo in list_fields(self.conn, &entrada.id)? {
+                    campos.push(json!({
+                        "nombre": campo.nombre,
+                        "valor": decrypt(self.master_key, &campo.valor)?,
+                        "protegido": campo.protegido,
+                    }));
+                }
+                valor["campos"] = Value::Array(campos);
+                Ok((200, Some(valor)))
+            }
+            (Method::Post, ["entries"]) => {
+                self.check_write(&token)?;
+                let cambios = read_body(peticion)?;
+                if cambios.app.as_deref().is_none_or(|a| a.trim().is_empty()) {
+                    return Err(fallo(400, "Falta el campo app"));
+                }
+                let mut entrada = PasswordEntry::new("", "", "", "", &encrypt(self.master_key, "")?, "");
+                self.apply(&token, &mut entrada, cambios)?;
+                insert_entry(self.conn, &entrada)?;
+                self.after_write()?;
+                Ok((201, Some(entry_json(&entrada))))
+            }
+            // PUT y PATCH cambian solo los campos enviados
+            (Method::Put | Method::Patch, ["entries", id]) => {
+                self.check_write(&token)?;
+                let mut entrada = self.entry(&token, id)?;
+                let cambios = read_body(peticion)?;
+                self.apply(&token, &mut entrada, cambios)?;
+                entrada.modificado = Utc::now().to_rfc3339();
+                update_entry(self.conn, &entrada)?;
+                self.after_write()?;
+                Ok((200, Some(entry_json(&entrada))))
+            }
+            (Method::Delete, ["entries", id]) => {
+                self.check_write(&token)?;
+                let entrada = self.entry(&token, id)?;
+                delete_entry(self.conn, &entrada.id)?;
+                self.after_write()?;
+                Ok((204, None))
+            }
+            (_, ["entries"]) | (_, ["entries", _]) => Err(fallo(405, format!("Método {} no admitido", metodo))),
+            _ => Err(fallo(404, format!("No existe {}", ruta))),
+        }
+    }
+}
+
+fn read_body(peticion: &mut Request) -> Result<Cambios, Fallo> {
+    let mut cuerpo = Vec::new();
+    peticion
+        .as_reader()
+        .take(LARGO_MAXIMO + 1)
+        .read_to_end(&mut cuerpo)
+        .map_err(|e| fallo(400, e.to_string()))?;
+    if cuerpo.len() as u64 > LARGO_MAXIMO {
+        return Err(fallo(413, "Cuerpo demasiado grande"));
+    }
+    serde_json::from_slice(&cuerpo).map_err(|e| fallo(400, format!("JSON inválido: {}", e)))
+}
+
+fn respond(peticion: Request, estado: u16, cuerpo: Option<Value>) {
+    let datos = cuerpo.map(|c| serde_json::to_vec(&c).unwrap_or_default()).unwrap_or_default();
+    let mut respuesta = Response::from_data(datos)
+        .with_status_code(estado)
+        .with_header(Header::from_bytes("Cache-Control", "no-store").unwrap());
+    if estado != 204 {
+        respuesta.add_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    }
+    if estado == 401 {
+        respuesta.add_header(Header::from_bytes("WWW-Authenticate", "Bearer").unwrap());
+    }
+    let _ = peticion.respond(respuesta);
+}
+
+fn bind(escucha: &Listen) -> Result<Server, String> {
+    match escucha {
+        Listen::Tcp(direccion) => {
+            if !direccion.ip().is_loopback() {
+                return Err(format!("{} no es una dirección local; usa 127.0.0.1 o ::1", direccion));
+            }
+            Server::http(direccion).map_err(|e| format!("No se pudo escuchar en {}: {}", direccion, e))
+        }
+        #[cfg(unix)]
+        Listen::Unix(ruta) => {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(dir) = ruta.parent() {
+                crate::agent::prepare_dir(dir)?;
+            }
+            if ruta.exists() {
+                if std::os::unix::net::UnixStream::connect(ruta).is_ok() {
+                    return Err(format!("Ya hay un servidor escuchando en {}", ruta.display()));
+                }
+                std::fs::remove_file(ruta).map_err(|e| e.to_string())?;
+            }
+            let servidor = Server::http_unix(ruta).map_err(|e| format!("No se pudo escuchar en {}: {}", ruta.display(), e))?;
+            std::fs::set_permissions(ruta, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+            Ok(servidor)
+        }
+    }
+}
+
+// Atiende peticiones, una tras otra, hasta que se termine el proceso
+pub fn serve(conn: &Connection, master_key: &str, config: &Config, escucha: &Listen) -> Result<(), String> {
+    let servidor = bind(escucha)?;
+    let estado = Estado { conn, master_key, config };
+    for mut peticion in servidor.incoming_requests() {
+        let linea = format!("{} {}", peticion.method(), peticion.url().split('?').next().unwrap_or(""));
+        let (codigo, cuerpo) = match estado.handle(&mut peticion) {
+            Ok(respuesta) => respuesta,
+            Err(f) => (f.estado, Some(json!({ "error": f.mensaje }))),
+        };
+        eprintln!("{} {}", linea, codigo);
+        respond(peticion, codigo, cuerpo);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_vault;
+    use tiny_http::TestRequest;
+
+    const CLAVE: &str = "maestra";
+
+    struct Prueba {
+        conn: Connection,
+        config: Config,
+        trabajo: PasswordEntry,
+        personal: PasswordEntry,
+    }
+
+    impl Prueba {
+        fn new() -> Self {
+            let conn = test_vault();
+            let mut config = Config::new();
+            config.auto_save = false;
+            let mut trabajo = PasswordEntry::new("Jira", "", "ana", "", &encrypt(CLAVE, "j1r4").unwrap(), "");
+            trabajo.carpeta = "Trabajo/Proyectos".to_string();
+            let mut personal = PasswordEntry::new("Banco", "", "ana", "", &encrypt(CLAVE, "b4nc0").unwrap(), "");
+            personal.carpeta = "Personal".to_string();
+            insert_entry(&conn, &trabajo).unwrap();
+            insert_entry(&conn, &personal).unwrap();
+            Self { conn, config, trabajo, personal }
+        }
+
+        fn token(&self, nombre: &str, solo_lectura: bool, carpetas: &[&str]) -> String {
+            let carpetas: Vec<String> = carpetas.iter().map(|c| c.to_string()).collect();
+            create_token(&self.conn, nombre, solo_lectura, &carpetas).unwrap().1
+        }
+
+        // Código de estado y cuerpo de la respuesta, como los vería el cliente
+        fn call(&self, metodo: Method, ruta: &str, token: &str, cuerpo: &'static str) -> (u16, Value) {
+            let estado = Estado { conn: &self.conn, master_key: CLAVE, config: &self.config };
+            let cabecera = Header::from_bytes("Authorization", format!("Bearer {}", token)).unwrap();
+            let mut peticion: Request =
+                TestRequest::new().with_method(metodo).with_path(ruta).with_header(cabecera).with_body(cuerpo).into();
+            match estado.handle(&mut peticion) {
+                Ok((codigo, cuerpo)) => (codigo, cuerpo.unwrap_or(Value::Null)),
+                Err(f) => (f.estado, json!({ "error": f.mensaje })),
+            }
+        }
+    }
+
+    #[test]
+    fn token_allows_its_folders_and_subfolders() {
+        let prueba = Prueba::new();
+        let token = |carpetas: &str| ApiToken {
+            id: String::new(),
+            nombre: String::new(),
+            hash: String::new(),
+            solo_lectura: false,
+            carpetas: carpetas.to_string(),
+            creado: String::new(),
+        };
+        assert!(token("").allows(&prueba.personal));
+        assert!(token("Trabajo").allows(&prueba.trabajo));
+        assert!(token("/Trabajo/Proyectos/").allows(&prueba.trabajo));
+        assert!(!token("Trabajo").allows(&prueba.personal));
+        assert!(!token("Trab").allows(&prueba.trabajo));
+        assert!(token("Trabajo, Personal").allows(&prueba.personal));
+    }
+
+    #[test]
+    fn rejects_missing_and_revoked_tokens() {
+        let prueba = Prueba::new();
+        assert_eq!(prueba.call(Method::Get, "/entries", "vecla_falso", "").0, 401);
+        let valor = prueba.token("ci", false, &[]);
+        assert_eq!(prueba.call(Method::Get, "/entries", &valor, "").0, 200);
+        revoke_token(&prueba.conn, "ci").unwrap();
+        assert_eq!(prueba.call(Method::Get, "/entries", &valor, "").0, 401);
+    }
+
+    #[test]
+    fn folder_scoped_token_only_sees_its_folders() {
+        let prueba = Prueba::new();
+        let valor = prueba.token("trabajo", false, &["Trabajo"]);
+
+        let (codigo, lista) = prueba.call(Method::Get, "/entries", &valor, "");
+        assert_eq!(codigo, 200);
+        let ids: Vec<&str> = lista.as_array().unwrap().iter().map(|e| e["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, [prueba.trabajo.id.as_str()]);
+
+        let (codigo, entrada) = prueba.call(Method::Get, &format!("/entries/{}", prueba.trabajo.id), &valor, "");
+        assert_eq!(codigo, 200);
+        assert_eq!(entrada["con"], "j1r4");
+        // Las entradas de otras carpetas no existen para el token
+        assert_eq!(prueba.call(Method::Get, &format!("/entries/{}", prueba.personal.id), &valor, "").0, 404);
+        assert_eq!(prueba.call(Method::Delete, &format!("/entries/{}", prueba.personal.id), &valor, "").0, 404);
+
+        // Ni se puede sacar una entrada de su alcance ni crearla fuera de él
+        let ruta = format!("/entries/{}", prueba.trabajo.id);
+        assert_eq!(prueba.call(Method::Patch, &ruta, &valor, r#"{"carpeta": "Personal"}"#).0, 403);
+        assert_eq!(get_entry(&prueba.conn, &prueba.trabajo.id).unwrap().unwrap().carpeta, "Trabajo/Proyectos");
+        assert_eq!(prueba.call(Method::Post, "/entries", &valor, r#"{"app": "Otra", "carpeta": "Personal"}"#).0, 403);
+
+        let cuerpo = r#"{"app": "Wiki", "carpeta": "/Trabajo/"}"#;
+        let (codigo, creada) = prueba.call(Method::Post, "/entries", &valor, cuerpo);
+        assert_eq!(codigo, 201);
+        assert_eq!(creada["carpeta"], "Trabajo");
+        assert_eq!(prueba.call(Method::Patch, &ruta, &valor, r#"{"carpeta": "Trabajo"}"#).0, 200);
+    }
+
+    #[test]
+    fn read_only_token_cannot_write() {
+        let prueba = Prueba::new();
+        let valor = prueba.token("lectura", true, &[]);
+        let ruta = format!("/entries/{}", prueba.personal.id);
+        assert_eq!(prueba.call(Method::Get, &ruta, &valor, "").0, 200);
+        assert_eq!(prueba.call(Method::Post, "/entries", &valor, r#"{"app": "Nueva"}"#).0, 403);
+        assert_eq!(prueba.call(Method::Patch, &ruta, &valor, r#"{"con": "otra"}"#).0, 403);
+        assert_eq!(prueba.call(Method::Delete, &ruta, &valor, "").0, 403);
+        let entrada = get_entry(&prueba.conn, &prueba.personal.id).unwrap().unwrap();
+        assert_eq!(decrypt(CLAVE, &entrada.con).unwrap(), "b4nc0");
+        assert_eq!(list_entries(&prueba.conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn filters_entries_by_query() {
+        let prueba = Prueba::new();
+        let valor = prueba.token("todo", false, &[]);
+        let (_, lista) = prueba.call(Method::Get, "/entries?folder=Trabajo%2FProyectos", &valor, "");
+        assert_eq!(lista.as_array().unwrap().len(), 1);
+        assert_eq!(lista[0]["app"], "Jira");
+        let (_, lista) = prueba.call(Method::Get, "/entries?tag=nada", &valor, "");
+        assert!(lista.as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn decodes_url_components() {
+        assert_eq!(decode_component("Mis+claves%2FTrabajo"), "Mis claves/Trabajo");
+        assert_eq!(decode_component("Contrase%C3%B1as"), "Contraseñas");
+        // Secuencias incompletas o no hexadecimales se dejan tal cual
+        assert_eq!(decode_component("100%"), "100%");
+        assert_eq!(decode_component("%zz%4"), "%zz%4");
+
+        assert_eq!(query_param("folder=Trabajo&tag=ci", "tag").as_deref(), Some("ci"));
+        assert_eq!(query_param("folder=Mis%20claves", "folder").as_deref(), Some("Mis claves"));
+        assert_eq!(query_param("vacio&tag=ci", "vacio").as_deref(), Some(""));
+        assert_eq!(query_param("folder=Trabajo", "tag"), None);
+        assert_eq!(query_param("", "tag"), None);
+    }
+}
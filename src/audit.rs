@@ -0,0 +1,265 @@
+// Informe de salud de la bóveda: contraseñas débiles, reutilizadas y antiguas, entradas sin
+// URL y entradas duplicadas. Las contraseñas se comparan por su HMAC con la clave maestra,
+// de modo que el informe no guarda ni muestra ninguna.
+use crate::crypto::decrypt;
+use crate::db::PasswordEntry;
+use crate::strength;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+
+#[derive(Serialize, Clone)]
+pub struct EntryRef {
+    pub id: String,
+    pub app: String,
+    pub usuario: String,
+    pub carpeta: String,
+}
+
+impl EntryRef {
+    fn new(entrada: &PasswordEntry) -> Self {
+        Self {
+            id: entrada.id.clone(),
+            app: entrada.app.clone(),
+            usuario: entrada.usuario.clone(),
+            carpeta: entrada.carpeta.clone(),
+        }
+    }
+
+    // "app (usuario)" para listados
+    pub fn label(&self) -> String {
+        if self.usuario.is_empty() {
+            self.app.clone()
+        } else {
+            format!("{} ({})", self.app, self.usuario)
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct WeakPassword {
+    #[serde(flatten)]
+    pub entrada: EntryRef,
+    pub puntuacion: u8,
+    pub nivel: &'static str,
+    pub tiempo: String, // tiempo estimado para adivinarla
+    pub aviso: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct OldPassword {
+    #[serde(flatten)]
+    pub entrada: EntryRef,
+    pub dias: i64, // desde el último cambio de la contraseña
+}
+
+#[derive(Serialize)]
+pub struct AuditReport {
+    pub total: usize,
+    pub dias_maximos: u64, // 0 = no se comprueba la antigüedad
+    pub debiles: Vec<WeakPassword>,
+    pub reutilizadas: Vec<Vec<EntryRef>>, // grupos de entradas con la misma contraseña
+    pub antiguas: Vec<OldPassword>,
+    pub sin_url: Vec<EntryRef>,
+    pub duplicadas: Vec<Vec<EntryRef>>,   // grupos de entradas idénticas
+    pub ilegibles: Vec<EntryRef>,         // no se pudieron descifrar
+}
+
+impl AuditReport {
+    // Número total de avisos
+    pub fn issues(&self) -> usize {
+        self.debiles.len()
+            + self.reutilizadas.len()
+            + self.antiguas.len()
+            + self.sin_url.len()
+            + self.duplicadas.len()
+            + self.ilegibles.len()
+    }
+}
+
+// dias_maximos: antigüedad a partir de la cual se avisa, 0 = nunca
+pub fn audit(entradas: &[PasswordEntry], master_key: &str, dias_maximos: u64) -> AuditReport {
+    let ahora = Utc::now();
+    let mut informe = AuditReport {
+        total: entradas.len(),
+        dias_maximos,
+        debiles: Vec::new(),
+        reutilizadas: Vec::new(),
+        antiguas: Vec::new(),
+        sin_url: Vec::new(),
+        duplicadas: Vec::new(),
+        ilegibles: Vec::new(),
+    };
+    // Entradas agrupadas por el HMAC de su contraseña, en el orden en que aparecen
+    let mut por_contrasena: Vec<([u8; 32], Vec<&PasswordEntry>)> = Vec::new();
+    let mut indice: HashMap<[u8; 32], usize> = HashMap::new();
+    for entrada in entradas {
+        let referencia = EntryRef::new(entrada);
+        if entrada.url.trim().is_empty() {
+            informe.sin_url.push(referencia.clone());
+        }
+        if dias_maximos > 0 {
+            if let Ok(fecha) = DateTime::parse_from_rfc3339(&entrada.con_modificada) {
+                let dias = (ahora - fecha.with_timezone(&Utc)).num_days();
+                if dias > dias_maximos as i64 {
+                    informe.antiguas.push(OldPassword { entrada: referencia.clone(), dias });
+                }
+            }
+        }
+        let contrasena = match decrypt(master_key, &entrada.con) {
+            Ok(c) => Zeroizing::new(c),
+            Err(_) => {
+                informe.ilegibles.push(referencia);
+                continue;
+            }
+        };
+        let datos = [entrada.app.as_str(), entrada.url.as_str(), entrada.usuario.as_str(), entrada.mail.as_str()];
+        let fortaleza = strength::estimate_with_inputs(&contrasena, &datos);
+        if fortaleza.score < 3 {
+            informe.debiles.push(WeakPassword {
+                entrada: referencia,
+                puntuacion: fortaleza.score,
+                nivel: fortaleza.label(),
+                tiempo: fortaleza.crack_time_label(),
+                aviso: fortaleza.warning,
+            });
+        }
+        let huella = password_hash(master_key, &contrasena);
+        match indice.get(&huella) {
+            Some(i) => por_contrasena[*i].1.push(entrada),
+            None => {
+                indice.insert(huella, por_contrasena.len());
+                por_contrasena.push((huella, vec![entrada]));
+            }
+        }
+    }
+    for (_, grupo) in por_contrasena.iter().filter(|(_, g)| g.len() > 1) {
+        // Dentro del grupo, las entradas idénticas son duplicados; solo hay reutilización
+        // si la contraseña se usa en más de una cuenta distinta
+        let mut cuentas: Vec<(&str, &str, &str, &str, Vec<EntryRef>)> = Vec::new();
+        for entrada in grupo {
+            let clave = (entrada.app.as_str(), entrada.url.as_str(), entrada.usuario.as_str(), entrada.mail.as_str());
+            match cuentas.iter_mut().find(|c| (c.0, c.1, c.2, c.3) == clave) {
+                Some(cuenta) => cuenta.4.push(EntryRef::new(entrada)),
+                None => cuentas.push((clave.0, clave.1, clave.2, clave.3, vec![EntryRef::new(entrada)])),
+            }
+        }
+        if cuentas.len() > 1 {
+            informe.reutilizadas.push(grupo.iter().map(|e| EntryRef::new(e)).collect());
+        }
+        informe.duplicadas.extend(cuentas.into_iter().map(|c| c.4).filter(|g| g.len() > 1));
+    }
+    informe.debiles.sort_by_key(|d| d.puntuacion);
+    informe.reutilizadas.sort_by_key(|g| std::cmp::Reverse(g.len()));
+    informe.antiguas.sort_by_key(|a| std::cmp::Reverse(a.dias));
+    informe
+}
+
+fn password_hash(master_key: &str, contrasena: &str) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(master_key.as_bytes()).expect("HMAC acepta cualquier longitud");
+    mac.update(contrasena.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::encrypt;
+    use chrono::Duration;
+
+    const CLAVE: &str = "maestra";
+    const FUERTE: &str = "x7#Kp2$vQ9!m-Lw4";
+
+    fn entry(app: &str, url: &str, usuario: &str, con: &str) -> PasswordEntry {
+        PasswordEntry::new(app, url, usuario, "", &encrypt(CLAVE, con).unwrap(), "")
+    }
+
+    fn apps(grupo: &[EntryRef]) -> Vec<&str> {
+        grupo.iter().map(|e| e.app.as_str()).collect()
+    }
+
+    #[test]
+    fn healthy_vault_has_no_issues() {
+        let entradas = [entry("Correo", "https://mail.example.com", "ana", FUERTE)];
+        let informe = audit(&entradas, CLAVE, 365);
+        assert_eq!(informe.total, 1);
+        assert_eq!(informe.issues(), 0);
+    }
+
+    #[test]
+    fn weak_passwords() {
+        let entradas = [
+            entry("Correo", "https://mail.example.com", "ana", "123456"),
+            entry("Banco", "https://banco.example.com", "ana", FUERTE),
+        ];
+        let informe = audit(&entradas, CLAVE, 0);
+        assert_eq!(informe.debiles.len(), 1);
+        assert_eq!(informe.debiles[0].entrada.app, "Correo");
+        assert_eq!(informe.debiles[0].puntuacion, 0);
+    }
+
+    #[test]
+    fn reused_and_duplicated() {
+        let entradas = [
+            entry("Correo", "https://mail.example.com", "ana", FUERTE),
+            entry("Banco", "https://banco.example.com", "ana", FUERTE),
+            // Copia exacta de la primera: duplicado, no reutilización
+            entry("Correo", "https://mail.example.com", "ana", FUERTE),
+            entry("Foro", "https://foro.example.com", "ana", "otra-Kp2$vQ9!m-Lw4"),
+            entry("Foro", "https://foro.example.com", "ana", "otra-Kp2$vQ9!m-Lw4"),
+        ];
+        let informe = audit(&entradas, CLAVE, 0);
+        assert_eq!(informe.reutilizadas.len(), 1);
+        assert_eq!(apps(&informe.reutilizadas[0]), ["Correo", "Banco", "Correo"]);
+        assert_eq!(informe.duplicadas.len(), 2);
+        assert_eq!(apps(&informe.duplicadas[0]), ["Correo", "Correo"]);
+        assert_eq!(apps(&informe.duplicadas[1]), ["Foro", "Foro"]);
+    }
+
+    #[test]
+    fn old_passwords_count_from_password_change() {
+        let hace = |dias: i64| (Utc::now() - Duration::days(dias)).to_rfc3339();
+        let mut antigua = entry("Antigua", "https://a.example.com", "ana", FUERTE);
+        antigua.con_modificada = hace(400);
+        // Editada hace poco pero con la contraseña de hace 500 días
+        let mut editada = entry("Editada", "https://b.example.com", "ana", "otra-Kp2$vQ9!m-Lw4");
+        editada.modificado = hace(1);
+        editada.con_modificada = hace(500);
+        let mut reciente = entry("Reciente", "https://c.example.com", "ana", "3ra-Kp2$vQ9!m-Lw4");
+        reciente.modificado = hace(400);
+        reciente.con_modificada = hace(10);
+        let entradas = [antigua, editada, reciente];
+
+        let informe = audit(&entradas, CLAVE, 365);
+        let antiguas: Vec<(&str, i64)> = informe.antiguas.iter().map(|a| (a.entrada.app.as_str(), a.dias)).collect();
+        assert_eq!(antiguas, [("Editada", 500), ("Antigua", 400)]);
+        assert!(audit(&entradas, CLAVE, 0).antiguas.is_empty());
+    }
+
+    #[test]
+    fn missing_urls() {
+        let entradas = [
+            entry("Correo", "https://mail.example.com", "ana", FUERTE),
+            entry("Wifi", "  ", "", "otra-Kp2$vQ9!m-Lw4"),
+        ];
+        let informe = audit(&entradas, CLAVE, 0);
+        assert_eq!(apps(&informe.sin_url), ["Wifi"]);
+    }
+
+    #[test]
+    fn unreadable_entries() {
+        let mut otra_clave = entry("Rota", "https://r.example.com", "ana", FUERTE);
+        otra_clave.con = encrypt("otra", FUERTE).unwrap();
+        let mut no_base64 = entry("Basura", "https://b.example.com", "ana", FUERTE);
+        no_base64.con = "no es base64".to_string();
+        let entradas = [otra_clave, no_base64, entry("Correo", "https://mail.example.com", "ana", FUERTE)];
+        let informe = audit(&entradas, CLAVE, 0);
+        assert_eq!(apps(&informe.ilegibles), ["Rota", "Basura"]);
+        // Las ilegibles no cuentan como reutilizadas aunque la contraseña sea la misma
+        assert!(informe.reutilizadas.is_empty());
+        assert_eq!(informe.issues(), 2);
+    }
+}
@@ -0,0 +1,220 @@
+// Copias de seguridad rotativas de la bóveda con la API de backup en línea de SQLite
+use chrono::{Duration, NaiveDateTime, Utc};
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::path::{Path, PathBuf};
+use crate::config::Config;
+use crate::db::migrate;
+
+pub struct BackupInfo {
+    pub ruta: PathBuf,
+    pub fecha: NaiveDateTime, // UTC, según el nombre del archivo
+    pub tamano: u64,
+}
+
+const FORMATO_FECHA: &str = "%Y%m%d-%H%M%S-%3f";
+// Como mucho una copia automática tras cambios cada tantos minutos: con una copia por
+// cambio, una ráfaga de escrituras (p. ej. una importación por la API) rotaría todas las
+// generaciones y solo quedarían copias de los últimos segundos
+const INTERVALO_AUTOMATICO_MINUTOS: i64 = 15;
+
+// Carpeta de copias: backup_path o "backups" junto a la bóveda
+pub fn backup_dir(config: &Config) -> PathBuf {
+    match config.get_backup_path() {
+        Some(ruta) => ruta,
+        None => config
+            .get_db_path()
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("backups"),
+    }
+}
+
+fn backup_prefix(config: &Config) -> String {
+    let db_path = config.get_db_path();
+    let nombre = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("vault");
+    format!("{}-", nombre)
+}
+
+// Comprueba que una copia se puede abrir, está íntegra y contiene la tabla de contraseñas.
+// Devuelve el número de entradas.
+pub fn verify_backup(ruta: &Path) -> Result<usize, String> {
+    let conn = Connection::open_with_flags(ruta, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("No se pudo abrir la copia: {}", e))?;
+    let estado: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if estado != "ok" {
+        return Err(format!("La copia está dañada: {}", estado));
+    }
+    conn.query_row("SELECT COUNT(*) FROM passwords", [], |row| row.get::<_, i64>(0))
+        .map(|n| n as usize)
+        .map_err(|e| format!("La copia no contiene una bóveda válida: {}", e))
+}
+
+// Toma una copia consistente, la verifica y elimina las generaciones sobrantes
+pub fn create_backup(conn: &Connection, config: &Config) -> Result<PathBuf, String> {
+    let destino = snapshot(conn, config)?;
+    rotate_backups(config)?;
+    Ok(destino)
+}
+
+// Copia tras un cambio en la bóveda: solo con auto_save y si la última copia tiene más de
+// INTERVALO_AUTOMATICO_MINUTOS. Las copias al desbloquear y las manuales no esperan.
+pub fn auto_backup(conn: &Connection, config: &Config) -> Result<Option<PathBuf>, String> {
+    if !config.auto_save {
+        return Ok(None);
+    }
+    let ahora = Utc::now().naive_utc();
+    // Una copia con fecha futura (reloj cambiado) no debe impedir las siguientes
+    let reciente = list_backups(config)?.first().is_some_and(|copia| {
+        copia.fecha <= ahora && ahora - copia.fecha < Duration::minutes(INTERVALO_AUTOMATICO_MINUTOS)
+    });
+    if reciente {
+        return Ok(None);
+    }
+    create_backup(conn, config).map(Some)
+}
+
+fn snapshot(conn: &Connection, config: &Config) -> Result<PathBuf, String> {
+    let dir = backup_dir(config);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+    let nombre = format!("{}{}.db", backup_prefix(config), Utc::now().format(FORMATO_FECHA));
+    let destino = dir.join(&nombre);
+    let temporal = dir.join(format!("{}.tmp", nombre));
+
+    conn.backup(DatabaseName::Main, &temporal, None)
+        .map_err(|e| format!("Error al copiar la bóveda: {}", e))?;
+    if let Err(e) = verify_backup(&temporal) {
+        let _ = std::fs::remove_file(&temporal);
+        return Err(e);
+    }
+    std::fs::rename(&temporal, &destino).map_err(|e| e.to_string())?;
+    Ok(destino)
+}
+
+// Copias existentes, de la más reciente a la más antigua
+pub fn list_backups(config: &Config) -> Result<Vec<BackupInfo>, String> {
+    let dir = backup_dir(config);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let prefijo = backup_prefix(config);
+    let mut copias = Vec::new();
+    for entrada in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entrada = entrada.map_err(|e| e.to_string())?;
+        let nombre = entrada.file_name().to_string_lossy().to_string();
+        // Solo "<bóveda>-<fecha>.db": las copias de otra bóveda que comparta la carpeta
+        // (p. ej. "vault-old-<fecha>.db" junto a las de "vault.db") no son de esta
+        let Some(fecha) = nombre
+            .strip_prefix(&prefijo)
+            .and_then(|n| n.strip_suffix(".db"))
+            .and_then(|marca| NaiveDateTime::parse_from_str(marca, FORMATO_FECHA).ok())
+        else {
+            continue;
+        };
+        copias.push(BackupInfo {
+            ruta: entrada.path(),
+            fecha,
+            tamano: entrada.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+    copias.sort_by_key(|c| std::cmp::Reverse(c.fecha));
+    Ok(copias)
+}
+
+fn rotate_backups(config: &Config) -> Result<(), String> {
+    let copias = list_backups(config)?;
+    for sobrante in copias.iter().skip(config.backup_generations.max(1)) {
+        std::fs::remove_file(&sobrante.ruta)
+            .map_err(|e| format!("No se pudo borrar {}: {}", sobrante.ruta.display(), e))?;
+    }
+    Ok(())
+}
+
+// Sustituye el contenido de la bóveda abierta por el de una copia. Antes se guarda
+// una copia del estado actual para poder deshacer la restauración.
+pub fn restore_backup(conn: &mut Connection, config: &Config, ruta: &Path) -> Result<(), String> {
+    verify_backup(ruta)?;
+    // La rotación se hace al final para no borrar la copia que se está restaurando
+    snapshot(conn, config)?;
+    conn.restore(DatabaseName::Main, ruta, None::<fn(rusqlite::backup::Progress)>)
+        .map_err(|e| format!("Error al restaurar: {}", e))?;
+    // La copia puede venir de una versión anterior del esquema
+    migrate(conn).map_err(|e| e.to_string())?;
+    rotate_backups(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{insert_entry, test_vault, PasswordEntry};
+
+    fn temp_vault() -> (Connection, Config, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("vecla-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = Config::new().with_db_path(&dir.join("vault.db").to_string_lossy());
+        let conn = test_vault();
+        insert_entry(&conn, &PasswordEntry::new("Correo", "", "ana", "", "x", "")).unwrap();
+        (conn, config, dir)
+    }
+
+    #[test]
+    fn auto_backup_is_rate_limited() {
+        let (conn, config, dir) = temp_vault();
+        assert!(auto_backup(&conn, &config).unwrap().is_some());
+        // Una ráfaga de cambios no crea más copias ni rota las anteriores
+        for _ in 0..20 {
+            assert!(auto_backup(&conn, &config).unwrap().is_none());
+        }
+        assert_eq!(list_backups(&config).unwrap().len(), 1);
+
+        // Pasado el intervalo se vuelve a copiar
+        let copia = &list_backups(&config).unwrap()[0].ruta;
+        let antigua = Utc::now().naive_utc() - Duration::minutes(INTERVALO_AUTOMATICO_MINUTOS + 1);
+        let nombre = format!("{}{}.db", backup_prefix(&config), antigua.format(FORMATO_FECHA));
+        std::fs::rename(copia, backup_dir(&config).join(nombre)).unwrap();
+        assert!(auto_backup(&conn, &config).unwrap().is_some());
+        assert_eq!(list_backups(&config).unwrap().len(), 2);
+
+        // Las copias manuales o al desbloquear no esperan
+        create_backup(&conn, &config).unwrap();
+        assert_eq!(list_backups(&config).unwrap().len(), 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn auto_backup_respects_auto_save() {
+        let (conn, mut config, dir) = temp_vault();
+        config.auto_save = false;
+        assert!(auto_backup(&conn, &config).unwrap().is_none());
+        assert!(list_backups(&config).unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn vaults_sharing_the_backup_dir_keep_their_own_backups() {
+        let (conn, mut config, dir) = temp_vault();
+        config.backup_generations = 2;
+        let otra = config.clone().with_db_path(&dir.join("vault-old.db").to_string_lossy());
+        for _ in 0..3 {
+            create_backup(&conn, &config).unwrap();
+            create_backup(&conn, &otra).unwrap();
+        }
+        // Un archivo con el prefijo pero sin fecha no es una copia
+        std::fs::write(backup_dir(&config).join("vault-notas.db"), "").unwrap();
+
+        let propias = list_backups(&config).unwrap();
+        let ajenas = list_backups(&otra).unwrap();
+        assert_eq!(propias.len(), 2);
+        assert_eq!(ajenas.len(), 2);
+        let nombre = |c: &BackupInfo| c.ruta.file_name().unwrap().to_string_lossy().to_string();
+        assert!(propias.iter().all(|c| !nombre(c).starts_with("vault-old-")));
+        assert!(ajenas.iter().all(|c| nombre(c).starts_with("vault-old-")));
+        assert!(propias[0].fecha > propias[1].fecha);
+        assert!(backup_dir(&config).join("vault-notas.db").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+// Órdenes de vecla-cli que hablan con el agente de desbloqueo
+use clap::Subcommand;
+use serde_json::json;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use vecla::agent::{self, Request};
+use vecla::config::Config;
+
+#[derive(Subcommand)]
+pub enum AgentCommand {
+    /// Arranca el agente en segundo plano
+    Start {
+        /// No pasa a segundo plano (para systemd o depuración)
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Detiene el agente y olvida todas las claves
+    Stop,
+    /// Muestra las bóvedas desbloqueadas y cuándo se bloquearán
+    Status,
+}
+
+pub fn run(comando: &AgentCommand, json: bool) -> Result<(), String> {
+    match comando {
+        AgentCommand::Start { foreground: true } => agent::serve(),
+        AgentCommand::Start { foreground: false } => {
+            if agent::is_running() {
+                return Err(format!("Ya hay un agente escuchando en {}", agent::socket_path().display()));
+            }
+            start_background()?;
+            eprintln!("Agente en marcha en {}", agent::socket_path().display());
+            Ok(())
+        }
+        AgentCommand::Stop => {
+            agent::request(&Request::Stop)?;
+            eprintln!("Agente detenido");
+            Ok(())
+        }
+        AgentCommand::Status => {
+            let respuesta = agent::request(&Request::Status)?;
+            if json {
+                let vaults: Vec<_> = respuesta
+                    .vaults
+                    .iter()
+                    .map(|v| json!({ "vault": v.vault, "expira_en": v.expira_en }))
+                    .collect();
+                println!("{}", json!({ "socket": agent::socket_path(), "vaults": vaults }));
+            } else {
+                println!("Agente en marcha en {}", agent::socket_path().display());
+                if respuesta.vaults.is_empty() {
+                    println!("No hay bóvedas desbloqueadas");
+                }
+                for v in &respuesta.vaults {
+                    println!("{}  (se bloquea en {} s sin uso)", v.vault, v.expira_en);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+// Lanza "vecla-cli agent start --foreground" desacoplado de la terminal y espera a que escuche
+fn start_background() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    Command::new(exe)
+        .args(["agent", "start", "--foreground"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("No se pudo arrancar el agente: {}", e))?;
+    let inicio = Instant::now();
+    while inicio.elapsed() < Duration::from_secs(3) {
+        if agent::is_running() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Err("El agente no ha respondido a tiempo".to_string())
+}
+
+// Guarda en el agente la clave ya comprobada, arrancándolo si hace falta
+pub fn unlock(config: &Config, master_key: &str, timeout: Option<u64>) -> Result<(), String> {
+    if !agent::is_running() {
+        start_background()?;
+    }
+    let timeout = timeout.unwrap_or(config.agent_timeout);
+    agent::request(&Request::Unlock {
+        vault: config.get_db_path().to_string_lossy().to_string(),
+        key: master_key.to_string(),
+        timeout,
+    })?;
+    eprintln!("Bóveda desbloqueada durante {} s sin uso", timeout);
+    Ok(())
+}
+
+pub fn lock(config: &Config, todas: bool) -> Result<(), String> {
+    let vault = (!todas).then(|| config.get_db_path().to_string_lossy().to_string());
+    agent::request(&Request::Lock { vault })?;
+    eprintln!("{}", if todas { "Todas las bóvedas bloqueadas" } else { "Bóveda bloqueada" });
+    Ok(())
+}
+
+pub fn cached_key(config: &Config) -> Option<String> {
+    agent::cached_key(&config.get_db_path())
+}
@@ -0,0 +1,93 @@
+// vecla-cli serve / token: API HTTP local para herramientas propias y sus tokens
+use clap::Subcommand;
+use serde_json::json;
+use vecla::api::{self, Listen};
+use vecla::config::Config;
+use vecla::db::{list_tokens, open_db};
+use crate::unlock;
+
+#[derive(Subcommand)]
+pub enum TokenCommand {
+    /// Crea un token y lo muestra (solo esta vez)
+    Create {
+        nombre: String,
+        /// Solo permite leer entradas
+        #[arg(long)]
+        read_only: bool,
+        /// Limita el token a esta carpeta y sus subcarpetas; se puede repetir
+        #[arg(long = "folder", value_name = "CARPETA")]
+        carpetas: Vec<String>,
+    },
+    /// Lista los tokens
+    List,
+    /// Revoca un token (nombre o id)
+    Revoke { token: String },
+}
+
+// vecla-cli serve [--listen 127.0.0.1:8787 | --socket <ruta>]
+pub fn serve(config: &Config, direccion: &Listen) -> Result<(), String> {
+    let (conn, master_key) = unlock(config, false)?;
+    match direccion {
+        Listen::Tcp(listen) => eprintln!("API escuchando en http://{}", listen),
+        #[cfg(unix)]
+        Listen::Unix(ruta) => eprintln!("API escuchando en {}", ruta.display()),
+    }
+    api::serve(&conn, &master_key, config, direccion)
+}
+
+// vecla-cli token create <nombre> [--read-only] [--folder <carpeta>]... | list | revoke <token>
+pub fn run(comando: &TokenCommand, config: &Config, json: bool) -> Result<(), String> {
+    match comando {
+        TokenCommand::Create { nombre, read_only, carpetas } => {
+            if config.readonly {
+                return Err("No se pueden crear tokens en modo solo lectura".to_string());
+            }
+            let (conn, _) = unlock(config, true)?;
+            let (token, valor) = api::create_token(&conn, nombre, *read_only, carpetas)?;
+            if json {
+                println!("{}", json!({ "id": token.id, "nombre": token.nombre, "token": valor }));
+            } else {
+                eprintln!("Token \"{}\" creado ({}). Guárdalo ahora: no se volverá a mostrar.", token.nombre, token.id);
+                println!("{}", valor);
+            }
+        }
+        TokenCommand::List => {
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let tokens = list_tokens(&conn).map_err(|e| e.to_string())?;
+            if json {
+                let lista: Vec<_> = tokens
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "id": t.id,
+                            "nombre": t.nombre,
+                            "solo_lectura": t.solo_lectura,
+                            "carpetas": t.carpetas,
+                            "creado": t.creado,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&lista).map_err(|e| e.to_string())?);
+                return Ok(());
+            }
+            if tokens.is_empty() {
+                println!("No hay tokens");
+            }
+            for t in &tokens {
+                let id: String = t.id.chars().take(8).collect();
+                let acceso = if t.solo_lectura { "lectura" } else { "lectura y escritura" };
+                let carpetas = if t.carpetas.is_empty() { "todas las carpetas".to_string() } else { t.carpetas.clone() };
+                println!("{}  {:<20}  {:<20}  {}  (creado {})", id, t.nombre, acceso, carpetas, t.creado);
+            }
+        }
+        TokenCommand::Revoke { token } => {
+            if config.readonly {
+                return Err("No se pueden revocar tokens en modo solo lectura".to_string());
+            }
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let revocado = api::revoke_token(&conn, token)?;
+            println!("Token \"{}\" revocado", revocado.nombre);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// vecla-cli audit: informe de salud de la bóveda
+use vecla::audit::{audit, AuditReport, EntryRef};
+use vecla::config::Config;
+use vecla::db::list_entries;
+use crate::unlock;
+
+// vecla-cli audit [--json] [--max-age <días>]
+pub fn run(config: &Config, max_age: Option<u64>, json: bool) -> Result<(), String> {
+    let (conn, master_key) = unlock(config, false)?;
+    let entradas = list_entries(&conn).map_err(|e| e.to_string())?;
+    let informe = audit(&entradas, &master_key, max_age.unwrap_or(config.password_max_age));
+    if json {
+        println!("{}", serde_json::to_string_pretty(&informe).map_err(|e| e.to_string())?);
+    } else {
+        print_audit(&informe);
+    }
+    Ok(())
+}
+
+fn print_audit(informe: &AuditReport) {
+    let lista = |grupo: &[EntryRef]| grupo.iter().map(|e| e.label()).collect::<Vec<_>>().join(", ");
+    println!("Entradas analizadas: {}", informe.total);
+    if informe.issues() == 0 {
+        println!("No se ha encontrado ningún problema");
+        return;
+    }
+    if !informe.debiles.is_empty() {
+        println!("\nContraseñas débiles ({}):", informe.debiles.len());
+        for d in &informe.debiles {
+            let aviso = d.aviso.map(|a| format!(". {}", a)).unwrap_or_default();
+            println!("  {}: {}, se adivinaría en {}{}", d.entrada.label(), d.nivel.to_lowercase(), d.tiempo, aviso);
+        }
+    }
+    if !informe.reutilizadas.is_empty() {
+        println!("\nContraseñas reutilizadas ({} grupos):", informe.reutilizadas.len());
+        for grupo in &informe.reutilizadas {
+            println!("  {}", lista(grupo));
+        }
+    }
+    if !informe.antiguas.is_empty() {
+        println!("\nContraseñas sin cambiar en más de {} días ({}):", informe.dias_maximos, informe.antiguas.len());
+        for a in &informe.antiguas {
+            println!("  {}: {} días", a.entrada.label(), a.dias);
+        }
+    }
+    if !informe.sin_url.is_empty() {
+        println!("\nEntradas sin URL ({}):", informe.sin_url.len());
+        println!("  {}", lista(&informe.sin_url));
+    }
+    if !informe.duplicadas.is_empty() {
+        println!("\nEntradas duplicadas ({} grupos):", informe.duplicadas.len());
+        for grupo in &informe.duplicadas {
+            println!("  {}", lista(grupo));
+        }
+    }
+    if !informe.ilegibles.is_empty() {
+        println!("\nEntradas que no se pueden descifrar con esta clave ({}):", informe.ilegibles.len());
+        println!("  {}", lista(&informe.ilegibles));
+    }
+}
@@ -0,0 +1,273 @@
+// Ayudante de credenciales de git (git credential-<nombre> get|store|erase). Git envía por
+// la entrada estándar líneas clave=valor (protocol, host, path, username, password) hasta
+// una línea vacía; en "get" se responde con username y password.
+//
+// Configuración: git config --global credential.helper "!vecla-cli git-credential"
+use chrono::Utc;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::io::BufRead;
+use vecla::config::Config;
+use vecla::crypto::{decrypt, encrypt};
+use vecla::db::{PasswordEntry, delete_entry, insert_entry, list_entries, update_entry};
+use crate::{after_write, unlock};
+
+// Etiqueta de las entradas creadas por el ayudante; solo esas se modifican o borran
+const ETIQUETA: &str = "git";
+
+// Esquema, servidor (con puerto) y ruta de una URL; las partes que falten quedan vacías
+pub fn url_parts(url: &str) -> (String, String, String) {
+    let url = url.trim();
+    let (esquema, resto) = match url.split_once("://") {
+        Some((esquema, resto)) => (esquema.to_lowercase(), resto),
+        None => (String::new(), url),
+    };
+    let resto = resto.split(['?', '#']).next().unwrap_or("");
+    let (servidor, ruta) = resto.split_once('/').unwrap_or((resto, ""));
+    // Quita el usuario si la URL lo lleva (https://usuario@servidor)
+    let servidor = servidor.rsplit('@').next().unwrap_or(servidor);
+    (esquema, servidor.to_lowercase(), ruta.trim_matches('/').to_string())
+}
+
+struct Peticion {
+    protocolo: String,
+    servidor: String,
+    ruta: String,
+    usuario: Option<String>,
+    contrasena: Option<String>,
+}
+
+fn read_request() -> Result<Peticion, String> {
+    let mut atributos = HashMap::new();
+    for linea in std::io::stdin().lock().lines() {
+        let linea = linea.map_err(|e| e.to_string())?;
+        if linea.is_empty() {
+            break;
+        }
+        if let Some((clave, valor)) = linea.split_once('=') {
+            atributos.insert(clave.to_string(), valor.to_string());
+        }
+    }
+    // "url" resume las demás si git la envía
+    let (mut protocolo, mut servidor, mut ruta) = atributos.get("url").map(|u| url_parts(u)).unwrap_or_default();
+    if let Some(p) = atributos.get("protocol") {
+        protocolo = p.to_lowercase();
+    }
+    if let Some(h) = atributos.get("host") {
+        servidor = h.to_lowercase();
+    }
+    if let Some(p) = atributos.get("path") {
+        ruta = p.trim_matches('/').to_string();
+    }
+    if servidor.is_empty() {
+        return Err("git no ha indicado el servidor (host)".to_string());
+    }
+    let no_vacio = |clave: &str| atributos.get(clave).filter(|v| !v.is_empty()).cloned();
+    Ok(Peticion { protocolo, servidor, ruta, usuario: no_vacio("username"), contrasena: no_vacio("password") })
+}
+
+// Entradas cuya URL corresponde a la petición, de la más a la menos específica
+fn matching_entries(conn: &Connection, peticion: &Peticion) -> Result<Vec<PasswordEntry>, String> {
+    let mut encontradas: Vec<(bool, PasswordEntry)> = list_entries(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|e| {
+            let (esquema, servidor, ruta) = url_parts(&e.url);
+            let coincide = servidor == peticion.servidor
+                // Una URL sin esquema ("github.com") solo vale para https: nunca se envía
+                // por http ni por otro protocolo sin cifrar
+                && (esquema == peticion.protocolo || (esquema.is_empty() && peticion.protocolo == "https"))
+                && (ruta.is_empty() || peticion.ruta.is_empty() || ruta == peticion.ruta)
+                && peticion.usuario.as_ref().is_none_or(|u| *u == e.usuario);
+            coincide.then(|| (!ruta.is_empty() && ruta == peticion.ruta, e))
+        })
+        .collect();
+    // Primero las que coinciden también en la ruta, luego las modificadas más recientemente
+    encontradas.sort_by(|(ruta_a, a), (ruta_b, b)| ruta_b.cmp(ruta_a).then(b.modificado.cmp(&a.modificado)));
+    Ok(encontradas.into_iter().map(|(_, e)| e).collect())
+}
+
+// Las acciones desconocidas se ignoran, como pide el protocolo de git
+pub fn run(accion: &str, config: &Config) -> Result<(), String> {
+    if !matches!(accion, "get" | "store" | "erase") {
+        return Ok(());
+    }
+    let peticion = read_request()?;
+    match accion {
+        "get" => {
+            let (conn, master_key) = unlock(config, false)?;
+            // Sin coincidencias no se responde nada y git pregunta al usuario
+            if let Some(entrada) = matching_entries(&conn, &peticion)?.into_iter().next() {
+                println!("username={}", entrada.usuario);
+                println!("password={}", decrypt(&master_key, &entrada.con)?);
+            }
+        }
+        "store" => {
+            let (conn, master_key) = unlock(config, true)?;
+            if store(&conn, &master_key, &peticion)? {
+                after_write(&conn, config)?;
+            }
+        }
+        _ => {
+            let (conn, master_key) = unlock(config, true)?;
+            if erase(&conn, &master_key, &peticion)? {
+                after_write(&conn, config)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Guarda la credencial que git ha usado con éxito. Solo se actualizan entradas creadas por
+// el ayudante: una entrada hecha a mano para el mismo servidor (p. ej. el acceso web) no
+// se toca y se crea otra con la etiqueta. Devuelve si ha cambiado algo.
+fn store(conn: &Connection, master_key: &str, peticion: &Peticion) -> Result<bool, String> {
+    let (Some(usuario), Some(contrasena)) = (&peticion.usuario, &peticion.contrasena) else {
+        return Ok(false);
+    };
+    let propia = matching_entries(conn, peticion)?.into_iter().find(|e| e.has_tag(ETIQUETA));
+    match propia {
+        Some(mut entrada) => {
+            if decrypt(master_key, &entrada.con).ok().as_ref() == Some(contrasena) {
+                return Ok(false);
+            }
+            entrada.con = encrypt(master_key, contrasena)?;
+            entrada.modificado = Utc::now().to_rfc3339();
+            update_entry(conn, &entrada).map_err(|e| e.to_string())?;
+        }
+        None => {
+            let mut url = format!("{}://{}", peticion.protocolo, peticion.servidor);
+            if !peticion.ruta.is_empty() {
+                url = format!("{}/{}", url, peticion.ruta);
+            }
+            let enc_con = encrypt(master_key, contrasena)?;
+            let mut nueva = PasswordEntry::new(&peticion.servidor, &url, usuario, "", &enc_con, "");
+            nueva.etiquetas = ETIQUETA.to_string();
+            insert_entry(conn, &nueva).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(true)
+}
+
+// Git borra las credenciales que el servidor rechaza. Solo se elimina una entrada creada por
+// el ayudante y cuya contraseña sea la rechazada, para no perder una ya actualizada ni una
+// hecha a mano. Devuelve si se ha borrado alguna.
+fn erase(conn: &Connection, master_key: &str, peticion: &Peticion) -> Result<bool, String> {
+    let Some(contrasena) = &peticion.contrasena else {
+        return Ok(false);
+    };
+    let mut borradas = 0;
+    for entrada in matching_entries(conn, peticion)?.into_iter().filter(|e| e.has_tag(ETIQUETA)) {
+        if decrypt(master_key, &entrada.con).ok().as_ref() == Some(contrasena) {
+            delete_entry(conn, &entrada.id).map_err(|e| e.to_string())?;
+            borradas += 1;
+        }
+    }
+    Ok(borradas > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vault;
+
+    const CLAVE: &str = "maestra";
+
+    fn peticion(protocolo: &str, servidor: &str, ruta: &str, usuario: Option<&str>, contrasena: Option<&str>) -> Peticion {
+        Peticion {
+            protocolo: protocolo.to_string(),
+            servidor: servidor.to_string(),
+            ruta: ruta.to_string(),
+            usuario: usuario.map(str::to_string),
+            contrasena: contrasena.map(str::to_string),
+        }
+    }
+
+    fn add(conn: &Connection, url: &str, usuario: &str, con: &str, etiquetas: &str) -> PasswordEntry {
+        let mut entrada = PasswordEntry::new(url, url, usuario, "", &encrypt(CLAVE, con).unwrap(), "");
+        entrada.etiquetas = etiquetas.to_string();
+        insert_entry(conn, &entrada).unwrap();
+        entrada
+    }
+
+    fn urls(entradas: &[PasswordEntry]) -> Vec<&str> {
+        entradas.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_urls() {
+        let texto = |a: &str, b: &str, c: &str| (a.to_string(), b.to_string(), c.to_string());
+        assert_eq!(url_parts("https://GitHub.com/ana/repo.git/"), texto("https", "github.com", "ana/repo.git"));
+        assert_eq!(url_parts("HTTP://ana@git.example.com:8080/x?y=1#z"), texto("http", "git.example.com:8080", "x"));
+        assert_eq!(url_parts("github.com"), texto("", "github.com", ""));
+        assert_eq!(url_parts("  github.com/ana "), texto("", "github.com", "ana"));
+        assert_eq!(url_parts(""), texto("", "", ""));
+    }
+
+    #[test]
+    fn scheme_less_urls_only_match_https() {
+        let conn = test_vault();
+        add(&conn, "github.com", "ana", "a", "");
+        add(&conn, "http://github.com", "ana", "b", "");
+        let https = matching_entries(&conn, &peticion("https", "github.com", "", None, None)).unwrap();
+        assert_eq!(urls(&https), ["github.com"]);
+        let http = matching_entries(&conn, &peticion("http", "github.com", "", None, None)).unwrap();
+        assert_eq!(urls(&http), ["http://github.com"]);
+        assert!(matching_entries(&conn, &peticion("ssh", "github.com", "", None, None)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn matches_path_and_username() {
+        let conn = test_vault();
+        add(&conn, "https://github.com", "ana", "a", "");
+        add(&conn, "https://github.com/ana/repo.git", "ana", "b", "");
+        add(&conn, "https://github.com/otra/repo.git", "ana", "c", "");
+        add(&conn, "https://gitlab.com", "ana", "d", "");
+
+        // La que coincide en la ruta va primero; la de otra ruta no vale
+        let con_ruta = matching_entries(&conn, &peticion("https", "github.com", "ana/repo.git", None, None)).unwrap();
+        assert_eq!(urls(&con_ruta), ["https://github.com/ana/repo.git", "https://github.com"]);
+        // Sin ruta en la petición valen todas las del servidor
+        let sin_ruta = matching_entries(&conn, &peticion("https", "github.com", "", None, None)).unwrap();
+        assert_eq!(sin_ruta.len(), 3);
+        // Otro usuario no coincide
+        assert!(matching_entries(&conn, &peticion("https", "github.com", "", Some("bob"), None)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_never_touches_manual_entries() {
+        let conn = test_vault();
+        let manual = add(&conn, "https://github.com", "ana", "web", "");
+        let git = peticion("https", "github.com", "", Some("ana"), Some("token-1"));
+        assert!(store(&conn, CLAVE, &git).unwrap());
+        let entradas = list_entries(&conn).unwrap();
+        assert_eq!(entradas.len(), 2);
+        let guardada = entradas.iter().find(|e| e.id == manual.id).unwrap();
+        assert_eq!(decrypt(CLAVE, &guardada.con).unwrap(), "web");
+
+        // La siguiente vez se actualiza la entrada del ayudante
+        let nueva = peticion("https", "github.com", "", Some("ana"), Some("token-2"));
+        assert!(store(&conn, CLAVE, &nueva).unwrap());
+        assert!(!store(&conn, CLAVE, &nueva).unwrap());
+        let entradas = list_entries(&conn).unwrap();
+        assert_eq!(entradas.len(), 2);
+        let propia = entradas.iter().find(|e| e.has_tag(ETIQUETA)).unwrap();
+        assert_eq!(decrypt(CLAVE, &propia.con).unwrap(), "token-2");
+        assert_eq!(decrypt(CLAVE, &entradas.iter().find(|e| e.id == manual.id).unwrap().con).unwrap(), "web");
+    }
+
+    #[test]
+    fn erase_only_removes_rejected_helper_entries() {
+        let conn = test_vault();
+        let manual = add(&conn, "https://github.com", "ana", "web", "");
+        add(&conn, "https://github.com", "ana", "token", ETIQUETA);
+        // Git rechaza la contraseña de la entrada manual: no se borra
+        assert!(!erase(&conn, CLAVE, &peticion("https", "github.com", "", Some("ana"), Some("web"))).unwrap());
+        // Una contraseña que ya no es la guardada tampoco borra nada
+        assert!(!erase(&conn, CLAVE, &peticion("https", "github.com", "", Some("ana"), Some("vieja"))).unwrap());
+        assert!(erase(&conn, CLAVE, &peticion("https", "github.com", "", Some("ana"), Some("token"))).unwrap());
+        let quedan = list_entries(&conn).unwrap();
+        assert_eq!(quedan.len(), 1);
+        assert_eq!(quedan[0].id, manual.id);
+    }
+}
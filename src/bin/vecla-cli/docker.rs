@@ -0,0 +1,125 @@
+// Ayudante de credenciales de Docker (docker-credential-vecla get|store|erase|list).
+// Docker envía la URL del registro (o, en store, un JSON con ServerURL, Username y Secret)
+// por la entrada estándar y espera JSON en la salida estándar.
+//
+// Las credenciales se guardan como entradas con la etiqueta "docker" y el ayudante solo
+// ve esas: el resto de entradas del mismo servidor (p. ej. la cuenta web) no se entregan.
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vecla::config::Config;
+use vecla::crypto::{decrypt, encrypt};
+use vecla::db::{PasswordEntry, delete_entry, insert_entry, list_entries, open_db, update_entry};
+use crate::credential::url_parts;
+use crate::{after_write, unlock};
+
+const ETIQUETA: &str = "docker";
+// Docker reconoce este mensaje exacto para saber que no hay credenciales
+const NO_ENCONTRADA: &str = "credentials not found in native keychain";
+
+#[derive(Serialize, Deserialize)]
+struct Credencial {
+    #[serde(rename = "ServerURL")]
+    servidor: String,
+    #[serde(rename = "Username")]
+    usuario: String,
+    #[serde(rename = "Secret")]
+    secreto: String,
+}
+
+fn read_stdin() -> Result<String, String> {
+    let texto = std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?;
+    Ok(texto.trim().to_string())
+}
+
+// Servidor del registro sin esquema ni ruta: "https://index.docker.io/v1/" -> "index.docker.io"
+fn registry_host(url: &str) -> String {
+    url_parts(url).1
+}
+
+fn registry_entries(conn: &Connection, servidor: &str) -> Result<Vec<PasswordEntry>, String> {
+    let host = registry_host(servidor);
+    if host.is_empty() {
+        return Err("Falta la URL del registro".to_string());
+    }
+    let mut entradas: Vec<PasswordEntry> = list_entries(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| e.has_tag(ETIQUETA) && registry_host(&e.url) == host)
+        .collect();
+    // La modificada más recientemente primero
+    entradas.sort_by(|a, b| b.modificado.cmp(&a.modificado));
+    Ok(entradas)
+}
+
+pub fn run(accion: &str, config: &Config) -> Result<(), String> {
+    match accion {
+        "get" => {
+            let servidor = read_stdin()?;
+            let (conn, master_key) = unlock(config, false)?;
+            let Some(entrada) = registry_entries(&conn, &servidor)?.into_iter().next() else {
+                println!("{}", NO_ENCONTRADA);
+                std::process::exit(1);
+            };
+            let credencial = Credencial {
+                servidor,
+                usuario: entrada.usuario.clone(),
+                secreto: decrypt(&master_key, &entrada.con)?,
+            };
+            println!("{}", serde_json::to_string(&credencial).map_err(|e| e.to_string())?);
+        }
+        "store" => {
+            let credencial: Credencial = serde_json::from_str(&read_stdin()?)
+                .map_err(|e| format!("Credencial de Docker inválida: {}", e))?;
+            let (conn, master_key) = unlock(config, true)?;
+            let existente = registry_entries(&conn, &credencial.servidor)?.into_iter().next();
+            let enc_con = encrypt(&master_key, &credencial.secreto)?;
+            match existente {
+                Some(mut entrada) => {
+                    entrada.usuario = credencial.usuario;
+                    entrada.con = enc_con;
+                    entrada.modificado = Utc::now().to_rfc3339();
+                    update_entry(&conn, &entrada).map_err(|e| e.to_string())?;
+                }
+                None => {
+                    let host = registry_host(&credencial.servidor);
+                    let mut nueva = PasswordEntry::new(&host, &credencial.servidor, &credencial.usuario, "", &enc_con, "");
+                    nueva.etiquetas = ETIQUETA.to_string();
+                    insert_entry(&conn, &nueva).map_err(|e| e.to_string())?;
+                }
+            }
+            after_write(&conn, config)?;
+        }
+        "erase" => {
+            // Solo se borran las entradas creadas por el ayudante (docker logout)
+            let servidor = read_stdin()?;
+            if config.readonly {
+                return Err("La bóveda está abierta en solo lectura".to_string());
+            }
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let borrar = registry_entries(&conn, &servidor)?;
+            if borrar.is_empty() {
+                println!("{}", NO_ENCONTRADA);
+                std::process::exit(1);
+            }
+            for entrada in &borrar {
+                delete_entry(&conn, &entrada.id).map_err(|e| e.to_string())?;
+            }
+            after_write(&conn, config)?;
+        }
+        "list" => {
+            // No necesita la clave maestra: solo muestra servidores y usuarios
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let lista: BTreeMap<String, String> = list_entries(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|e| e.has_tag(ETIQUETA))
+                .map(|e| (e.url, e.usuario))
+                .collect();
+            println!("{}", serde_json::to_string(&lista).map_err(|e| e.to_string())?);
+        }
+        otra => return Err(format!("Acción desconocida: {}", otra)),
+    }
+    Ok(())
+}
@@ -0,0 +1,759 @@
+#[cfg(unix)]
+mod agent;
+mod api;
+mod audit;
+mod credential;
+mod docker;
+#[cfg(unix)]
+mod native;
+mod run;
+#[cfg(unix)]
+mod ssh;
+
+use chrono::Utc;
+use clap::{Args, Parser, Subcommand};
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use vecla::config::Config;
+use vecla::crypto::{decrypt, encrypt};
+use vecla::generator::PasswordPolicy;
+use vecla::passphrase::{PassphraseOptions, Wordlist};
+use vecla::strength;
+use vecla::export::PLAINTEXT_FIELDS;
+use vecla::db::{
+    PasswordEntry, delete_entry, find_entries, insert_entry, list_entries, list_fields, open_db, search_entries,
+    update_entry,
+};
+
+#[derive(Parser)]
+#[command(name = "vecla-cli", version, about = "Vecla desde la terminal, sin interfaz gráfica")]
+struct Cli {
+    /// Bóveda a abrir (por defecto, la indicada en la configuración)
+    #[arg(long, global = true, env = "VECLA_VAULT", value_name = "RUTA")]
+    vault: Option<String>,
+
+    /// Archivo de clave de la bóveda (por defecto, key_file de la configuración)
+    #[arg(long, global = true, env = "VECLA_KEY_FILE", value_name = "RUTA")]
+    key_file: Option<String>,
+
+    /// Archivo de configuración a usar
+    #[arg(long, global = true, env = "VECLA_CONFIG", value_name = "RUTA")]
+    config: Option<PathBuf>,
+
+    /// Abre la bóveda en solo lectura
+    #[arg(long, global = true, env = "VECLA_READONLY", value_parser = clap::builder::FalseyValueParser::new())]
+    readonly: bool,
+
+    /// Salida en JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    comando: Comando,
+}
+
+#[derive(Subcommand)]
+enum Comando {
+    /// Añade una entrada
+    Add {
+        app: String,
+        #[command(flatten)]
+        campos: CamposEntrada,
+        #[command(flatten)]
+        con: OrigenContrasena,
+    },
+    /// Modifica una entrada; la versión anterior se guarda en el historial
+    Edit {
+        /// Id, prefijo del id o nombre de la app
+        entrada: String,
+        /// Nuevo nombre de la app
+        #[arg(long)]
+        app: Option<String>,
+        #[command(flatten)]
+        campos: CamposEntrada,
+        /// Pide la nueva contraseña
+        #[arg(long, conflicts_with_all = ["generate", "password_stdin"])]
+        password: bool,
+        #[command(flatten)]
+        con: OrigenContrasena,
+    },
+    /// Muestra una entrada o solo los campos pedidos
+    Get {
+        /// Id, prefijo del id o nombre de la app
+        entrada: String,
+        /// Campo a mostrar (app, url, usuario, mail, con, notas, carpeta, etiquetas,
+        /// id, fecha, modificado o un campo personalizado); se puede repetir
+        #[arg(short = 'f', long = "field", value_name = "CAMPO")]
+        campos: Vec<String>,
+    },
+    /// Lista las entradas (sin contraseñas)
+    List {
+        /// Solo esta carpeta y sus subcarpetas
+        #[arg(long)]
+        folder: Option<String>,
+        /// Solo las entradas con esta etiqueta
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Busca por app, usuario o mail
+    Search { texto: String },
+    /// Elimina una entrada
+    Rm {
+        /// Id, prefijo del id o nombre de la app
+        entrada: String,
+        /// No pide confirmación
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Ejecuta un comando con secretos de la bóveda en su entorno
+    Run {
+        /// Variable a definir, p. ej. DB_PASSWORD=entry:prod-db/con; se puede repetir
+        #[arg(short = 'e', long = "env", value_name = "NOMBRE=entry:ENTRADA/CAMPO", required = true)]
+        variables: Vec<String>,
+        /// Comando y argumentos, tras --
+        #[arg(last = true, required = true)]
+        comando: Vec<String>,
+    },
+    /// Escribe en la salida estándar una plantilla con las referencias {{ entry:ENTRADA/CAMPO }} resueltas
+    Render {
+        /// Plantilla a procesar ("-" para la entrada estándar)
+        plantilla: PathBuf,
+    },
+    /// Ayudante de credenciales para git (credential.helper = "!vecla-cli git-credential")
+    GitCredential {
+        /// Acción que pide git: get, store o erase
+        accion: String,
+    },
+    /// Ayudante de credenciales para Docker; también se usa como docker-credential-vecla
+    DockerCredential {
+        /// Acción que pide Docker: get, store, erase o list
+        accion: String,
+    },
+    /// Genera una contraseña sin guardarla
+    Generate {
+        #[command(flatten)]
+        politica: PoliticaArgs,
+        /// Guarda la política resultante como perfil con este nombre
+        #[arg(long, value_name = "NOMBRE")]
+        save_profile: Option<String>,
+        /// Borra el perfil guardado con este nombre
+        #[arg(long, value_name = "NOMBRE", exclusive = true)]
+        delete_profile: Option<String>,
+        /// Lista los perfiles guardados
+        #[arg(long, exclusive = true)]
+        list_profiles: bool,
+    },
+    /// Genera una frase de contraseña con palabras al azar (estilo Diceware)
+    Passphrase {
+        /// Número de palabras
+        #[arg(short, long, default_value_t = 6)]
+        words: usize,
+        /// Lista incluida: es o en (por defecto, passphrase_wordlist de la configuración o es)
+        #[arg(long, conflicts_with = "wordlist")]
+        lang: Option<String>,
+        /// Lista propia, una palabra por línea o en formato Diceware (p. ej. la de la EFF)
+        #[arg(long, value_name = "RUTA")]
+        wordlist: Option<PathBuf>,
+        /// Separador entre palabras
+        #[arg(short, long, default_value = "-", allow_hyphen_values = true)]
+        separator: String,
+        /// Primera letra de cada palabra en mayúscula
+        #[arg(short, long)]
+        capitalize: bool,
+        /// Añade un dígito al azar
+        #[arg(short, long)]
+        digit: bool,
+    },
+    /// Pide la clave maestra y la deja en el agente para las órdenes siguientes
+    #[cfg(unix)]
+    Unlock {
+        /// Segundos sin uso tras los que se olvida (por defecto, agent_timeout de la configuración)
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// Hace que el agente olvide la clave de la bóveda
+    #[cfg(unix)]
+    Lock {
+        /// Bloquea todas las bóvedas, no solo la actual
+        #[arg(long)]
+        all: bool,
+    },
+    /// Informe de salud: contraseñas débiles, reutilizadas o antiguas, entradas sin URL y duplicadas
+    Audit {
+        /// Días a partir de los que una contraseña se considera antigua, 0 = no comprobarlo
+        /// (por defecto, password_max_age de la configuración)
+        #[arg(long, value_name = "DIAS")]
+        max_age: Option<u64>,
+    },
+    /// Sirve la API HTTP local para herramientas propias (ver vecla-cli token)
+    Serve {
+        /// Dirección de loopback donde escuchar
+        #[arg(long, value_name = "DIRECCIÓN", default_value = vecla::api::DIRECCION_POR_DEFECTO)]
+        listen: std::net::SocketAddr,
+        /// Escucha en este socket Unix en lugar de TCP
+        #[cfg(unix)]
+        #[arg(long, value_name = "RUTA", conflicts_with = "listen")]
+        socket: Option<PathBuf>,
+    },
+    /// Gestiona los tokens de la API local
+    Token {
+        #[command(subcommand)]
+        comando: api::TokenCommand,
+    },
+    /// Gestiona el agente de desbloqueo
+    #[cfg(unix)]
+    Agent {
+        #[command(subcommand)]
+        comando: agent::AgentCommand,
+    },
+    /// Host de mensajería nativa para la extensión del navegador; también se usa como vecla-native-host
+    #[cfg(unix)]
+    NativeHost {
+        /// Argumentos que añade el navegador (origen de la extensión, manifiesto); se ignoran
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, hide = true)]
+        navegador: Vec<String>,
+    },
+    /// Proveedor de Secret Service (org.freedesktop.secrets) para aplicaciones con libsecret
+    #[cfg(unix)]
+    SecretService {
+        /// Sustituye al proveedor actual (gnome-keyring, KWallet...)
+        #[arg(long)]
+        replace: bool,
+        /// Empieza con la bóveda bloqueada aunque haya terminal o agente
+        #[arg(long)]
+        locked: bool,
+    },
+    /// Claves SSH guardadas en la bóveda y agente SSH que las sirve
+    #[cfg(unix)]
+    Ssh {
+        #[command(subcommand)]
+        comando: ssh::SshCommand,
+    },
+}
+
+#[derive(Args)]
+struct CamposEntrada {
+    #[arg(long)]
+    url: Option<String>,
+    #[arg(short, long)]
+    usuario: Option<String>,
+    #[arg(long)]
+    mail: Option<String>,
+    #[arg(long)]
+    notas: Option<String>,
+    /// Carpeta, con subcarpetas separadas por "/"
+    #[arg(long)]
+    carpeta: Option<String>,
+    /// Etiquetas separadas por coma
+    #[arg(long)]
+    etiquetas: Option<String>,
+}
+
+// De dónde sale la contraseña: generada, leída de la entrada estándar o pedida por terminal
+#[derive(Args)]
+struct OrigenContrasena {
+    /// Genera una contraseña aleatoria
+    #[arg(short, long, conflicts_with = "password_stdin")]
+    generate: bool,
+    /// Longitud de la contraseña generada
+    #[arg(short, long, requires = "generate")]
+    length: Option<usize>,
+    /// Perfil del generador (password_profiles de la configuración)
+    #[arg(long, value_name = "NOMBRE", requires = "generate")]
+    profile: Option<String>,
+    /// Lee la contraseña de la primera línea de la entrada estándar (para scripts)
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+// Política del generador: un perfil guardado (o la de por defecto) con estos cambios
+#[derive(Args)]
+struct PoliticaArgs {
+    /// Perfil del generador (password_profiles de la configuración)
+    #[arg(long, value_name = "NOMBRE")]
+    profile: Option<String>,
+    /// Longitud (por defecto, la del perfil o default_password_length de la configuración)
+    #[arg(short, long)]
+    length: Option<usize>,
+    /// Sin minúsculas
+    #[arg(long)]
+    no_lowercase: bool,
+    /// Sin mayúsculas
+    #[arg(long)]
+    no_uppercase: bool,
+    /// Sin dígitos
+    #[arg(long)]
+    no_digits: bool,
+    /// Sin símbolos
+    #[arg(long, conflicts_with = "symbols")]
+    no_symbols: bool,
+    /// Símbolos permitidos, p. ej. --symbols '-_.'
+    #[arg(long, value_name = "SIMBOLOS", allow_hyphen_values = true)]
+    symbols: Option<String>,
+    /// Excluye caracteres que se confunden (0/O, l/1/I...)
+    #[arg(long)]
+    exclude_ambiguous: bool,
+    /// No obliga a incluir al menos un carácter de cada tipo
+    #[arg(long)]
+    no_require_each: bool,
+}
+
+impl PoliticaArgs {
+    fn policy(&self, config: &Config) -> Result<PasswordPolicy, String> {
+        let mut politica = config.password_policy(self.profile.as_deref())?;
+        if let Some(largo) = self.length {
+            politica.length = largo;
+        }
+        politica.lowercase &= !self.no_lowercase;
+        politica.uppercase &= !self.no_uppercase;
+        politica.digits &= !self.no_digits;
+        politica.symbols &= !self.no_symbols;
+        if let Some(simbolos) = &self.symbols {
+            politica.symbols = true;
+            politica.symbol_set = simbolos.clone();
+        }
+        politica.exclude_ambiguous |= self.exclude_ambiguous;
+        politica.require_each &= !self.no_require_each;
+        Ok(politica)
+    }
+}
+
+fn main() {
+    // Con un enlace llamado docker-credential-vecla, Docker pasa solo la acción, y el
+    // navegador arranca vecla-native-host con sus propios argumentos
+    let programa = std::env::args_os()
+        .next()
+        .and_then(|a| PathBuf::from(a).file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default();
+    let orden = if programa.starts_with("docker-credential-") {
+        Some("docker-credential")
+    } else if programa == "vecla-native-host" {
+        Some("native-host")
+    } else {
+        None
+    };
+    let cli = match orden {
+        Some(orden) => {
+            let argumentos = std::env::args_os().skip(1);
+            Cli::parse_from(["vecla-cli".into(), orden.into()].into_iter().chain(argumentos))
+        }
+        None => Cli::parse(),
+    };
+
+    let cargada = Config::load_with_args(cli.config.as_deref(), cli.vault.as_deref(), cli.key_file.as_deref(), cli.readonly)
+        .and_then(|(config, avisos)| config.check_legacy_vault().map(|_| (config, avisos)));
+    let config = match cargada {
+        Ok((config, avisos)) => {
+            for aviso in avisos {
+                eprintln!("Aviso: {}", aviso);
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = run(&cli, &config) {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+}
+
+fn run(cli: &Cli, config: &Config) -> Result<(), String> {
+    match &cli.comando {
+        Comando::Add { app, campos, con } => {
+            let (conn, master_key) = unlock(config, true)?;
+            let contrasena = new_password(con, config)?;
+            let mut entrada = PasswordEntry::new(
+                app,
+                campos.url.as_deref().unwrap_or(""),
+                campos.usuario.as_deref().unwrap_or(""),
+                campos.mail.as_deref().unwrap_or(""),
+                &encrypt(&master_key, &contrasena)?,
+                campos.notas.as_deref().unwrap_or(""),
+            );
+            entrada.carpeta = campos.carpeta.as_deref().map(normalize_folder).unwrap_or_default();
+            entrada.etiquetas = campos.etiquetas.clone().unwrap_or_default();
+            insert_entry(&conn, &entrada).map_err(|e| e.to_string())?;
+            after_write(&conn, config)?;
+            if cli.json {
+                println!("{}", json!({ "id": entrada.id }));
+            } else {
+                println!("{}", entrada.id);
+            }
+        }
+        Comando::Edit { entrada, app, campos, password, con } => {
+            let nueva_con = *password || con.generate || con.password_stdin;
+            let hay_cambios = nueva_con
+                || app.is_some()
+                || campos.url.is_some()
+                || campos.usuario.is_some()
+                || campos.mail.is_some()
+                || campos.notas.is_some()
+                || campos.carpeta.is_some()
+                || campos.etiquetas.is_some();
+            if !hay_cambios {
+                return Err("No se ha indicado ningún cambio".to_string());
+            }
+            let (conn, master_key) = unlock(config, true)?;
+            let mut editada = resolve_entry(&conn, entrada)?;
+            let cambiar = |destino: &mut String, valor: &Option<String>| {
+                if let Some(v) = valor {
+                    *destino = v.clone();
+                }
+            };
+            cambiar(&mut editada.app, app);
+            cambiar(&mut editada.url, &campos.url);
+            cambiar(&mut editada.usuario, &campos.usuario);
+            cambiar(&mut editada.mail, &campos.mail);
+            cambiar(&mut editada.notas, &campos.notas);
+            cambiar(&mut editada.etiquetas, &campos.etiquetas);
+            if let Some(carpeta) = &campos.carpeta {
+                editada.carpeta = normalize_folder(carpeta);
+            }
+            if nueva_con {
+                editada.con = encrypt(&master_key, &new_password(con, config)?)?;
+            }
+            editada.modificado = Utc::now().to_rfc3339();
+            update_entry(&conn, &editada).map_err(|e| e.to_string())?;
+            after_write(&conn, config)?;
+            if cli.json {
+                println!("{}", json!({ "id": editada.id }));
+            } else {
+                eprintln!("Entrada \"{}\" actualizada", editada.app);
+            }
+        }
+        Comando::Get { entrada, campos } => {
+            let (conn, master_key) = unlock(config, false)?;
+            let entrada = resolve_entry(&conn, entrada)?;
+            let nombres: Vec<String> = if campos.is_empty() {
+                let mut todos: Vec<String> = PLAINTEXT_FIELDS.iter().map(|c| c.to_string()).collect();
+                let personalizados = list_fields(&conn, &entrada.id).map_err(|e| e.to_string())?;
+                todos.extend(personalizados.into_iter().map(|c| c.nombre));
+                todos
+            } else {
+                campos.clone()
+            };
+            let mut valores = Vec::new();
+            for nombre in &nombres {
+                valores.push((nombre.clone(), field_value(&conn, &master_key, &entrada, nombre)?));
+            }
+            if cli.json {
+                let objeto: Map<String, Value> = valores.into_iter().map(|(k, v)| (k, Value::String(v))).collect();
+                println!("{}", serde_json::to_string_pretty(&objeto).map_err(|e| e.to_string())?);
+            } else if campos.len() == 1 {
+                // Un solo campo se imprime tal cual, para usarlo en scripts
+                println!("{}", valores[0].1);
+            } else {
+                for (nombre, valor) in valores {
+                    println!("{}: {}", nombre, valor);
+                }
+            }
+        }
+        Comando::List { folder, tag } => {
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let entradas: Vec<PasswordEntry> = list_entries(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|e| folder.as_deref().is_none_or(|f| e.in_folder(f)))
+                .filter(|e| tag.as_deref().is_none_or(|t| e.has_tag(t)))
+                .collect();
+            print_entries(&entradas, cli.json)?;
+        }
+        Comando::Search { texto } => {
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let entradas = search_entries(&conn, texto).map_err(|e| e.to_string())?;
+            print_entries(&entradas, cli.json)?;
+        }
+        Comando::Rm { entrada, yes } => {
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            if config.readonly {
+                return Err("La bóveda está abierta en solo lectura".to_string());
+            }
+            let entrada = resolve_entry(&conn, entrada)?;
+            if !yes && !confirm(&format!("¿Eliminar \"{}\" ({})?", entrada.app, entrada.usuario))? {
+                return Err("Cancelado".to_string());
+            }
+            delete_entry(&conn, &entrada.id).map_err(|e| e.to_string())?;
+            after_write(&conn, config)?;
+            if cli.json {
+                println!("{}", json!({ "id": entrada.id }));
+            } else {
+                eprintln!("Entrada \"{}\" eliminada", entrada.app);
+            }
+        }
+        Comando::Run { variables, comando } => {
+            let (conn, master_key) = unlock(config, false)?;
+            let codigo = run::run(&conn, &master_key, variables, comando)?;
+            std::process::exit(codigo);
+        }
+        Comando::Render { plantilla } => {
+            let texto = if plantilla.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?
+            } else {
+                std::fs::read_to_string(plantilla)
+                    .map_err(|e| format!("No se pudo leer {}: {}", plantilla.display(), e))?
+            };
+            let (conn, master_key) = unlock(config, false)?;
+            print!("{}", run::render(&conn, &master_key, &texto)?);
+        }
+        Comando::GitCredential { accion } => credential::run(accion, config)?,
+        Comando::DockerCredential { accion } => docker::run(accion, config)?,
+        Comando::Generate { politica, save_profile, delete_profile, list_profiles } => {
+            let mut config = config.clone();
+            if *list_profiles {
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&config.password_profiles).map_err(|e| e.to_string())?);
+                } else {
+                    for (nombre, p) in &config.password_profiles {
+                        println!("{:<16}  {} caracteres, ~{:.0} bits", nombre, p.length, p.entropy_bits());
+                    }
+                }
+                return Ok(());
+            }
+            if let Some(nombre) = delete_profile {
+                config.delete_profile(nombre)?;
+                eprintln!("Perfil \"{}\" borrado", nombre);
+                return Ok(());
+            }
+            let politica = politica.policy(&config)?;
+            let contrasena = politica.generate()?;
+            if let Some(nombre) = save_profile {
+                config.save_profile(nombre, &politica)?;
+                eprintln!("Perfil \"{}\" guardado", nombre.trim());
+            }
+            if cli.json {
+                println!("{}", json!({ "con": contrasena, "bits": politica.entropy_bits().round() }));
+            } else {
+                println!("{}", contrasena);
+            }
+        }
+        Comando::Passphrase { words, lang, wordlist, separator, capitalize, digit } => {
+            let lista = match (wordlist, lang) {
+                (Some(ruta), _) => Wordlist::load(ruta)?,
+                (None, Some(idioma)) => Wordlist::bundled(idioma)?,
+                (None, None) => match config.get_passphrase_wordlist_path() {
+                    Some(ruta) => Wordlist::load(&ruta)?,
+                    None => Wordlist::bundled("es")?,
+                },
+            };
+            let opciones = PassphraseOptions {
+                words: *words,
+                separator: separator.clone(),
+                capitalize: *capitalize,
+                add_digit: *digit,
+            };
+            let frase = opciones.generate(&lista)?;
+            let bits = opciones.entropy_bits(&lista);
+            if cli.json {
+                println!("{}", json!({ "con": frase, "bits": bits.round(), "palabras_lista": lista.len() }));
+            } else {
+                println!("{}", frase);
+                // La entropía va a stderr para poder usar la salida en scripts
+                eprintln!("~{:.0} bits ({} palabras de una lista de {})", bits, words, lista.len());
+            }
+        }
+        Comando::Audit { max_age } => audit::run(config, *max_age, cli.json)?,
+        #[cfg(unix)]
+        Comando::Serve { socket: Some(ruta), .. } => api::serve(config, &vecla::api::Listen::Unix(ruta.clone()))?,
+        Comando::Serve { listen, .. } => api::serve(config, &vecla::api::Listen::Tcp(*listen))?,
+        Comando::Token { comando } => api::run(comando, config, cli.json)?,
+        #[cfg(unix)]
+        Comando::Unlock { timeout } => {
+            let conn = open_db(config).map_err(|e| e.to_string())?;
+            let master_key = prompt_master_key(&conn, config)?;
+            agent::unlock(config, &master_key, *timeout)?;
+        }
+        #[cfg(unix)]
+        Comando::Lock { all } => agent::lock(config, *all)?,
+        #[cfg(unix)]
+        Comando::Agent { comando } => agent::run(comando, cli.json)?,
+        #[cfg(unix)]
+        Comando::NativeHost { .. } => native::run(config)?,
+        #[cfg(unix)]
+        Comando::SecretService { replace, locked } => {
+            // Sin terminal ni agente (p. ej. arrancado por D-Bus) empieza bloqueado y la
+            // clave se pide con $VECLA_ASKPASS cuando una aplicación desbloquea
+            let master_key = if *locked {
+                None
+            } else if agent::cached_key(config).is_some() || std::io::stdin().is_terminal() {
+                Some(unlock(config, false)?.1)
+            } else {
+                None
+            };
+            vecla::secret_service::serve(config.clone(), master_key, *replace)?
+        }
+        #[cfg(unix)]
+        Comando::Ssh { comando } => ssh::run(comando, config, cli.json)?,
+    }
+    Ok(())
+}
+
+// Abre la bóveda y obtiene la clave maestra del agente o, si está bloqueada, del usuario
+fn unlock(config: &Config, escritura: bool) -> Result<(Connection, String), String> {
+    if escritura && config.readonly {
+        return Err("La bóveda está abierta en solo lectura".to_string());
+    }
+    let conn = open_db(config).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    if let Some(master_key) = agent::cached_key(config) {
+        if check_master_key(&conn, &master_key)? {
+            return Ok((conn, master_key));
+        }
+    }
+    let master_key = prompt_master_key(&conn, config)?;
+    Ok((conn, master_key))
+}
+
+// Pide la clave maestra, le añade el archivo de clave si lo hay y comprueba que
+// descifra las entradas existentes
+fn prompt_master_key(conn: &Connection, config: &Config) -> Result<String, String> {
+    let contrasena = rpassword::prompt_password("Clave maestra: ")
+        .map_err(|e| format!("No se pudo leer la clave maestra: {}", e))?;
+    let master_key = vecla::crypto::compose_master_key(&contrasena, config.get_key_file_path().as_deref())?;
+    if !check_master_key(conn, &master_key)? {
+        return Err("Clave maestra incorrecta".to_string());
+    }
+    Ok(master_key)
+}
+
+fn check_master_key(conn: &Connection, master_key: &str) -> Result<bool, String> {
+    vecla::db::check_master_key(conn, master_key).map_err(|e| e.to_string())
+}
+
+// Copia de seguridad tras los cambios, igual que en la interfaz gráfica
+fn after_write(conn: &Connection, config: &Config) -> Result<(), String> {
+    vecla::backup::auto_backup(conn, config)?;
+    Ok(())
+}
+
+fn resolve_entry(conn: &Connection, referencia: &str) -> Result<PasswordEntry, String> {
+    let mut encontradas = find_entries(conn, referencia).map_err(|e| e.to_string())?;
+    match encontradas.len() {
+        0 => Err(format!("No hay ninguna entrada \"{}\"", referencia)),
+        1 => Ok(encontradas.remove(0)),
+        _ => {
+            let opciones: Vec<String> = encontradas
+                .iter()
+                .map(|e| format!("  {}  {}  {}", e.id, e.app, e.usuario))
+                .collect();
+            Err(format!("\"{}\" coincide con varias entradas; usa el id:\n{}", referencia, opciones.join("\n")))
+        }
+    }
+}
+
+// Valor descifrado de un campo propio o personalizado
+fn field_value(conn: &Connection, master_key: &str, entrada: &PasswordEntry, campo: &str) -> Result<String, String> {
+    let valor = match campo {
+        "id" => entrada.id.clone(),
+        "app" => entrada.app.clone(),
+        "url" => entrada.url.clone(),
+        "usuario" => entrada.usuario.clone(),
+        "mail" => entrada.mail.clone(),
+        "con" => decrypt(master_key, &entrada.con)?,
+        "notas" => entrada.notas.clone(),
+        "carpeta" => entrada.carpeta.clone(),
+        "etiquetas" => entrada.etiquetas.clone(),
+        "fecha" => entrada.fecha.clone(),
+        "modificado" => entrada.modificado.clone(),
+        otro => {
+            let campos = list_fields(conn, &entrada.id).map_err(|e| e.to_string())?;
+            let personalizado = campos
+                .iter()
+                .find(|c| c.nombre.eq_ignore_ascii_case(otro))
+                .ok_or(format!("La entrada \"{}\" no tiene el campo \"{}\"", entrada.app, otro))?;
+            decrypt(master_key, &personalizado.valor)?
+        }
+    };
+    Ok(valor)
+}
+
+fn entry_json(entrada: &PasswordEntry) -> Value {
+    json!({
+        "id": entrada.id,
+        "app": entrada.app,
+        "url": entrada.url,
+        "usuario": entrada.usuario,
+        "mail": entrada.mail,
+        "notas": entrada.notas,
+        "carpeta": entrada.carpeta,
+        "etiquetas": entrada.etiquetas,
+        "fecha": entrada.fecha,
+        "modificado": entrada.modificado,
+    })
+}
+
+fn print_entries(entradas: &[PasswordEntry], json: bool) -> Result<(), String> {
+    if json {
+        let lista: Vec<Value> = entradas.iter().map(entry_json).collect();
+        println!("{}", serde_json::to_string_pretty(&lista).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    for e in entradas {
+        let id: String = e.id.chars().take(8).collect();
+        println!("{}  {:<24}  {:<24}  {}", id, e.app, e.usuario, e.carpeta);
+    }
+    Ok(())
+}
+
+fn new_password(origen: &OrigenContrasena, config: &Config) -> Result<String, String> {
+    if origen.generate {
+        let mut politica = config.password_policy(origen.profile.as_deref())?;
+        if let Some(largo) = origen.length {
+            politica.length = largo;
+        }
+        return politica.generate();
+    }
+    if origen.password_stdin {
+        let mut linea = String::new();
+        std::io::stdin().lock().read_line(&mut linea).map_err(|e| e.to_string())?;
+        let linea = linea.trim_end_matches(['\r', '\n']).to_string();
+        if linea.is_empty() {
+            return Err("La entrada estándar no contiene ninguna contraseña".to_string());
+        }
+        return Ok(linea);
+    }
+    let primera = rpassword::prompt_password("Contraseña: ").map_err(|e| e.to_string())?;
+    let segunda = rpassword::prompt_password("Repite la contraseña: ").map_err(|e| e.to_string())?;
+    if primera != segunda {
+        return Err("Las contraseñas no coinciden".to_string());
+    }
+    if primera.is_empty() {
+        return Err("La contraseña no puede estar vacía".to_string());
+    }
+    let fortaleza = strength::estimate(&primera);
+    if fortaleza.score < 3 {
+        eprintln!("Aviso: contraseña {} (se adivinaría en {})", fortaleza.label().to_lowercase(), fortaleza.crack_time_label());
+        for consejo in fortaleza.warning.iter().chain(&fortaleza.suggestions) {
+            eprintln!("  - {}", consejo);
+        }
+    }
+    Ok(primera)
+}
+
+fn normalize_folder(carpeta: &str) -> String {
+    carpeta.trim().trim_matches('/').to_string()
+}
+
+fn confirm(pregunta: &str) -> Result<bool, String> {
+    if !std::io::stdin().is_terminal() {
+        return Err("Sin terminal para confirmar; usa --yes".to_string());
+    }
+    eprint!("{} [s/N] ", pregunta);
+    std::io::stderr().flush().map_err(|e| e.to_string())?;
+    let mut respuesta = String::new();
+    std::io::stdin().lock().read_line(&mut respuesta).map_err(|e| e.to_string())?;
+    Ok(matches!(respuesta.trim().to_lowercase().as_str(), "s" | "si" | "sí" | "y" | "yes"))
+}
+
+// Bóveda vacía en memoria para las pruebas de los módulos de vecla-cli
+#[cfg(test)]
+fn test_vault() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    vecla::db::migrate(&conn).unwrap();
+    conn
+}
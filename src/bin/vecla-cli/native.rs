@@ -0,0 +1,278 @@
+// Host de mensajería nativa para extensiones de navegador (Chrome/Firefox). El navegador
+// arranca el programa y le envía mensajes JSON por la entrada estándar, cada uno precedido
+// de su longitud (u32 en el orden de bytes de la máquina); las respuestas van igual por la
+// salida estándar. La salida de error es solo para avisos.
+//
+// Las consultas necesitan la bóveda desbloqueada en el agente (vecla-cli unlock): el host
+// nunca pide la clave maestra. Entregar una contraseña requiere además que el usuario lo
+// apruebe con el programa de $VECLA_ASKPASS o $SSH_ASKPASS.
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::process::Command;
+use vecla::config::Config;
+use vecla::crypto::decrypt;
+use vecla::db::{PasswordEntry, check_master_key, get_entry, list_entries, open_db};
+use crate::agent::cached_key;
+use crate::credential::url_parts;
+
+// Chrome no acepta mensajes del host mayores de 1 MB
+const LARGO_MAXIMO_SALIDA: usize = 1024 * 1024;
+// Ni envía mensajes mayores de 4 GB; aquí basta mucho menos
+const LARGO_MAXIMO_ENTRADA: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+#[serde(tag = "orden", rename_all = "snake_case")]
+enum Request {
+    // Si la bóveda está desbloqueada y hay un programa para aprobar
+    Status,
+    // Entradas que corresponden al origen de la página, sin contraseñas
+    Lookup { origin: String },
+    // Usuario y contraseña de una entrada, si corresponde al origen y el usuario lo aprueba
+    Credentials { origin: String, id: String },
+}
+
+#[derive(Serialize, Default)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unlocked: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    approval: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entries: Option<Vec<EntrySummary>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EntrySummary {
+    id: String,
+    app: String,
+    username: String,
+    url: String,
+}
+
+fn askpass() -> Option<std::ffi::OsString> {
+    std::env::var_os("VECLA_ASKPASS").or_else(|| std::env::var_os("SSH_ASKPASS"))
+}
+
+// Clave del agente, si tiene desbloqueada esta bóveda
+fn session_key(config: &Config, conn: &Connection) -> Result<String, String> {
+    let clave = cached_key(config).ok_or("La bóveda está bloqueada: ejecuta vecla-cli unlock")?;
+    if !check_master_key(conn, &clave).map_err(|e| e.to_string())? {
+        return Err("La clave del agente no corresponde a esta bóveda".to_string());
+    }
+    Ok(clave)
+}
+
+// Pregunta al usuario como ssh-agent -c; sin programa para preguntar se deniega
+fn approve(entrada: &PasswordEntry, origen: &str) -> bool {
+    let Some(programa) = askpass() else {
+        return false;
+    };
+    let mensaje = format!("¿Entregar a {} la contraseña de {} ({})?", origen, entrada.app, entrada.usuario);
+    Command::new(programa)
+        .arg(mensaje)
+        .env("SSH_ASKPASS_PROMPT", "confirm")
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+// Nivel de coincidencia de la URL de la entrada con el origen de la página: 2 si es el
+// mismo servidor, 1 si el origen es un subdominio suyo. Una entrada https o sin esquema
+// ("github.com") solo se entrega a páginas https, como en el ayudante de git.
+fn match_level(url: &str, origen: &str) -> Option<u8> {
+    let (esquema, servidor, _) = url_parts(url);
+    let (esquema_origen, servidor_origen, _) = url_parts(origen);
+    if servidor.is_empty() || servidor_origen.is_empty() {
+        return None;
+    }
+    let esquema_valido = match esquema.as_str() {
+        "" | "https" => esquema_origen == "https",
+        "http" => esquema_origen == "http" || esquema_origen == "https",
+        otro => otro == esquema_origen,
+    };
+    if !esquema_valido {
+        return None;
+    }
+    if servidor == servidor_origen {
+        Some(2)
+    } else if servidor_origen.ends_with(&format!(".{}", servidor)) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn matching_entries(conn: &Connection, origen: &str) -> Result<Vec<PasswordEntry>, String> {
+    let mut encontradas: Vec<(u8, PasswordEntry)> = list_entries(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|e| match_level(&e.url, origen).map(|nivel| (nivel, e)))
+        .collect();
+    // Primero el mismo servidor, luego las modificadas más recientemente
+    encontradas.sort_by(|(nivel_a, a), (nivel_b, b)| nivel_b.cmp(nivel_a).then(b.modificado.cmp(&a.modificado)));
+    Ok(encontradas.into_iter().map(|(_, e)| e).collect())
+}
+
+fn handle(config: &Config, conn: &Connection, peticion: Request) -> Result<Response, String> {
+    let correcta = Response { ok: true, ..Default::default() };
+    match peticion {
+        Request::Status => Ok(Response {
+            unlocked: Some(session_key(config, conn).is_ok()),
+            approval: Some(askpass().is_some()),
+            ..correcta
+        }),
+        Request::Lookup { origin } => {
+            session_key(config, conn)?;
+            let entries = matching_entries(conn, &origin)?
+                .into_iter()
+                .map(|e| EntrySummary { id: e.id, app: e.app, username: e.usuario, url: e.url })
+                .collect();
+            Ok(Response { entries: Some(entries), ..correcta })
+        }
+        Request::Credentials { origin, id } => {
+            let clave = session_key(config, conn)?;
+            let entrada = get_entry(conn, &id)
+                .map_err(|e| e.to_string())?
+                .filter(|e| match_level(&e.url, &origin).is_some())
+                .ok_or("La entrada no corresponde a este origen")?;
+            if !approve(&entrada, &origin) {
+                return Err("El usuario no ha aprobado la petición".to_string());
+            }
+            Ok(Response {
+                username: Some(entrada.usuario.clone()),
+                password: Some(decrypt(&clave, &entrada.con)?),
+                ..correcta
+            })
+        }
+    }
+}
+
+fn read_message(entrada: &mut impl Read) -> Result<Option<Vec<u8>>, String> {
+    let mut largo = [0u8; 4];
+    match entrada.read_exact(&mut largo) {
+        Ok(()) => {}
+        // El navegador cierra la entrada estándar al desconectar
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let largo = u32::from_ne_bytes(largo) as usize;
+    if largo > LARGO_MAXIMO_ENTRADA {
+        return Err(format!("Mensaje demasiado grande ({} bytes)", largo));
+    }
+    let mut mensaje = vec![0u8; largo];
+    entrada.read_exact(&mut mensaje).map_err(|e| e.to_string())?;
+    Ok(Some(mensaje))
+}
+
+fn write_message(salida: &mut impl Write, respuesta: &Response) -> Result<(), String> {
+    let mut datos = serde_json::to_vec(respuesta).map_err(|e| e.to_string())?;
+    if datos.len() > LARGO_MAXIMO_SALIDA {
+        let error = Response { error: Some("Respuesta demasiado grande".to_string()), ..Default::default() };
+        datos = serde_json::to_vec(&error).map_err(|e| e.to_string())?;
+    }
+    salida.write_all(&(datos.len() as u32).to_ne_bytes()).map_err(|e| e.to_string())?;
+    salida.write_all(&datos).map_err(|e| e.to_string())?;
+    salida.flush().map_err(|e| e.to_string())
+}
+
+// Atiende mensajes hasta que el navegador cierra la conexión
+pub fn run(config: &Config) -> Result<(), String> {
+    let conn = open_db(config).map_err(|e| e.to_string())?;
+    let mut entrada = std::io::stdin().lock();
+    let mut salida = std::io::stdout().lock();
+    while let Some(mensaje) = read_message(&mut entrada)? {
+        let respuesta = match serde_json::from_slice::<Request>(&mensaje) {
+            Ok(peticion) => handle(config, &conn, peticion),
+            Err(e) => Err(format!("Mensaje inválido: {}", e)),
+        };
+        let respuesta = respuesta.unwrap_or_else(|e| Response { error: Some(e), ..Default::default() });
+        write_message(&mut salida, &respuesta)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vault;
+    use std::io::Cursor;
+    use vecla::db::insert_entry;
+
+    #[test]
+    fn matches_subdomains_but_not_lookalikes() {
+        assert_eq!(match_level("https://example.com", "https://example.com"), Some(2));
+        assert_eq!(match_level("https://example.com/login", "https://www.example.com"), Some(1));
+        assert_eq!(match_level("https://example.com", "https://notexample.com"), None);
+        assert_eq!(match_level("https://example.com", "https://example.com.evil.net"), None);
+        assert_eq!(match_level("https://www.example.com", "https://example.com"), None);
+        assert_eq!(match_level("", "https://example.com"), None);
+    }
+
+    #[test]
+    fn scheme_less_urls_only_match_https() {
+        assert_eq!(match_level("example.com", "https://example.com"), Some(2));
+        assert_eq!(match_level("example.com", "http://example.com"), None);
+        assert_eq!(match_level("https://example.com", "http://example.com"), None);
+        assert_eq!(match_level("http://example.com", "http://example.com"), Some(2));
+        assert_eq!(match_level("http://example.com", "https://example.com"), Some(2));
+        assert_eq!(match_level("ftp://example.com", "https://example.com"), None);
+    }
+
+    fn ids(conn: &Connection, origen: &str) -> Vec<String> {
+        matching_entries(conn, origen).unwrap().into_iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn lists_the_same_host_first() {
+        let conn = test_vault();
+        let subdominio = PasswordEntry::new("Correo", "https://mail.example.com", "ana", "", "x", "");
+        let raiz = PasswordEntry::new("Example", "example.com", "ana", "", "x", "");
+        let http = PasswordEntry::new("Inseguro", "http://mail.example.com", "ana", "", "x", "");
+        for entrada in [&raiz, &subdominio, &http] {
+            insert_entry(&conn, entrada).unwrap();
+        }
+        let encontradas = ids(&conn, "https://mail.example.com");
+        assert_eq!(encontradas.len(), 3);
+        assert_eq!(encontradas[2], raiz.id);
+        assert_eq!(ids(&conn, "http://mail.example.com"), [http.id]);
+    }
+
+    #[test]
+    fn frames_messages_with_native_length() {
+        let mut salida = Vec::new();
+        write_message(&mut salida, &Response { ok: true, ..Default::default() }).unwrap();
+        assert_eq!(&salida[..4], &11u32.to_ne_bytes());
+        assert_eq!(&salida[4..], br#"{"ok":true}"#);
+
+        let mut entrada = Cursor::new(salida);
+        assert_eq!(read_message(&mut entrada).unwrap().unwrap(), br#"{"ok":true}"#);
+        // Fin de la entrada: el navegador se ha desconectado
+        assert_eq!(read_message(&mut entrada).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_oversize_messages() {
+        let mut entrada = Cursor::new(((LARGO_MAXIMO_ENTRADA + 1) as u32).to_ne_bytes().to_vec());
+        assert!(read_message(&mut entrada).is_err());
+        // Un mensaje cortado antes de su longitud es un error, no un cierre limpio
+        let mut entrada = Cursor::new([&5u32.to_ne_bytes()[..], b"{}"].concat());
+        assert!(read_message(&mut entrada).is_err());
+
+        let respuesta = Response { ok: true, password: Some("x".repeat(LARGO_MAXIMO_SALIDA)), ..Default::default() };
+        let mut salida = Vec::new();
+        write_message(&mut salida, &respuesta).unwrap();
+        let largo = u32::from_ne_bytes(salida[..4].try_into().unwrap()) as usize;
+        assert_eq!(largo, salida.len() - 4);
+        let error: serde_json::Value = serde_json::from_slice(&salida[4..]).unwrap();
+        assert_eq!(error["ok"], false);
+        assert_eq!(error["error"], "Respuesta demasiado grande");
+    }
+}
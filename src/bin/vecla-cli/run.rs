@@ -0,0 +1,66 @@
+// vecla-cli run / render: secretos de la bóveda como variables de entorno o en plantillas,
+// sin escribirlos nunca en disco. Las referencias tienen la forma entry:<entrada>/<campo>,
+// donde <entrada> es lo mismo que acepta "get" y <campo> es "con" si se omite.
+use rusqlite::Connection;
+use std::process::Command;
+use crate::{field_value, resolve_entry};
+
+const PREFIJO: &str = "entry:";
+
+// Valor descifrado de una referencia entry:<entrada>/<campo>
+fn resolve_reference(conn: &Connection, master_key: &str, referencia: &str) -> Result<String, String> {
+    let ruta = referencia
+        .strip_prefix(PREFIJO)
+        .ok_or(format!("Referencia inválida \"{}\": debe empezar por {}", referencia, PREFIJO))?;
+    let (entrada, campo) = ruta.rsplit_once('/').unwrap_or((ruta, "con"));
+    if entrada.is_empty() || campo.is_empty() {
+        return Err(format!("Referencia inválida \"{}\": usa {}<entrada>/<campo>", referencia, PREFIJO));
+    }
+    let entrada = resolve_entry(conn, entrada)?;
+    field_value(conn, master_key, &entrada, campo)
+}
+
+// Ejecuta el comando con las variables NOMBRE=entry:... añadidas a su entorno y
+// devuelve su código de salida
+pub fn run(conn: &Connection, master_key: &str, variables: &[String], comando: &[String]) -> Result<i32, String> {
+    let (programa, argumentos) = comando.split_first().ok_or("Falta el comando a ejecutar tras --")?;
+    let mut hijo = Command::new(programa);
+    hijo.args(argumentos);
+    for variable in variables {
+        let (nombre, referencia) = variable
+            .split_once('=')
+            .ok_or(format!("Variable inválida \"{}\": usa NOMBRE=entry:<entrada>/<campo>", variable))?;
+        if nombre.is_empty() {
+            return Err(format!("Variable sin nombre: \"{}\"", variable));
+        }
+        hijo.env(nombre, resolve_reference(conn, master_key, referencia)?);
+    }
+    let estado = hijo
+        .status()
+        .map_err(|e| format!("No se pudo ejecutar \"{}\": {}", programa, e))?;
+    Ok(estado.code().unwrap_or(1))
+}
+
+// Sustituye cada {{ entry:<entrada>/<campo> }} de la plantilla por su valor. Lo que no
+// sea una referencia entre llaves se deja tal cual.
+pub fn render(conn: &Connection, master_key: &str, plantilla: &str) -> Result<String, String> {
+    let mut salida = String::with_capacity(plantilla.len());
+    let mut resto = plantilla;
+    while let Some(inicio) = resto.find("{{") {
+        salida.push_str(&resto[..inicio]);
+        let tras_apertura = &resto[inicio + 2..];
+        let Some(fin) = tras_apertura.find("}}") else {
+            salida.push_str(&resto[inicio..]);
+            return Ok(salida);
+        };
+        let contenido = tras_apertura[..fin].trim();
+        if contenido.starts_with(PREFIJO) {
+            salida.push_str(&resolve_reference(conn, master_key, contenido)?);
+        } else {
+            salida.push_str(&resto[inicio..inicio + 2 + fin + 2]);
+        }
+        resto = &tras_apertura[fin + 2..];
+    }
+    salida.push_str(resto);
+    Ok(salida)
+}
@@ -0,0 +1,194 @@
+// Órdenes de vecla-cli para las claves SSH guardadas en la bóveda: importarlas,
+// listarlas y servirlas con el agente SSH (vecla::ssh_agent)
+use clap::Subcommand;
+use rusqlite::Connection;
+use serde_json::json;
+use ssh_key::{HashAlg, PrivateKey};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use vecla::config::Config;
+use vecla::crypto::{encrypt, encrypt_bytes};
+use vecla::db::{Attachment, PasswordEntry, insert_attachment, insert_entry, list_entries};
+use vecla::ssh_agent::{self, ETIQUETA_CONFIRMAR, SshKey};
+use crate::{after_write, resolve_entry, unlock};
+
+// Etiqueta de las entradas que el agente sirve por defecto
+const ETIQUETA: &str = "ssh";
+
+#[derive(Subcommand)]
+pub enum SshCommand {
+    /// Sirve las claves de la bóveda por un socket del agente SSH
+    Agent {
+        /// Socket donde escuchar (por defecto, ssh-agent.sock en el directorio del agente)
+        #[arg(long, value_name = "RUTA")]
+        socket: Option<PathBuf>,
+        /// Pide confirmación ($SSH_ASKPASS) en cada uso de cualquier clave
+        #[arg(long)]
+        confirm: bool,
+        /// Entrada a servir en lugar de las etiquetadas "ssh"; se puede repetir
+        #[arg(short = 'e', long = "entry", value_name = "ENTRADA")]
+        entradas: Vec<String>,
+        /// No pasa a segundo plano (para systemd o depuración)
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Guarda una clave privada OpenSSH en una entrada nueva y la etiqueta "ssh"
+    Import {
+        /// Archivo de la clave privada (p. ej. ~/.ssh/id_ed25519)
+        archivo: PathBuf,
+        /// Nombre de la entrada (por defecto, el comentario de la clave o el nombre del archivo)
+        #[arg(long)]
+        app: Option<String>,
+        /// El agente pedirá confirmación cada vez que se use esta clave
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Lista las claves de las entradas etiquetadas "ssh" y la entrada de la que sale cada una
+    List,
+}
+
+pub fn run(comando: &SshCommand, config: &Config, json: bool) -> Result<(), String> {
+    match comando {
+        SshCommand::Agent { socket, confirm, entradas, foreground } => {
+            let (conn, master_key) = unlock(config, false)?;
+            let claves = load(&conn, &master_key, entradas, *confirm)?;
+            if claves.is_empty() {
+                return Err("No hay ninguna clave SSH que servir".to_string());
+            }
+            // El agente solo necesita las claves ya descifradas
+            drop((conn, master_key));
+            let ruta = socket.clone().unwrap_or_else(ssh_agent::socket_path);
+            let listener = ssh_agent::listen(&ruta)?;
+            eprintln!("Agente SSH con {} clave(s) en {}", claves.len(), ruta.display());
+            if *foreground {
+                print_env(&ruta, std::process::id());
+                ssh_agent::serve(listener, claves);
+                return Ok(());
+            }
+            // Aún no hay más hilos, así que fork es seguro aquí
+            match unsafe { libc::fork() } {
+                -1 => Err(format!("No se pudo pasar a segundo plano: {}", std::io::Error::last_os_error())),
+                0 => {
+                    detach();
+                    ssh_agent::serve(listener, claves);
+                    Ok(())
+                }
+                hijo => {
+                    print_env(&ruta, hijo as u32);
+                    Ok(())
+                }
+            }
+        }
+        SshCommand::Import { archivo, app, confirm } => import(config, archivo, app.as_deref(), *confirm),
+        SshCommand::List => {
+            let (conn, master_key) = unlock(config, false)?;
+            let claves = load(&conn, &master_key, &[], false)?;
+            if json {
+                let lista: Vec<_> = claves
+                    .iter()
+                    .map(|c| {
+                        json!({
+                            "entry_id": c.entry_id,
+                            "nombre": c.nombre,
+                            "algoritmo": c.algorithm(),
+                            "huella": c.fingerprint(),
+                            "confirmar": c.confirmar,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&lista).map_err(|e| e.to_string())?);
+                return Ok(());
+            }
+            for c in &claves {
+                let id: String = c.entry_id.chars().take(8).collect();
+                let confirmar = if c.confirmar { "  (confirmar)" } else { "" };
+                println!("{}  {}  {}  {}{}", id, c.algorithm(), c.fingerprint(), c.nombre, confirmar);
+            }
+            Ok(())
+        }
+    }
+}
+
+// Variables para el shell, como ssh-agent: eval "$(vecla-cli ssh agent)"
+fn print_env(ruta: &Path, pid: u32) {
+    println!("SSH_AUTH_SOCK={}; export SSH_AUTH_SOCK;", ruta.display());
+    println!("SSH_AGENT_PID={}; export SSH_AGENT_PID;", pid);
+}
+
+// Separa el proceso hijo de la terminal para que el shell no espere por él
+fn detach() {
+    unsafe {
+        libc::setsid();
+        let nulo = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if nulo >= 0 {
+            for fd in 0..3 {
+                libc::dup2(nulo, fd);
+            }
+        }
+    }
+}
+
+// Claves de las entradas pedidas o, si no se pide ninguna, de las etiquetadas "ssh"
+fn load(conn: &Connection, master_key: &str, referencias: &[String], confirmar: bool) -> Result<Vec<SshKey>, String> {
+    let entradas: Vec<PasswordEntry> = if referencias.is_empty() {
+        list_entries(conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|e| e.has_tag(ETIQUETA) || e.has_tag(ETIQUETA_CONFIRMAR))
+            .collect()
+    } else {
+        referencias.iter().map(|r| resolve_entry(conn, r)).collect::<Result<_, _>>()?
+    };
+    let (claves, avisos) = ssh_agent::load_keys(conn, master_key, &entradas, confirmar)?;
+    for aviso in avisos {
+        eprintln!("Aviso: {}", aviso);
+    }
+    Ok(claves)
+}
+
+fn import(config: &Config, archivo: &Path, app: Option<&str>, confirmar: bool) -> Result<(), String> {
+    let texto = std::fs::read_to_string(archivo).map_err(|e| format!("No se pudo leer {}: {}", archivo.display(), e))?;
+    let clave = PrivateKey::from_openssh(texto.trim())
+        .map_err(|e| format!("{} no es una clave privada OpenSSH: {}", archivo.display(), e))?;
+
+    // La frase de paso de una clave protegida se guarda como contraseña de la entrada
+    let mut frase = String::new();
+    let publica = if clave.is_encrypted() {
+        frase = rpassword::prompt_password(format!("Frase de paso de {}: ", archivo.display()))
+            .map_err(|e| e.to_string())?;
+        let descifrada = clave.decrypt(frase.as_bytes()).map_err(|_| "Frase de paso incorrecta".to_string())?;
+        descifrada.public_key().clone()
+    } else {
+        clave.public_key().clone()
+    };
+
+    let nombre_archivo = archivo
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "id".to_string());
+    let app = match app {
+        Some(app) => app.to_string(),
+        None if !publica.comment().is_empty() => publica.comment().to_string(),
+        None => nombre_archivo.clone(),
+    };
+    let linea_publica = publica.to_openssh().map_err(|e| e.to_string())?;
+
+    let (conn, master_key) = unlock(config, true)?;
+    let notas = format!("Clave pública:\n{}\nHuella: {}", linea_publica, publica.fingerprint(HashAlg::Sha256));
+    let mut entrada = PasswordEntry::new(&app, "", "", "", &encrypt(&master_key, &frase)?, &notas);
+    entrada.etiquetas = if confirmar {
+        format!("{},{}", ETIQUETA, ETIQUETA_CONFIRMAR)
+    } else {
+        ETIQUETA.to_string()
+    };
+    insert_entry(&conn, &entrada).map_err(|e| e.to_string())?;
+    let adjunto = Attachment {
+        id: Uuid::new_v4().to_string(),
+        nombre: nombre_archivo,
+        datos: encrypt_bytes(&master_key, texto.as_bytes())?,
+    };
+    insert_attachment(&conn, &entrada.id, &adjunto).map_err(|e| e.to_string())?;
+    after_write(&conn, config)?;
+    eprintln!("Clave guardada en \"{}\" ({}); ya puedes borrar {}", app, entrada.id, archivo.display());
+    Ok(())
+}
@@ -0,0 +1,256 @@
+// Portapapeles del sistema detrás de un trait: API de Windows en Windows, wl-clipboard en
+// Wayland y X11 (o el de macOS) con arboard en el resto. SecretClipboard lo vacía pasado
+// un tiempo, pero solo si todavía contiene el secreto copiado.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+pub trait Clipboard: Send {
+    fn set_text(&mut self, texto: &str) -> Result<(), String>;
+    fn get_text(&mut self) -> Result<String, String>;
+    fn clear(&mut self) -> Result<(), String>;
+}
+
+// Portapapeles de la sesión actual
+pub fn system() -> Result<Box<dyn Clipboard>, String> {
+    #[cfg(windows)]
+    {
+        Ok(Box::new(windows::WindowsClipboard))
+    }
+    #[cfg(not(windows))]
+    {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() && wayland::available() {
+            return Ok(Box::new(wayland::WaylandClipboard));
+        }
+        Ok(Box::new(arboard_clipboard::ArboardClipboard::new()?))
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use winapi::um::minwinbase::GMEM_MOVEABLE;
+    use winapi::um::winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock};
+    use winapi::um::winuser::{CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData, CF_UNICODETEXT};
+
+    pub struct WindowsClipboard;
+
+    // Abre el portapapeles, ejecuta la operación y lo cierra siempre
+    fn with_clipboard<T>(operacion: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        unsafe {
+            if OpenClipboard(ptr::null_mut()) == 0 {
+                return Err("No se pudo abrir el portapapeles".to_string());
+            }
+            let resultado = operacion();
+            CloseClipboard();
+            resultado
+        }
+    }
+
+    impl super::Clipboard for WindowsClipboard {
+        fn set_text(&mut self, texto: &str) -> Result<(), String> {
+            // UTF-16 terminado en nulo
+            let texto_utf16: Vec<u16> = OsStr::new(texto).encode_wide().chain(std::iter::once(0)).collect();
+            with_clipboard(|| unsafe {
+                EmptyClipboard();
+                let tamano = texto_utf16.len() * std::mem::size_of::<u16>();
+                let h_glob = GlobalAlloc(GMEM_MOVEABLE, tamano);
+                if h_glob.is_null() {
+                    return Err("Error al asignar memoria".to_string());
+                }
+                let p_glob = GlobalLock(h_glob) as *mut u16;
+                if p_glob.is_null() {
+                    GlobalFree(h_glob);
+                    return Err("Error al bloquear memoria".to_string());
+                }
+                ptr::copy_nonoverlapping(texto_utf16.as_ptr(), p_glob, texto_utf16.len());
+                GlobalUnlock(h_glob);
+                // Si se acepta, la memoria pasa a ser del sistema
+                if SetClipboardData(CF_UNICODETEXT, h_glob).is_null() {
+                    GlobalFree(h_glob);
+                    return Err("No se pudo establecer el contenido del portapapeles".to_string());
+                }
+                Ok(())
+            })
+        }
+
+        fn get_text(&mut self) -> Result<String, String> {
+            with_clipboard(|| unsafe {
+                let h_datos = GetClipboardData(CF_UNICODETEXT);
+                if h_datos.is_null() {
+                    return Ok(String::new());
+                }
+                let p_datos = GlobalLock(h_datos) as *const u16;
+                if p_datos.is_null() {
+                    return Err("Error al bloquear memoria".to_string());
+                }
+                let mut largo = 0;
+                while *p_datos.add(largo) != 0 {
+                    largo += 1;
+                }
+                let texto = String::from_utf16_lossy(std::slice::from_raw_parts(p_datos, largo));
+                GlobalUnlock(h_datos);
+                Ok(texto)
+            })
+        }
+
+        fn clear(&mut self) -> Result<(), String> {
+            with_clipboard(|| unsafe {
+                EmptyClipboard();
+                Ok(())
+            })
+        }
+    }
+}
+
+// wl-copy y wl-paste (paquete wl-clipboard): en Wayland solo la ventana con el foco
+// puede tocar el portapapeles, y wl-copy se queda en segundo plano sirviéndolo
+#[cfg(all(unix, not(target_os = "macos")))]
+mod wayland {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const TIPO: &str = "text/plain;charset=utf-8";
+
+    pub struct WaylandClipboard;
+
+    pub fn available() -> bool {
+        std::env::var_os("PATH")
+            .map(|ruta| std::env::split_paths(&ruta).any(|dir| dir.join("wl-copy").is_file()))
+            .unwrap_or(false)
+    }
+
+    impl super::Clipboard for WaylandClipboard {
+        fn set_text(&mut self, texto: &str) -> Result<(), String> {
+            let mut hijo = Command::new("wl-copy")
+                .args(["--type", TIPO])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()
+                .map_err(|e| format!("No se pudo ejecutar wl-copy: {}", e))?;
+            if let Some(mut entrada) = hijo.stdin.take() {
+                entrada.write_all(texto.as_bytes()).map_err(|e| e.to_string())?;
+            }
+            match hijo.wait() {
+                Ok(estado) if estado.success() => Ok(()),
+                _ => Err("wl-copy no pudo copiar al portapapeles".to_string()),
+            }
+        }
+
+        fn get_text(&mut self) -> Result<String, String> {
+            let salida = Command::new("wl-paste")
+                .args(["--no-newline", "--type", TIPO])
+                .stderr(Stdio::null())
+                .output()
+                .map_err(|e| format!("No se pudo ejecutar wl-paste: {}", e))?;
+            // Sin nada copiado wl-paste termina con error
+            if !salida.status.success() {
+                return Ok(String::new());
+            }
+            String::from_utf8(salida.stdout).map_err(|e| e.to_string())
+        }
+
+        fn clear(&mut self) -> Result<(), String> {
+            let estado = Command::new("wl-copy").arg("--clear").status().map_err(|e| e.to_string())?;
+            if estado.success() {
+                Ok(())
+            } else {
+                Err("wl-copy no pudo vaciar el portapapeles".to_string())
+            }
+        }
+    }
+}
+
+// X11 (también XWayland) y macOS. En X11 el contenido lo sirve este proceso: se pierde
+// al cerrar Vecla, salvo que haya un gestor de portapapeles que lo recoja.
+#[cfg(not(windows))]
+mod arboard_clipboard {
+    pub struct ArboardClipboard(arboard::Clipboard);
+
+    impl ArboardClipboard {
+        pub fn new() -> Result<Self, String> {
+            arboard::Clipboard::new()
+                .map(Self)
+                .map_err(|e| format!("No se pudo abrir el portapapeles: {}", e))
+        }
+    }
+
+    impl super::Clipboard for ArboardClipboard {
+        fn set_text(&mut self, texto: &str) -> Result<(), String> {
+            self.0.set_text(texto).map_err(|e| e.to_string())
+        }
+
+        fn get_text(&mut self) -> Result<String, String> {
+            match self.0.get_text() {
+                Ok(texto) => Ok(texto),
+                Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+
+        fn clear(&mut self) -> Result<(), String> {
+            self.0.clear().map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Portapapeles para contraseñas: cada copia programa su limpieza, que no hace nada si
+// mientras tanto se ha copiado otra cosa (en Vecla o en otro programa)
+pub struct SecretClipboard {
+    portapapeles: Arc<Mutex<Box<dyn Clipboard>>>,
+    copias: Arc<AtomicU64>,
+    ultima: Arc<Mutex<Option<Zeroizing<String>>>>,
+}
+
+impl SecretClipboard {
+    pub fn new(portapapeles: Box<dyn Clipboard>) -> Self {
+        Self {
+            portapapeles: Arc::new(Mutex::new(portapapeles)),
+            copias: Arc::new(AtomicU64::new(0)),
+            ultima: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Copia el texto y, si hay tiempo de limpieza, vacía el portapapeles al cumplirse
+    pub fn copy(&self, texto: &str, limpiar_tras: Option<Duration>) -> Result<(), String> {
+        self.portapapeles.lock().unwrap().set_text(texto)?;
+        let copia = self.copias.fetch_add(1, Ordering::SeqCst) + 1;
+        *self.ultima.lock().unwrap() = Some(Zeroizing::new(texto.to_string()));
+        let Some(espera) = limpiar_tras else {
+            return Ok(());
+        };
+        let portapapeles = Arc::clone(&self.portapapeles);
+        let copias = Arc::clone(&self.copias);
+        let ultima = Arc::clone(&self.ultima);
+        std::thread::spawn(move || {
+            std::thread::sleep(espera);
+            // Una copia posterior programa su propia limpieza
+            if copias.load(Ordering::SeqCst) == copia {
+                clear_if_unchanged(&portapapeles, &ultima);
+            }
+        });
+        Ok(())
+    }
+
+    // Vacía el portapapeles si aún contiene lo último que se copió (p. ej. al cerrar)
+    pub fn clear_now(&self) {
+        clear_if_unchanged(&self.portapapeles, &self.ultima);
+    }
+}
+
+fn clear_if_unchanged(portapapeles: &Mutex<Box<dyn Clipboard>>, ultima: &Mutex<Option<Zeroizing<String>>>) {
+    let Some(secreto) = ultima.lock().unwrap().take() else {
+        return;
+    };
+    let mut portapapeles = portapapeles.lock().unwrap();
+    let actual = Zeroizing::new(portapapeles.get_text().unwrap_or_default());
+    if *actual == *secreto {
+        if let Err(e) = portapapeles.clear() {
+            eprintln!("No se pudo vaciar el portapapeles: {}", e);
+        }
+    }
+}
@@ -1,20 +1,61 @@
-use std::path::Path;
+use crate::generator::PasswordPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const NOMBRE_PORTABLE: &str = "vecla.json";
+const MARCA_PORTABLE: &str = "vecla.portable";
+const CLAVES_CONOCIDAS: [&str; 15] = [
+    "db_path", "key_file", "backup_path", "backup_generations", "default_password_length", "auto_save",
+    "agent_timeout", "clipboard_timeout", "reveal_timeout", "reveal_requires_key", "lock_timeout",
+    "lock_on_minimize", "password_profiles", "password_max_age", "passphrase_wordlist",
+];
 
 // Configuración de la aplicación
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub db_path: String,
+    pub key_file: Option<String>,  // archivo de clave que acompaña a la clave maestra
     pub backup_path: Option<String>,
+    pub backup_generations: usize, // copias que se conservan
     pub default_password_length: usize,
-    pub auto_save: bool,
+    pub auto_save: bool,           // copia automática tras los cambios, como mucho cada 15 min
+    pub agent_timeout: u64,        // segundos sin uso tras los que el agente olvida la clave
+    pub clipboard_timeout: u64,    // segundos tras los que se vacía el portapapeles, 0 = nunca
+    pub reveal_timeout: u64,       // segundos que una contraseña se muestra en la lista
+    pub reveal_requires_key: bool, // pedir la clave maestra para mostrar una contraseña
+    pub lock_timeout: u64,         // segundos de inactividad tras los que se bloquea la interfaz, 0 = nunca
+    pub lock_on_minimize: bool,    // bloquear la interfaz al minimizar la ventana
+    pub password_profiles: BTreeMap<String, PasswordPolicy>, // políticas del generador guardadas por nombre
+    pub password_max_age: u64,     // días tras los que el informe de salud avisa de una contraseña, 0 = nunca
+    pub passphrase_wordlist: Option<String>, // lista Diceware para las frases en lugar de las incluidas
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>, // archivo del que se cargó
+    #[serde(skip)]
+    pub readonly: bool,               // solo desde la línea de comandos
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             db_path: "vault.db".to_string(),
+            key_file: None,
             backup_path: None,
+            backup_generations: 10,
             default_password_length: 16,
             auto_save: true,
+            agent_timeout: 900,
+            clipboard_timeout: 30,
+            reveal_timeout: 15,
+            reveal_requires_key: false,
+            lock_timeout: 300,
+            lock_on_minimize: true,
+            password_profiles: BTreeMap::new(),
+            password_max_age: 365,
+            passphrase_wordlist: None,
+            config_path: None,
+            readonly: false,
         }
     }
 }
@@ -29,11 +70,358 @@ impl Config {
         self
     }
     
-    pub fn get_db_path(&self) -> &str {
-        &self.db_path
+    // Bóveda indicada con --vault: se interpreta respecto al directorio actual,
+    // como cualquier ruta escrita en la terminal
+    pub fn with_vault_arg(self, vault: &str) -> Self {
+        let ruta = std::path::absolute(vault).unwrap_or_else(|_| PathBuf::from(vault));
+        self.with_db_path(&ruta.to_string_lossy())
+    }
+
+    // Archivo de clave indicado con --key-file, resuelto como --vault
+    pub fn with_key_file_arg(mut self, key_file: &str) -> Self {
+        let ruta = std::path::absolute(key_file).unwrap_or_else(|_| PathBuf::from(key_file));
+        self.key_file = Some(ruta.to_string_lossy().to_string());
+        self
+    }
+
+    // Ruta de la bóveda; las rutas relativas se resuelven desde base_dir()
+    pub fn get_db_path(&self) -> PathBuf {
+        Self::resolve(&self.db_path)
+    }
+
+    // Carpeta de copias configurada, resuelta igual que la bóveda
+    pub fn get_backup_path(&self) -> Option<PathBuf> {
+        self.backup_path.as_deref().map(Self::resolve)
     }
     
+    pub fn get_key_file_path(&self) -> Option<PathBuf> {
+        self.key_file.as_deref().map(Self::resolve)
+    }
+
+    pub fn get_passphrase_wordlist_path(&self) -> Option<PathBuf> {
+        self.passphrase_wordlist.as_deref().map(Self::resolve)
+    }
+
     pub fn db_exists(&self) -> bool {
-        Path::new(&self.db_path).exists()
+        self.get_db_path().exists()
+    }
+
+    fn resolve(ruta: &str) -> PathBuf {
+        let ruta = Path::new(ruta);
+        if ruta.is_absolute() {
+            ruta.to_path_buf()
+        } else {
+            Self::base_dir().join(ruta)
+        }
+    }
+
+    // Directorio del ejecutable si está en modo portable: existe vecla.portable
+    // (o vecla.json) junto a él
+    pub fn portable_dir() -> Option<PathBuf> {
+        let dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        if dir.join(MARCA_PORTABLE).exists() || dir.join(NOMBRE_PORTABLE).exists() {
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_portable() -> bool {
+        Self::portable_dir().is_some()
+    }
+
+    // Directorio desde el que se resuelven la bóveda y las copias: el del ejecutable
+    // en modo portable y el de datos del usuario en otro caso, nunca el directorio actual
+    pub fn base_dir() -> PathBuf {
+        Self::portable_dir().unwrap_or_else(|| {
+            dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("vecla")
+        })
+    }
+
+    // Bóveda que dejaron versiones anteriores, que resolvían las rutas relativas desde el
+    // directorio actual o el del ejecutable. Solo se busca si la configurada no existe.
+    pub fn legacy_vault(&self) -> Option<PathBuf> {
+        let ruta = Path::new(&self.db_path);
+        if ruta.is_absolute() || Self::is_portable() || self.db_exists() {
+            return None;
+        }
+        let actual = std::env::current_dir().ok();
+        let ejecutable = std::env::current_exe().ok().and_then(|e| e.parent().map(Path::to_path_buf));
+        [actual, ejecutable].into_iter().flatten().map(|dir| dir.join(ruta)).find(|r| r.is_file())
+    }
+
+    pub fn legacy_vault_warning(&self, antigua: &Path) -> String {
+        format!(
+            "No existe {}, pero hay una bóveda en {} (las versiones anteriores la buscaban ahí). \
+             Muévela a la nueva ubicación o indica su ruta con --vault o db_path.",
+            self.get_db_path().display(),
+            antigua.display()
+        )
+    }
+
+    // Para la terminal: error, con la orden para moverla, si la bóveda sigue en la ubicación
+    // antigua. Así no se crea una bóveda vacía en la nueva.
+    pub fn check_legacy_vault(&self) -> Result<(), String> {
+        let Some(antigua) = self.legacy_vault() else {
+            return Ok(());
+        };
+        let mut error = self.legacy_vault_warning(&antigua);
+        let nueva = self.get_db_path();
+        if let Some(dir) = nueva.parent() {
+            error.push_str(&format!(
+                "\nPara moverla: mkdir -p \"{}\" && mv \"{}\" \"{}\"",
+                dir.display(),
+                antigua.display(),
+                nueva.display()
+            ));
+        }
+        Err(error)
+    }
+
+    // Mueve la bóveda antigua a la ubicación actual, creando su carpeta si hace falta
+    pub fn move_legacy_vault(&self, antigua: &Path) -> Result<(), String> {
+        let nueva = self.get_db_path();
+        if nueva.exists() {
+            return Err(format!("Ya existe {}", nueva.display()));
+        }
+        if let Some(dir) = nueva.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+        }
+        // rename no funciona entre sistemas de archivos distintos
+        if std::fs::rename(antigua, &nueva).is_err() {
+            std::fs::copy(antigua, &nueva).map_err(|e| format!("No se pudo copiar {}: {}", antigua.display(), e))?;
+            std::fs::remove_file(antigua).map_err(|e| format!("No se pudo borrar {}: {}", antigua.display(), e))?;
+        }
+        Ok(())
+    }
+
+    // vecla.json junto al ejecutable (modo portable) o el directorio de configuración del sistema
+    pub fn default_path() -> PathBuf {
+        if let Some(dir) = Self::portable_dir() {
+            return dir.join(NOMBRE_PORTABLE);
+        }
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("vecla")
+            .join("config.json")
+    }
+
+    // Carga la configuración; si el archivo no existe se usan los valores por defecto.
+    // Devuelve también los avisos (claves desconocidas).
+    pub fn load(path: &Path) -> Result<(Self, Vec<String>), String> {
+        let mut avisos = Vec::new();
+        let mut config = if path.exists() {
+            let texto = std::fs::read_to_string(path)
+                .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+            let valor: serde_json::Value = serde_json::from_str(&texto)
+                .map_err(|e| format!("{} no es un JSON válido: {}", path.display(), e))?;
+            let objeto = valor.as_object().ok_or(format!("{} debe contener un objeto JSON", path.display()))?;
+            for clave in objeto.keys().filter(|c| !CLAVES_CONOCIDAS.contains(&c.as_str())) {
+                avisos.push(format!("Clave desconocida en {}: \"{}\" (se ignora)", path.display(), clave));
+            }
+            serde_json::from_value::<Config>(valor)
+                .map_err(|e| format!("Valor inválido en {}: {}", path.display(), e))?
+        } else {
+            Config::default()
+        };
+        config.validate()?;
+        config.config_path = Some(path.to_path_buf());
+        Ok((config, avisos))
+    }
+
+    // Configuración de --config (o la de default_path) con --vault, --key-file y --readonly
+    // encima: los argumentos y variables de entorno tienen prioridad sobre el archivo
+    pub fn load_with_args(
+        path: Option<&Path>,
+        vault: Option<&str>,
+        key_file: Option<&str>,
+        readonly: bool,
+    ) -> Result<(Self, Vec<String>), String> {
+        let (mut config, avisos) = match path {
+            Some(ruta) => Self::load(ruta)?,
+            None => Self::load(&Self::default_path())?,
+        };
+        if let Some(vault) = vault {
+            config = config.with_vault_arg(vault);
+        }
+        if let Some(key_file) = key_file {
+            config = config.with_key_file_arg(key_file);
+        }
+        config.readonly = readonly;
+        Ok((config, avisos))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errores = Vec::new();
+        if self.db_path.trim().is_empty() {
+            errores.push("db_path no puede estar vacío".to_string());
+        }
+        if matches!(&self.key_file, Some(ruta) if ruta.trim().is_empty()) {
+            errores.push("key_file no puede ser una cadena vacía (usa null para no usar archivo de clave)".to_string());
+        }
+        if matches!(&self.backup_path, Some(ruta) if ruta.trim().is_empty()) {
+            errores.push("backup_path no puede ser una cadena vacía (usa null para la carpeta por defecto)".to_string());
+        }
+        if matches!(&self.passphrase_wordlist, Some(ruta) if ruta.trim().is_empty()) {
+            errores.push("passphrase_wordlist no puede ser una cadena vacía (usa null para las listas incluidas)".to_string());
+        }
+        if !(1..=1000).contains(&self.backup_generations) {
+            errores.push("backup_generations debe estar entre 1 y 1000".to_string());
+        }
+        if !(4..=512).contains(&self.default_password_length) {
+            errores.push("default_password_length debe estar entre 4 y 512".to_string());
+        }
+        if !(10..=86_400).contains(&self.agent_timeout) {
+            errores.push("agent_timeout debe estar entre 10 y 86400 segundos".to_string());
+        }
+        if self.clipboard_timeout > 3600 {
+            errores.push("clipboard_timeout debe estar entre 0 (nunca) y 3600 segundos".to_string());
+        }
+        if !(1..=600).contains(&self.reveal_timeout) {
+            errores.push("reveal_timeout debe estar entre 1 y 600 segundos".to_string());
+        }
+        if self.lock_timeout > 86_400 {
+            errores.push("lock_timeout debe estar entre 0 (nunca) y 86400 segundos".to_string());
+        }
+        if self.password_max_age > 36_500 {
+            errores.push("password_max_age debe estar entre 0 (nunca) y 36500 días".to_string());
+        }
+        for (nombre, politica) in &self.password_profiles {
+            if nombre.trim().is_empty() {
+                errores.push("password_profiles no puede tener perfiles sin nombre".to_string());
+            } else if let Err(e) = politica.validate() {
+                errores.push(format!("password_profiles.{}: {}", nombre, e));
+            }
+        }
+        if errores.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Configuración inválida:\n  - {}", errores.join("\n  - ")))
+        }
+    }
+
+    // Tiempo hasta vaciar el portapapeles tras copiar una contraseña
+    pub fn clipboard_clear_after(&self) -> Option<std::time::Duration> {
+        (self.clipboard_timeout > 0).then(|| std::time::Duration::from_secs(self.clipboard_timeout))
+    }
+
+    // Política del perfil indicado o, sin perfil, la de por defecto con default_password_length
+    pub fn password_policy(&self, perfil: Option<&str>) -> Result<PasswordPolicy, String> {
+        match perfil {
+            Some(nombre) => self
+                .password_profiles
+                .get(nombre)
+                .cloned()
+                .ok_or_else(|| format!("No existe el perfil de contraseña \"{}\"", nombre)),
+            None => Ok(PasswordPolicy::with_length(self.default_password_length)),
+        }
+    }
+
+    // Guarda (o reemplaza) un perfil en el archivo de configuración. Se parte de lo que hay
+    // en disco para no guardar de paso opciones que solo valen para esta ejecución (--vault...).
+    pub fn save_profile(&mut self, nombre: &str, politica: &PasswordPolicy) -> Result<(), String> {
+        let nombre = nombre.trim();
+        if nombre.is_empty() {
+            return Err("El perfil necesita un nombre".to_string());
+        }
+        politica.validate()?;
+        let mut guardada = self.on_disk()?;
+        guardada.password_profiles.insert(nombre.to_string(), politica.clone());
+        guardada.save()?;
+        self.password_profiles.insert(nombre.to_string(), politica.clone());
+        Ok(())
+    }
+
+    pub fn delete_profile(&mut self, nombre: &str) -> Result<(), String> {
+        let mut guardada = self.on_disk()?;
+        if guardada.password_profiles.remove(nombre).is_none() {
+            return Err(format!("No existe el perfil de contraseña \"{}\"", nombre));
+        }
+        guardada.save()?;
+        self.password_profiles.remove(nombre);
+        Ok(())
+    }
+
+    // Guarda las opciones de la pantalla de configuración de la interfaz. Como en save_profile,
+    // se parte de lo que hay en disco: db_path solo se guarda si se ha cambiado, porque el de
+    // esta ejecución puede venir de --vault o de la pantalla de desbloqueo, y key_file no se
+    // edita ahí.
+    pub fn save_settings(&mut self, ajustes: &Config) -> Result<(), String> {
+        ajustes.validate()?;
+        let mut guardada = self.on_disk()?;
+        guardada.copy_settings(ajustes);
+        if ajustes.db_path != self.db_path {
+            guardada.db_path = ajustes.db_path.clone();
+        }
+        guardada.save()?;
+        self.copy_settings(ajustes);
+        self.db_path = ajustes.db_path.clone();
+        Ok(())
+    }
+
+    fn copy_settings(&mut self, de: &Config) {
+        self.backup_path = de.backup_path.clone();
+        self.backup_generations = de.backup_generations;
+        self.default_password_length = de.default_password_length;
+        self.auto_save = de.auto_save;
+        self.agent_timeout = de.agent_timeout;
+        self.clipboard_timeout = de.clipboard_timeout;
+        self.reveal_timeout = de.reveal_timeout;
+        self.reveal_requires_key = de.reveal_requires_key;
+        self.lock_timeout = de.lock_timeout;
+        self.lock_on_minimize = de.lock_on_minimize;
+        self.password_max_age = de.password_max_age;
+        self.passphrase_wordlist = de.passphrase_wordlist.clone();
+    }
+
+    fn on_disk(&self) -> Result<Config, String> {
+        let ruta = self.config_path.clone().unwrap_or_else(Self::default_path);
+        Config::load(&ruta).map(|(config, _)| config)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.validate()?;
+        let ruta = self.config_path.clone().unwrap_or_else(Self::default_path);
+        if let Some(dir) = ruta.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+        }
+        let texto = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&ruta, texto).map_err(|e| format!("No se pudo escribir {}: {}", ruta.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_do_not_persist_command_line_overrides() {
+        let dir = std::env::temp_dir().join(format!("vecla-config-{}", uuid::Uuid::new_v4()));
+        let ruta = dir.join("config.json");
+        let mut original = Config::new().with_db_path("mi-boveda.db");
+        original.config_path = Some(ruta.clone());
+        original.save().unwrap();
+
+        let (mut config, _) =
+            Config::load_with_args(Some(&ruta), Some("/tmp/otra.db"), Some("/tmp/otra.key"), true).unwrap();
+        let mut ajustes = config.clone();
+        ajustes.clipboard_timeout = 5;
+        config.save_settings(&ajustes).unwrap();
+
+        let (guardada, _) = Config::load(&ruta).unwrap();
+        assert_eq!(guardada.db_path, "mi-boveda.db");
+        assert_eq!(guardada.key_file, None);
+        assert_eq!(guardada.clipboard_timeout, 5);
+        // La ejecución actual sigue con la bóveda de --vault
+        assert_eq!(config.db_path, "/tmp/otra.db");
+        assert_eq!(config.clipboard_timeout, 5);
+
+        // Cambiar la bóveda en la pantalla de configuración sí se guarda
+        ajustes.db_path = "nueva.db".to_string();
+        config.save_settings(&ajustes).unwrap();
+        assert_eq!(Config::load(&ruta).unwrap().0.db_path, "nueva.db");
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -1,36 +1,59 @@
 use rpassword::prompt_password;
 use aes_gcm::{Aes256Gcm, Key, Nonce};
-use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::aead::{Aead, KeyInit};
 use rand::RngCore;
-use rand::Rng;
-use rand::distributions::{Alphanumeric, Distribution, Standard};
-use base64::{encode, decode};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::path::Path;
 
 pub fn prompt_master_key() -> String {
     prompt_password("Clave maestra: ").expect("No se pudo leer la clave maestra")
 }
 
+// Clave maestra de una bóveda protegida con contraseña y, opcionalmente, archivo de clave.
+// El archivo se interpreta como en KeePass (XML, 32 bytes, 64 hex o hash de su contenido).
+pub fn compose_master_key(password: &str, key_file: Option<&Path>) -> Result<String, String> {
+    let Some(ruta) = key_file else {
+        return Ok(password.to_string());
+    };
+    let archivo = std::fs::read(ruta).map_err(|e| format!("No se pudo leer el archivo de clave {}: {}", ruta.display(), e))?;
+    let clave = crate::kdbx::key_file_key(&archivo)?;
+    let hex: String = clave.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}:{}", password, hex))
+}
+
 pub fn encrypt(master_key: &str, plaintext: &str) -> Result<String, String> {
-    let key = Key::from_slice(&derive_key(master_key));
+    encrypt_bytes(master_key, plaintext.as_bytes())
+}
+
+pub fn decrypt(master_key: &str, b64: &str) -> Result<String, String> {
+    let plaintext = decrypt_bytes(master_key, b64)?;
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+// Variante binaria, usada para adjuntos
+pub fn encrypt_bytes(master_key: &str, plaintext: &[u8]) -> Result<String, String> {
+    let key_bytes = derive_key(master_key);
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
     let cipher = Aes256Gcm::new(key);
     let mut nonce_bytes = [0u8; 12];
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
-    let ciphertext = cipher.encrypt(nonce, plaintext.as_bytes()).map_err(|e| e.to_string())?;
+    let ciphertext = cipher.encrypt(nonce, plaintext).map_err(|e| e.to_string())?;
     let mut result = nonce_bytes.to_vec();
     result.extend(ciphertext);
-    Ok(encode(&result))
+    Ok(STANDARD.encode(&result))
 }
 
-pub fn decrypt(master_key: &str, b64: &str) -> Result<String, String> {
-    let data = decode(b64).map_err(|e| e.to_string())?;
+pub fn decrypt_bytes(master_key: &str, b64: &str) -> Result<Vec<u8>, String> {
+    let data = STANDARD.decode(b64).map_err(|e| e.to_string())?;
     if data.len() < 12 { return Err("Datos cifrados corruptos".to_string()); }
     let (nonce_bytes, ciphertext) = data.split_at(12);
-    let key = Key::from_slice(&derive_key(master_key));
+    let key_bytes = derive_key(master_key);
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
     let cipher = Aes256Gcm::new(key);
     let nonce = Nonce::from_slice(nonce_bytes);
-    let plaintext = cipher.decrypt(nonce, ciphertext).map_err(|e| e.to_string())?;
-    String::from_utf8(plaintext).map_err(|e| e.to_string())
+    cipher.decrypt(nonce, ciphertext).map_err(|e| e.to_string())
 }
 
 fn derive_key(master_key: &str) -> [u8; 32] {
@@ -40,8 +63,8 @@ fn derive_key(master_key: &str) -> [u8; 32] {
     let bytes = master_key.as_bytes();
     
     // Primera pasada: copiar bytes disponibles o rellenar con valor estático
-    for i in 0..32 {
-        key[i] = *bytes.get(i).unwrap_or(&(i as u8 + 1));
+    for (i, k) in key.iter_mut().enumerate() {
+        *k = *bytes.get(i).unwrap_or(&(i as u8 + 1));
     }
     
     // Segunda pasada: mezclar para aumentar entropía
@@ -60,16 +83,3 @@ fn derive_key(master_key: &str) -> [u8; 32] {
     
     key
 }
-
-pub fn generate_password(length: usize) -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*()-_=+";
-    let mut rng = rand::thread_rng();
-    let password: String = (0..length)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect();
-    
-    password
-}
\ No newline at end of file
@@ -1,7 +1,12 @@
-use rusqlite::{Connection, Result, params};
-use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OpenFlags, Result, Row, params};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::path::Path;
 use uuid::Uuid;
+use crate::config::Config;
+use crate::crypto::{decrypt, encrypt};
 
+#[derive(Clone)]
 pub struct PasswordEntry {
     pub id: String,
     pub fecha: String,
@@ -11,26 +16,111 @@ pub struct PasswordEntry {
     pub mail: String,
     pub con: String, // cifrada
     pub notas: String,
+    pub carpeta: String,    // ruta separada por "/", vacía = raíz
+    pub etiquetas: String,  // separadas por coma
+    pub modificado: String,
+    pub con_modificada: String, // último cambio de la contraseña
 }
 
 impl PasswordEntry {
     pub fn new(app: &str, url: &str, usuario: &str, mail: &str, con: &str, notas: &str) -> Self {
+        let ahora = Utc::now().to_rfc3339();
         Self {
             id: Uuid::new_v4().to_string(),
-            fecha: Utc::now().to_rfc3339(),
+            fecha: ahora.clone(),
             app: app.to_string(),
             url: url.to_string(),
             usuario: usuario.to_string(),
             mail: mail.to_string(),
             con: con.to_string(),
             notas: notas.to_string(),
+            carpeta: String::new(),
+            etiquetas: String::new(),
+            modificado: ahora.clone(),
+            con_modificada: ahora,
         }
     }
+
+    // Pertenece a la carpeta o a alguna de sus subcarpetas
+    pub fn in_folder(&self, carpeta: &str) -> bool {
+        let carpeta = carpeta.trim_matches('/');
+        carpeta.is_empty()
+            || self.carpeta == carpeta
+            || self.carpeta.starts_with(&format!("{}/", carpeta))
+    }
+
+    pub fn has_tag(&self, etiqueta: &str) -> bool {
+        self.etiquetas.split(',').any(|t| t.trim().eq_ignore_ascii_case(etiqueta.trim()))
+    }
+}
+
+// Campo personalizado de una entrada (valor cifrado)
+pub struct CustomField {
+    pub nombre: String,
+    pub valor: String,
+    pub protegido: bool,
+}
+
+// Archivo adjunto de una entrada (datos cifrados)
+pub struct Attachment {
+    pub id: String,
+    pub nombre: String,
+    pub datos: String,
+}
+
+// Token de la API local (vecla-cli serve). Del token solo se guarda su hash SHA-256.
+pub struct ApiToken {
+    pub id: String,
+    pub nombre: String,
+    pub hash: String,
+    pub solo_lectura: bool,
+    pub carpetas: String, // separadas por coma, vacía = todas
+    pub creado: String,
+}
+
+// Versión anterior de una entrada
+pub struct HistoryEntry {
+    pub entry_id: String,
+    pub entrada: PasswordEntry,
+}
+
+const ENTRY_COLUMNS: &str = "id, fecha, app, url, usuario, mail, con, notas, carpeta, etiquetas, modificado, con_modificada";
+
+// Columnas de una entrada. Una bóveda antigua abierta en solo lectura no se migra y puede
+// no tener con_modificada.
+fn entry_columns(conn: &Connection, table: &str) -> Result<String> {
+    if has_column(conn, table, "con_modificada")? {
+        Ok(ENTRY_COLUMNS.to_string())
+    } else {
+        Ok(ENTRY_COLUMNS.replace("con_modificada", "NULL"))
+    }
+}
+
+fn entry_from_row(row: &Row) -> Result<PasswordEntry> {
+    let fecha: String = row.get(1)?;
+    let modificado = row.get::<_, Option<String>>(10)?.filter(|m| !m.is_empty()).unwrap_or_else(|| fecha.clone());
+    // Las bóvedas antiguas no guardaban cuándo cambió la contraseña
+    let con_modificada = row.get::<_, Option<String>>(11)?.filter(|m| !m.is_empty()).unwrap_or_else(|| modificado.clone());
+    Ok(PasswordEntry {
+        id: row.get(0)?,
+        app: row.get(2)?,
+        url: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        usuario: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+        mail: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        con: row.get(6)?,
+        notas: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+        carpeta: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+        etiquetas: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+        modificado,
+        con_modificada,
+        fecha,
+    })
 }
 
 pub fn insert_entry(conn: &Connection, entry: &PasswordEntry) -> Result<()> {
     conn.execute(
-        "INSERT INTO passwords (id, fecha, app, url, usuario, mail, con, notas) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        "INSERT INTO passwords (id, fecha, app, url, usuario, mail, con, notas, carpeta, etiquetas, modificado, con_modificada)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             entry.id,
             entry.fecha,
@@ -39,26 +129,19 @@ pub fn insert_entry(conn: &Connection, entry: &PasswordEntry) -> Result<()> {
             entry.usuario,
             entry.mail,
             entry.con,
-            entry.notas
+            entry.notas,
+            entry.carpeta,
+            entry.etiquetas,
+            entry.modificado,
+            entry.con_modificada
         ],
     )?;
     Ok(())
 }
 
 pub fn list_entries(conn: &Connection) -> Result<Vec<PasswordEntry>> {
-    let mut stmt = conn.prepare("SELECT id, fecha, app, url, usuario, mail, con, notas FROM passwords ORDER BY fecha DESC")?;
-    let rows = stmt.query_map([], |row| {
-        Ok(PasswordEntry {
-            id: row.get(0)?,
-            fecha: row.get(1)?,
-            app: row.get(2)?,
-            url: row.get(3)?,
-            usuario: row.get(4)?,
-            mail: row.get(5)?,
-            con: row.get(6)?,
-            notas: row.get(7)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM passwords ORDER BY fecha DESC", entry_columns(conn, "passwords")?))?;
+    let rows = stmt.query_map([], entry_from_row)?;
     let mut entries = Vec::new();
     for entry in rows {
         entries.push(entry?);
@@ -68,21 +151,11 @@ pub fn list_entries(conn: &Connection) -> Result<Vec<PasswordEntry>> {
 
 pub fn search_entries(conn: &Connection, query: &str) -> Result<Vec<PasswordEntry>> {
     let like = format!("%{}%", query);
-    let mut stmt = conn.prepare(
-        "SELECT id, fecha, app, url, usuario, mail, con, notas FROM passwords WHERE app LIKE ?1 OR usuario LIKE ?1 OR mail LIKE ?1 ORDER BY fecha DESC"
-    )?;
-    let rows = stmt.query_map([like], |row| {
-        Ok(PasswordEntry {
-            id: row.get(0)?,
-            fecha: row.get(1)?,
-            app: row.get(2)?,
-            url: row.get(3)?,
-            usuario: row.get(4)?,
-            mail: row.get(5)?,
-            con: row.get(6)?,
-            notas: row.get(7)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM passwords WHERE app LIKE ?1 OR usuario LIKE ?1 OR mail LIKE ?1 ORDER BY fecha DESC",
+        entry_columns(conn, "passwords")?
+    ))?;
+    let rows = stmt.query_map([like], entry_from_row)?;
     let mut entries = Vec::new();
     for entry in rows {
         entries.push(entry?);
@@ -90,13 +163,282 @@ pub fn search_entries(conn: &Connection, query: &str) -> Result<Vec<PasswordEntr
     Ok(entries)
 }
 
+pub fn get_entry(conn: &Connection, id: &str) -> Result<Option<PasswordEntry>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM passwords WHERE id = ?1", entry_columns(conn, "passwords")?))?;
+    let mut rows = stmt.query_map([id], entry_from_row)?;
+    rows.next().transpose()
+}
+
+// Busca entradas por id exacto o, si no hay ninguna, por prefijo del id o nombre de la app
+// (sin distinguir mayúsculas). Puede devolver varias si la referencia es ambigua.
+pub fn find_entries(conn: &Connection, referencia: &str) -> Result<Vec<PasswordEntry>> {
+    if let Some(entrada) = get_entry(conn, referencia)? {
+        return Ok(vec![entrada]);
+    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM passwords WHERE substr(id, 1, length(?1)) = ?1 OR lower(app) = lower(?1) ORDER BY fecha DESC",
+        entry_columns(conn, "passwords")?
+    ))?;
+    let rows = stmt.query_map([referencia], entry_from_row)?;
+    rows.collect()
+}
+
+// Guarda los cambios de una entrada; la versión anterior pasa al historial. Si cambia la
+// contraseña y quien llama no ha fijado con_modificada, se toma la fecha de modificación.
+pub fn update_entry(conn: &Connection, entry: &PasswordEntry) -> Result<()> {
+    let mut con_modificada = entry.con_modificada.clone();
+    if let Some(mut anterior) = get_entry(conn, &entry.id)? {
+        if anterior.con != entry.con && anterior.con_modificada == entry.con_modificada {
+            con_modificada = entry.modificado.clone();
+        }
+        anterior.id = Uuid::new_v4().to_string();
+        insert_history(conn, &HistoryEntry { entry_id: entry.id.clone(), entrada: anterior })?;
+    }
+    conn.execute(
+        "UPDATE passwords SET fecha = ?2, app = ?3, url = ?4, usuario = ?5, mail = ?6, con = ?7,
+         notas = ?8, carpeta = ?9, etiquetas = ?10, modificado = ?11, con_modificada = ?12 WHERE id = ?1",
+        params![
+            entry.id,
+            entry.fecha,
+            entry.app,
+            entry.url,
+            entry.usuario,
+            entry.mail,
+            entry.con,
+            entry.notas,
+            entry.carpeta,
+            entry.etiquetas,
+            entry.modificado,
+            con_modificada
+        ],
+    )?;
+    Ok(())
+}
+
 pub fn delete_entry(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM campos WHERE entry_id = ?1", [id])?;
+    conn.execute("DELETE FROM adjuntos WHERE entry_id = ?1", [id])?;
+    conn.execute("DELETE FROM historial WHERE entry_id = ?1", [id])?;
+    conn.execute("DELETE FROM atributos WHERE entry_id = ?1", [id])?;
     conn.execute("DELETE FROM passwords WHERE id = ?1", [id])?;
     Ok(())
 }
 
-pub fn init_db(path: &str) -> Result<Connection> {
+pub fn insert_field(conn: &Connection, entry_id: &str, field: &CustomField) -> Result<()> {
+    conn.execute(
+        "INSERT INTO campos (entry_id, nombre, valor, protegido) VALUES (?1, ?2, ?3, ?4)",
+        params![entry_id, field.nombre, field.valor, field.protegido],
+    )?;
+    Ok(())
+}
+
+// Borra todos los campos personalizados de una entrada
+pub fn delete_fields(conn: &Connection, entry_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM campos WHERE entry_id = ?1", [entry_id])?;
+    Ok(())
+}
+
+pub fn list_fields(conn: &Connection, entry_id: &str) -> Result<Vec<CustomField>> {
+    let mut stmt = conn.prepare("SELECT nombre, valor, protegido FROM campos WHERE entry_id = ?1 ORDER BY rowid")?;
+    let rows = stmt.query_map([entry_id], |row| {
+        Ok(CustomField {
+            nombre: row.get(0)?,
+            valor: row.get(1)?,
+            protegido: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// Reemplaza los atributos de búsqueda de una entrada (Secret Service). No son secretos
+// y se guardan sin cifrar para poder buscar con la bóveda bloqueada.
+pub fn set_attributes(conn: &Connection, entry_id: &str, atributos: &BTreeMap<String, String>) -> Result<()> {
+    conn.execute("DELETE FROM atributos WHERE entry_id = ?1", [entry_id])?;
+    for (nombre, valor) in atributos {
+        conn.execute(
+            "INSERT INTO atributos (entry_id, nombre, valor) VALUES (?1, ?2, ?3)",
+            params![entry_id, nombre, valor],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn list_attributes(conn: &Connection, entry_id: &str) -> Result<BTreeMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT nombre, valor FROM atributos WHERE entry_id = ?1")?;
+    let rows = stmt.query_map([entry_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+pub fn insert_token(conn: &Connection, token: &ApiToken) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tokens (id, nombre, hash, solo_lectura, carpetas, creado) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![token.id, token.nombre, token.hash, token.solo_lectura, token.carpetas, token.creado],
+    )?;
+    Ok(())
+}
+
+pub fn list_tokens(conn: &Connection) -> Result<Vec<ApiToken>> {
+    let mut stmt = conn.prepare("SELECT id, nombre, hash, solo_lectura, carpetas, creado FROM tokens ORDER BY creado")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ApiToken {
+            id: row.get(0)?,
+            nombre: row.get(1)?,
+            hash: row.get(2)?,
+            solo_lectura: row.get(3)?,
+            carpetas: row.get(4)?,
+            creado: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn delete_token(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM tokens WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+pub fn insert_attachment(conn: &Connection, entry_id: &str, attachment: &Attachment) -> Result<()> {
+    conn.execute(
+        "INSERT INTO adjuntos (id, entry_id, nombre, datos) VALUES (?1, ?2, ?3, ?4)",
+        params![attachment.id, entry_id, attachment.nombre, attachment.datos],
+    )?;
+    Ok(())
+}
+
+pub fn delete_attachments(conn: &Connection, entry_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM adjuntos WHERE entry_id = ?1", [entry_id])?;
+    Ok(())
+}
+
+pub fn list_attachments(conn: &Connection, entry_id: &str) -> Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare("SELECT id, nombre, datos FROM adjuntos WHERE entry_id = ?1 ORDER BY rowid")?;
+    let rows = stmt.query_map([entry_id], |row| {
+        Ok(Attachment {
+            id: row.get(0)?,
+            nombre: row.get(1)?,
+            datos: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn insert_history(conn: &Connection, history: &HistoryEntry) -> Result<()> {
+    let e = &history.entrada;
+    conn.execute(
+        "INSERT INTO historial (id, entry_id, fecha, app, url, usuario, mail, con, notas, carpeta, etiquetas, modificado, con_modificada)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            e.id,
+            history.entry_id,
+            e.fecha,
+            e.app,
+            e.url,
+            e.usuario,
+            e.mail,
+            e.con,
+            e.notas,
+            e.carpeta,
+            e.etiquetas,
+            e.modificado,
+            e.con_modificada
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_history(conn: &Connection, entry_id: &str) -> Result<Vec<HistoryEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, entry_id FROM historial WHERE entry_id = ?1 ORDER BY modificado",
+        entry_columns(conn, "historial")?
+    ))?;
+    let rows = stmt.query_map([entry_id], |row| {
+        Ok(HistoryEntry {
+            entrada: entry_from_row(row)?,
+            entry_id: row.get(12)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columnas = stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<Result<Vec<_>>>()?;
+    Ok(columnas.iter().any(|c| c == column))
+}
+
+// Añade una columna a una tabla existente si todavía no la tiene (bóvedas antiguas)
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
+// Valor conocido que se guarda cifrado con la clave maestra para comprobarla
+const VERIFICADOR: &str = "vecla-verificador";
+
+fn get_meta(conn: &Connection, clave: &str) -> Result<Option<String>> {
+    // Una bóveda antigua abierta en solo lectura no tiene la tabla
+    let existe: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'meta')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !existe {
+        return Ok(None);
+    }
+    let mut stmt = conn.prepare("SELECT valor FROM meta WHERE clave = ?1")?;
+    let mut rows = stmt.query_map([clave], |row| row.get(0))?;
+    rows.next().transpose()
+}
+
+fn set_meta(conn: &Connection, clave: &str, valor: &str) -> Result<()> {
+    conn.execute("INSERT OR REPLACE INTO meta (clave, valor) VALUES (?1, ?2)", params![clave, valor])?;
+    Ok(())
+}
+
+// Guarda el verificador de la clave maestra
+pub fn set_key_verifier(conn: &Connection, master_key: &str) -> Result<()> {
+    let cifrado = encrypt(master_key, VERIFICADOR).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+    set_meta(conn, "verificador", &cifrado)
+}
+
+// La clave es correcta si descifra el verificador. Las bóvedas sin verificador (nuevas
+// o anteriores a él) aceptan la clave si están vacías o si descifra alguna entrada, y
+// a partir de ahí guardan el verificador con ella.
+pub fn check_master_key(conn: &Connection, master_key: &str) -> Result<bool> {
+    if let Some(verificador) = get_meta(conn, "verificador")? {
+        return Ok(decrypt(master_key, &verificador).is_ok_and(|v| v == VERIFICADOR));
+    }
+    let entradas = list_entries(conn)?;
+    let correcta = entradas.is_empty() || entradas.iter().any(|e| decrypt(master_key, &e.con).is_ok());
+    if correcta && !conn.is_readonly(rusqlite::DatabaseName::Main)? {
+        set_key_verifier(conn, master_key)?;
+    }
+    Ok(correcta)
+}
+
+pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
     let conn = Connection::open(path)?;
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+// Abre la bóveda de la configuración. En solo lectura la bóveda debe existir
+// y no se actualiza su esquema; en otro caso se crea su carpeta si hace falta.
+pub fn open_db(config: &Config) -> Result<Connection> {
+    let ruta = config.get_db_path();
+    if config.readonly {
+        return Connection::open_with_flags(&ruta, OpenFlags::SQLITE_OPEN_READ_ONLY);
+    }
+    if let Some(dir) = ruta.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|_| rusqlite::Error::InvalidPath(dir.to_path_buf()))?;
+    }
+    init_db(&ruta)
+}
+
+// Crea las tablas que falten y actualiza el esquema de bóvedas antiguas
+pub fn migrate(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS passwords (
             id TEXT PRIMARY KEY,
@@ -110,5 +452,112 @@ pub fn init_db(path: &str) -> Result<Connection> {
         )",
         [],
     )?;
-    Ok(conn)
-} 
\ No newline at end of file
+    ensure_column(conn, "passwords", "carpeta", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "passwords", "etiquetas", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "passwords", "modificado", "TEXT")?;
+    ensure_column(conn, "passwords", "con_modificada", "TEXT")?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS campos (
+            entry_id TEXT NOT NULL,
+            nombre TEXT NOT NULL,
+            valor TEXT NOT NULL,
+            protegido INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS adjuntos (
+            id TEXT PRIMARY KEY,
+            entry_id TEXT NOT NULL,
+            nombre TEXT NOT NULL,
+            datos TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS historial (
+            id TEXT PRIMARY KEY,
+            entry_id TEXT NOT NULL,
+            fecha TEXT NOT NULL,
+            app TEXT NOT NULL,
+            url TEXT,
+            usuario TEXT,
+            mail TEXT,
+            con TEXT NOT NULL,
+            notas TEXT,
+            carpeta TEXT NOT NULL DEFAULT '',
+            etiquetas TEXT NOT NULL DEFAULT '',
+            modificado TEXT
+        );
+        CREATE TABLE IF NOT EXISTS atributos (
+            entry_id TEXT NOT NULL,
+            nombre TEXT NOT NULL,
+            valor TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS meta (
+            clave TEXT PRIMARY KEY,
+            valor TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS tokens (
+            id TEXT PRIMARY KEY,
+            nombre TEXT NOT NULL,
+            hash TEXT NOT NULL UNIQUE,
+            solo_lectura INTEGER NOT NULL DEFAULT 0,
+            carpetas TEXT NOT NULL DEFAULT '',
+            creado TEXT NOT NULL
+        );",
+    )?;
+    ensure_column(conn, "historial", "con_modificada", "TEXT")?;
+    Ok(())
+}
+
+// Bóveda vacía en memoria para las pruebas de todos los módulos
+#[cfg(test)]
+pub(crate) fn test_vault() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    migrate(&conn).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_vault_rejects_other_keys() {
+        let conn = test_vault();
+        assert!(check_master_key(&conn, "buena").unwrap());
+        assert!(!check_master_key(&conn, "mala").unwrap());
+        assert!(check_master_key(&conn, "buena").unwrap());
+    }
+
+    #[test]
+    fn password_change_date_only_moves_with_the_password() {
+        let conn = test_vault();
+        let mut entrada = PasswordEntry::new("app", "", "", "", &encrypt("k", "uno").unwrap(), "");
+        entrada.modificado = "2020-01-01T00:00:00+00:00".to_string();
+        entrada.con_modificada = entrada.modificado.clone();
+        insert_entry(&conn, &entrada).unwrap();
+
+        entrada.notas = "solo notas".to_string();
+        entrada.modificado = "2021-01-01T00:00:00+00:00".to_string();
+        update_entry(&conn, &entrada).unwrap();
+        let guardada = get_entry(&conn, &entrada.id).unwrap().unwrap();
+        assert_eq!(guardada.con_modificada, "2020-01-01T00:00:00+00:00");
+
+        entrada.con = encrypt("k", "dos").unwrap();
+        entrada.modificado = "2022-01-01T00:00:00+00:00".to_string();
+        update_entry(&conn, &entrada).unwrap();
+        let guardada = get_entry(&conn, &entrada.id).unwrap().unwrap();
+        assert_eq!(guardada.con_modificada, "2022-01-01T00:00:00+00:00");
+        assert_eq!(list_history(&conn, &entrada.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn legacy_vault_gets_verifier() {
+        let conn = test_vault();
+        let entrada = PasswordEntry::new("app", "", "", "", &encrypt("buena", "x").unwrap(), "");
+        insert_entry(&conn, &entrada).unwrap();
+        assert!(!check_master_key(&conn, "mala").unwrap());
+        assert!(get_meta(&conn, "verificador").unwrap().is_none());
+        assert!(check_master_key(&conn, "buena").unwrap());
+        assert!(get_meta(&conn, "verificador").unwrap().is_some());
+        // Con el verificador ya no cuentan las entradas
+        delete_entry(&conn, &entrada.id).unwrap();
+        assert!(!check_master_key(&conn, "mala").unwrap());
+    }
+}
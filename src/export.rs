@@ -0,0 +1,597 @@
+// Exportación e importación de la bóveda completa en un archivo cifrado portable
+//
+// Formato del archivo:
+//   "VECLAEXP" | versión (u8) | memoria KiB (u32 LE) | iteraciones (u32 LE) | paralelismo (u32 LE)
+//   | sal (16 bytes) | nonce (12 bytes) | AES-256-GCM(JSON comprimido con gzip)
+// La cabecera completa se autentica como datos adicionales del cifrado.
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use rand::RngCore;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use uuid::Uuid;
+use crate::crypto::{encrypt, decrypt, encrypt_bytes, decrypt_bytes};
+use crate::db::{
+    PasswordEntry, CustomField, Attachment, HistoryEntry, delete_attachments, delete_entry, delete_fields, get_entry,
+    insert_entry, insert_field, insert_attachment, insert_history, list_entries, list_fields, list_attachments,
+    list_history, update_entry,
+};
+
+const MAGIA: &[u8; 8] = b"VECLAEXP";
+const VERSION_FORMATO: u8 = 1;
+const LARGO_CABECERA: usize = 8 + 1 + 12 + 16 + 12;
+
+// Parámetros de Argon2id para archivos nuevos
+const ARGON2_MEMORIA_KIB: u32 = 64 * 1024;
+const ARGON2_ITERACIONES: u32 = 3;
+const ARGON2_PARALELISMO: u32 = 1;
+// Límites al leer, para no agotar la memoria ni bloquear la importación con un archivo manipulado
+const ARGON2_MEMORIA_MAXIMA_KIB: u32 = 1024 * 1024;
+const ARGON2_ITERACIONES_MAXIMAS: u32 = 100;
+const ARGON2_PARALELISMO_MAXIMO: u32 = 16;
+
+#[derive(Serialize, Deserialize)]
+struct Archivo {
+    version: u8,
+    creado: String,
+    entradas: Vec<EntradaExportada>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntradaExportada {
+    id: String,
+    fecha: String,
+    modificado: String,
+    #[serde(default)]
+    con_modificada: String,
+    app: String,
+    url: String,
+    usuario: String,
+    mail: String,
+    con: String,
+    notas: String,
+    carpeta: String,
+    etiquetas: String,
+    #[serde(default)]
+    campos: Vec<CampoExportado>,
+    #[serde(default)]
+    adjuntos: Vec<AdjuntoExportado>,
+    #[serde(default)]
+    historial: Vec<EntradaExportada>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CampoExportado {
+    nombre: String,
+    valor: String,
+    protegido: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AdjuntoExportado {
+    nombre: String,
+    datos: String, // base64
+}
+
+// Cómo se incorpora un archivo a la bóveda actual
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportMode {
+    Merge,   // añade las nuevas y actualiza las que sean más recientes en el archivo
+    Replace, // borra la bóveda actual y deja solo el contenido del archivo
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub nuevas: usize,
+    pub actualizadas: usize,
+    pub omitidas: usize,
+}
+
+fn derive_export_key(password: &str, sal: &[u8], memoria: u32, iteraciones: u32, paralelismo: u32) -> Result<[u8; 32], String> {
+    let params = Params::new(memoria, iteraciones, paralelismo, Some(32))
+        .map_err(|e| format!("Parámetros de derivación inválidos: {}", e))?;
+    let mut clave = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), sal, &mut clave)
+        .map_err(|e| e.to_string())?;
+    Ok(clave)
+}
+
+fn entry_to_export(entrada: &PasswordEntry, master_key: &str) -> Result<EntradaExportada, String> {
+    Ok(EntradaExportada {
+        id: entrada.id.clone(),
+        fecha: entrada.fecha.clone(),
+        modificado: entrada.modificado.clone(),
+        con_modificada: entrada.con_modificada.clone(),
+        app: entrada.app.clone(),
+        url: entrada.url.clone(),
+        usuario: entrada.usuario.clone(),
+        mail: entrada.mail.clone(),
+        con: decrypt(master_key, &entrada.con)?,
+        notas: entrada.notas.clone(),
+        carpeta: entrada.carpeta.clone(),
+        etiquetas: entrada.etiquetas.clone(),
+        campos: Vec::new(),
+        adjuntos: Vec::new(),
+        historial: Vec::new(),
+    })
+}
+
+fn export_to_entry(exportada: &EntradaExportada, master_key: &str) -> Result<PasswordEntry, String> {
+    let mut entrada = PasswordEntry::new(
+        &exportada.app,
+        &exportada.url,
+        &exportada.usuario,
+        &exportada.mail,
+        &encrypt(master_key, &exportada.con)?,
+        &exportada.notas,
+    );
+    entrada.id = exportada.id.clone();
+    entrada.fecha = exportada.fecha.clone();
+    entrada.modificado = exportada.modificado.clone();
+    // Los archivos anteriores no la incluyen
+    entrada.con_modificada = if exportada.con_modificada.is_empty() {
+        exportada.modificado.clone()
+    } else {
+        exportada.con_modificada.clone()
+    };
+    entrada.carpeta = exportada.carpeta.clone();
+    entrada.etiquetas = exportada.etiquetas.clone();
+    Ok(entrada)
+}
+
+fn collect_vault(conn: &Connection, master_key: &str) -> Result<Archivo, String> {
+    let mut entradas = Vec::new();
+    for entrada in list_entries(conn).map_err(|e| e.to_string())? {
+        let mut exportada = entry_to_export(&entrada, master_key)?;
+        for campo in list_fields(conn, &entrada.id).map_err(|e| e.to_string())? {
+            exportada.campos.push(CampoExportado {
+                nombre: campo.nombre,
+                valor: decrypt(master_key, &campo.valor)?,
+                protegido: campo.protegido,
+            });
+        }
+        for adjunto in list_attachments(conn, &entrada.id).map_err(|e| e.to_string())? {
+            exportada.adjuntos.push(AdjuntoExportado {
+                nombre: adjunto.nombre,
+                datos: STANDARD.encode(decrypt_bytes(master_key, &adjunto.datos)?),
+            });
+        }
+        for anterior in list_history(conn, &entrada.id).map_err(|e| e.to_string())? {
+            exportada.historial.push(entry_to_export(&anterior.entrada, master_key)?);
+        }
+        entradas.push(exportada);
+    }
+    Ok(Archivo { version: VERSION_FORMATO, creado: Utc::now().to_rfc3339(), entradas })
+}
+
+// Genera el contenido del archivo cifrado con toda la bóveda
+pub fn export_vault(conn: &Connection, master_key: &str, password: &str) -> Result<Vec<u8>, String> {
+    if password.is_empty() {
+        return Err("La contraseña del archivo no puede estar vacía".to_string());
+    }
+    let archivo = collect_vault(conn, master_key)?;
+    let json = serde_json::to_vec(&archivo).map_err(|e| e.to_string())?;
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&json).map_err(|e| e.to_string())?;
+    let comprimido = gz.finish().map_err(|e| e.to_string())?;
+
+    let mut sal = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut sal);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut cabecera = Vec::with_capacity(LARGO_CABECERA);
+    cabecera.extend_from_slice(MAGIA);
+    cabecera.push(VERSION_FORMATO);
+    cabecera.extend(ARGON2_MEMORIA_KIB.to_le_bytes());
+    cabecera.extend(ARGON2_ITERACIONES.to_le_bytes());
+    cabecera.extend(ARGON2_PARALELISMO.to_le_bytes());
+    cabecera.extend(sal);
+    cabecera.extend(nonce);
+
+    let clave = derive_export_key(password, &sal, ARGON2_MEMORIA_KIB, ARGON2_ITERACIONES, ARGON2_PARALELISMO)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&clave));
+    let cifrado = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &comprimido, aad: &cabecera })
+        .map_err(|e| e.to_string())?;
+
+    let mut salida = cabecera;
+    salida.extend(cifrado);
+    Ok(salida)
+}
+
+fn read_archive(datos: &[u8], password: &str) -> Result<Archivo, String> {
+    if datos.len() < LARGO_CABECERA || &datos[..8] != MAGIA {
+        return Err("El archivo no es una exportación de Vecla".to_string());
+    }
+    if datos[8] != VERSION_FORMATO {
+        return Err(format!("Versión de exportación no soportada: {}", datos[8]));
+    }
+    let leer_u32 = |pos: usize| u32::from_le_bytes(datos[pos..pos + 4].try_into().unwrap());
+    let (memoria, iteraciones, paralelismo) = (leer_u32(9), leer_u32(13), leer_u32(17));
+    if memoria > ARGON2_MEMORIA_MAXIMA_KIB
+        || iteraciones > ARGON2_ITERACIONES_MAXIMAS
+        || paralelismo > ARGON2_PARALELISMO_MAXIMO
+    {
+        return Err("Parámetros de derivación fuera de rango".to_string());
+    }
+    let sal = &datos[21..37];
+    let nonce = &datos[37..49];
+    let (cabecera, cifrado) = datos.split_at(LARGO_CABECERA);
+
+    let clave = derive_export_key(password, sal, memoria, iteraciones, paralelismo)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&clave));
+    let comprimido = cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: cifrado, aad: cabecera })
+        .map_err(|_| "Contraseña incorrecta o archivo dañado".to_string())?;
+    let mut json = Vec::new();
+    GzDecoder::new(comprimido.as_slice())
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Error al descomprimir: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Contenido inválido: {}", e))
+}
+
+fn insert_exported(conn: &Connection, master_key: &str, exportada: &EntradaExportada) -> Result<(), String> {
+    insert_entry(conn, &export_to_entry(exportada, master_key)?).map_err(|e| e.to_string())?;
+    insert_contents(conn, master_key, exportada)?;
+    for anterior in &exportada.historial {
+        insert_exported_history(conn, master_key, &exportada.id, anterior)?;
+    }
+    Ok(())
+}
+
+// Actualiza una entrada existente con la versión del archivo. La versión local pasa al
+// historial y se conserva su historial; del archivo se añaden las versiones que falten.
+fn update_exported(
+    conn: &Connection,
+    master_key: &str,
+    exportada: &EntradaExportada,
+    actual: &PasswordEntry,
+) -> Result<(), String> {
+    let mut entrada = export_to_entry(exportada, master_key)?;
+    // Misma contraseña: se conserva el cifrado local para que no cuente como un cambio
+    if decrypt(master_key, &actual.con).is_ok_and(|con| con == exportada.con) {
+        entrada.con = actual.con.clone();
+    }
+    update_entry(conn, &entrada).map_err(|e| e.to_string())?;
+    delete_fields(conn, &exportada.id).map_err(|e| e.to_string())?;
+    delete_attachments(conn, &exportada.id).map_err(|e| e.to_string())?;
+    insert_contents(conn, master_key, exportada)?;
+    let locales = list_history(conn, &exportada.id).map_err(|e| e.to_string())?;
+    for anterior in &exportada.historial {
+        if !locales.iter().any(|h| h.entrada.modificado == anterior.modificado) {
+            insert_exported_history(conn, master_key, &exportada.id, anterior)?;
+        }
+    }
+    Ok(())
+}
+
+// Campos personalizados y adjuntos de una entrada exportada
+fn insert_contents(conn: &Connection, master_key: &str, exportada: &EntradaExportada) -> Result<(), String> {
+    for campo in &exportada.campos {
+        let campo = CustomField {
+            nombre: campo.nombre.clone(),
+            valor: encrypt(master_key, &campo.valor)?,
+            protegido: campo.protegido,
+        };
+        insert_field(conn, &exportada.id, &campo).map_err(|e| e.to_string())?;
+    }
+    for adjunto in &exportada.adjuntos {
+        let datos = STANDARD.decode(&adjunto.datos).map_err(|e| e.to_string())?;
+        let adjunto = Attachment {
+            id: Uuid::new_v4().to_string(),
+            nombre: adjunto.nombre.clone(),
+            datos: encrypt_bytes(master_key, &datos)?,
+        };
+        insert_attachment(conn, &exportada.id, &adjunto).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn insert_exported_history(
+    conn: &Connection,
+    master_key: &str,
+    entry_id: &str,
+    anterior: &EntradaExportada,
+) -> Result<(), String> {
+    let mut version = export_to_entry(anterior, master_key)?;
+    version.id = Uuid::new_v4().to_string();
+    insert_history(conn, &HistoryEntry { entry_id: entry_id.to_string(), entrada: version }).map_err(|e| e.to_string())
+}
+
+fn is_newer(a: &str, b: &str) -> bool {
+    match (DateTime::parse_from_rfc3339(a), DateTime::parse_from_rfc3339(b)) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a > b,
+    }
+}
+
+// Incorpora un archivo exportado a la bóveda en una única transacción
+pub fn import_vault(
+    conn: &mut Connection,
+    master_key: &str,
+    datos: &[u8],
+    password: &str,
+    modo: ImportMode,
+) -> Result<ImportSummary, String> {
+    let archivo = read_archive(datos, password)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut resumen = ImportSummary::default();
+
+    if modo == ImportMode::Replace {
+        for entrada in list_entries(&tx).map_err(|e| e.to_string())? {
+            delete_entry(&tx, &entrada.id).map_err(|e| e.to_string())?;
+        }
+    }
+    for exportada in &archivo.entradas {
+        match get_entry(&tx, &exportada.id).map_err(|e| e.to_string())? {
+            // Se conserva la versión más reciente
+            Some(actual) if !is_newer(&exportada.modificado, &actual.modificado) => resumen.omitidas += 1,
+            Some(actual) => {
+                update_exported(&tx, master_key, exportada, &actual)?;
+                resumen.actualizadas += 1;
+            }
+            None => {
+                insert_exported(&tx, master_key, exportada)?;
+                resumen.nuevas += 1;
+            }
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(resumen)
+}
+
+// ---------------------------------------------------------------------------
+// Exportación en texto plano (auditorías y migraciones)
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaintextFormat {
+    Json,
+    Csv,
+}
+
+// Campos que se pueden incluir, en el orden en que se escriben
+pub const PLAINTEXT_FIELDS: [&str; 11] = [
+    "id", "fecha", "modificado", "app", "url", "usuario", "mail", "con", "notas", "carpeta", "etiquetas",
+];
+
+pub struct PlaintextOptions {
+    pub formato: PlaintextFormat,
+    pub campos: Vec<String>,
+    pub carpeta: Option<String>, // incluye subcarpetas
+    pub etiqueta: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EntradaPlana {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fecha: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modificado: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usuario: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    con: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notas: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    carpeta: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etiquetas: Option<String>,
+}
+
+// Devuelve el contenido del archivo con las entradas DESCIFRADAS. Quien llame es
+// responsable de haber pedido confirmación explícita al usuario.
+pub fn export_plaintext(conn: &Connection, master_key: &str, opciones: &PlaintextOptions) -> Result<String, String> {
+    for campo in &opciones.campos {
+        if !PLAINTEXT_FIELDS.contains(&campo.as_str()) {
+            return Err(format!("Campo desconocido: {}", campo));
+        }
+    }
+    if opciones.campos.is_empty() {
+        return Err("Selecciona al menos un campo".to_string());
+    }
+    let incluir = |campo: &str| opciones.campos.iter().any(|c| c == campo);
+
+    let mut filas = Vec::new();
+    for entrada in list_entries(conn).map_err(|e| e.to_string())? {
+        if let Some(carpeta) = &opciones.carpeta {
+            if !entrada.in_folder(carpeta) {
+                continue;
+            }
+        }
+        if let Some(etiqueta) = &opciones.etiqueta {
+            if !entrada.has_tag(etiqueta) {
+                continue;
+            }
+        }
+        let con = if incluir("con") { Some(decrypt(master_key, &entrada.con)?) } else { None };
+        let valor = |campo: &str, texto: &String| incluir(campo).then(|| texto.clone());
+        filas.push(EntradaPlana {
+            id: valor("id", &entrada.id),
+            fecha: valor("fecha", &entrada.fecha),
+            modificado: valor("modificado", &entrada.modificado),
+            app: valor("app", &entrada.app),
+            url: valor("url", &entrada.url),
+            usuario: valor("usuario", &entrada.usuario),
+            mail: valor("mail", &entrada.mail),
+            con,
+            notas: valor("notas", &entrada.notas),
+            carpeta: valor("carpeta", &entrada.carpeta),
+            etiquetas: valor("etiquetas", &entrada.etiquetas),
+        });
+    }
+
+    match opciones.formato {
+        PlaintextFormat::Json => serde_json::to_string_pretty(&filas).map_err(|e| e.to_string()),
+        PlaintextFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            let cabecera: Vec<&str> = PLAINTEXT_FIELDS.iter().copied().filter(|c| incluir(c)).collect();
+            writer.write_record(&cabecera).map_err(|e| e.to_string())?;
+            for fila in &filas {
+                let valores = [
+                    &fila.id, &fila.fecha, &fila.modificado, &fila.app, &fila.url, &fila.usuario,
+                    &fila.mail, &fila.con, &fila.notas, &fila.carpeta, &fila.etiquetas,
+                ];
+                let registro: Vec<&str> = valores.iter().filter_map(|v| v.as_deref()).collect();
+                writer.write_record(&registro).map_err(|e| e.to_string())?;
+            }
+            let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_vault;
+
+    const CLAVE: &str = "maestra";
+    const CLAVE_ARCHIVO: &str = "archivo";
+
+    // Entrada con un campo protegido, un adjunto y una versión anterior
+    fn add_entry(conn: &Connection, app: &str, con: &str, modificado: &str) -> PasswordEntry {
+        let mut entrada = PasswordEntry::new(app, "https://ejemplo.com", "ana", "", &encrypt(CLAVE, con).unwrap(), "nota");
+        entrada.modificado = modificado.to_string();
+        insert_entry(conn, &entrada).unwrap();
+        let campo = CustomField { nombre: "pin".to_string(), valor: encrypt(CLAVE, "1234").unwrap(), protegido: true };
+        insert_field(conn, &entrada.id, &campo).unwrap();
+        let adjunto = Attachment {
+            id: Uuid::new_v4().to_string(),
+            nombre: "clave.txt".to_string(),
+            datos: encrypt_bytes(CLAVE, b"\x00secreto\xff").unwrap(),
+        };
+        insert_attachment(conn, &entrada.id, &adjunto).unwrap();
+        let mut anterior = entrada.clone();
+        anterior.id = Uuid::new_v4().to_string();
+        anterior.con = encrypt(CLAVE, "antigua").unwrap();
+        anterior.modificado = "2020-01-01T00:00:00+00:00".to_string();
+        insert_history(conn, &HistoryEntry { entry_id: entrada.id.clone(), entrada: anterior }).unwrap();
+        entrada
+    }
+
+    fn history_passwords(conn: &Connection, id: &str) -> Vec<String> {
+        list_history(conn, id).unwrap().iter().map(|h| decrypt(CLAVE, &h.entrada.con).unwrap()).collect()
+    }
+
+    #[test]
+    fn replace_restores_fields_attachments_and_history() {
+        let origen = test_vault();
+        let entrada = add_entry(&origen, "correo", "nueva", "2024-01-01T00:00:00+00:00");
+        let datos = export_vault(&origen, CLAVE, CLAVE_ARCHIVO).unwrap();
+
+        let mut destino = test_vault();
+        add_entry(&destino, "otra", "x", "2024-01-01T00:00:00+00:00");
+        let resumen = import_vault(&mut destino, CLAVE, &datos, CLAVE_ARCHIVO, ImportMode::Replace).unwrap();
+        assert_eq!((resumen.nuevas, resumen.actualizadas, resumen.omitidas), (1, 0, 0));
+
+        let entradas = list_entries(&destino).unwrap();
+        assert_eq!(entradas.len(), 1);
+        let importada = &entradas[0];
+        assert_eq!(importada.id, entrada.id);
+        assert_eq!(importada.modificado, entrada.modificado);
+        assert_eq!(decrypt(CLAVE, &importada.con).unwrap(), "nueva");
+
+        let campos = list_fields(&destino, &entrada.id).unwrap();
+        assert_eq!(campos.len(), 1);
+        assert_eq!(campos[0].nombre, "pin");
+        assert!(campos[0].protegido);
+        assert_eq!(decrypt(CLAVE, &campos[0].valor).unwrap(), "1234");
+
+        let adjuntos = list_attachments(&destino, &entrada.id).unwrap();
+        assert_eq!(adjuntos.len(), 1);
+        assert_eq!(adjuntos[0].nombre, "clave.txt");
+        assert_eq!(decrypt_bytes(CLAVE, &adjuntos[0].datos).unwrap(), b"\x00secreto\xff");
+
+        assert_eq!(history_passwords(&destino, &entrada.id), ["antigua"]);
+    }
+
+    #[test]
+    fn merge_keeps_newest_and_local_history() {
+        let origen = test_vault();
+        let entrada = add_entry(&origen, "correo", "del archivo", "2024-06-01T00:00:00+00:00");
+        let datos = export_vault(&origen, CLAVE, CLAVE_ARCHIVO).unwrap();
+
+        // Destino con la misma entrada en una versión más antigua y su propio historial
+        let mut destino = test_vault();
+        let mut local = entrada.clone();
+        local.con = encrypt(CLAVE, "local").unwrap();
+        local.modificado = "2024-01-01T00:00:00+00:00".to_string();
+        insert_entry(&destino, &local).unwrap();
+        let mut version_local = local.clone();
+        version_local.id = Uuid::new_v4().to_string();
+        version_local.con = encrypt(CLAVE, "historial local").unwrap();
+        version_local.modificado = "2023-01-01T00:00:00+00:00".to_string();
+        insert_history(&destino, &HistoryEntry { entry_id: local.id.clone(), entrada: version_local }).unwrap();
+        let solo_local = add_entry(&destino, "solo local", "x", "2024-01-01T00:00:00+00:00");
+
+        let resumen = import_vault(&mut destino, CLAVE, &datos, CLAVE_ARCHIVO, ImportMode::Merge).unwrap();
+        assert_eq!((resumen.nuevas, resumen.actualizadas, resumen.omitidas), (0, 1, 0));
+        assert!(get_entry(&destino, &solo_local.id).unwrap().is_some());
+
+        let actualizada = get_entry(&destino, &entrada.id).unwrap().unwrap();
+        assert_eq!(decrypt(CLAVE, &actualizada.con).unwrap(), "del archivo");
+        assert_eq!(list_fields(&destino, &entrada.id).unwrap().len(), 1);
+        assert_eq!(list_attachments(&destino, &entrada.id).unwrap().len(), 1);
+        // Historial ordenado por fecha de modificación: el del archivo, el local y la versión sustituida
+        assert_eq!(history_passwords(&destino, &entrada.id), ["antigua", "historial local", "local"]);
+
+        // Volver a importar no cambia nada: la versión local ya es la más reciente
+        let resumen = import_vault(&mut destino, CLAVE, &datos, CLAVE_ARCHIVO, ImportMode::Merge).unwrap();
+        assert_eq!((resumen.nuevas, resumen.actualizadas, resumen.omitidas), (0, 0, 1));
+        assert_eq!(list_history(&destino, &entrada.id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn rejects_wrong_password() {
+        let origen = test_vault();
+        add_entry(&origen, "correo", "secreta", "2024-01-01T00:00:00+00:00");
+        let datos = export_vault(&origen, CLAVE, CLAVE_ARCHIVO).unwrap();
+        let mut destino = test_vault();
+        let error = import_vault(&mut destino, CLAVE, &datos, "otra", ImportMode::Merge).unwrap_err();
+        assert_eq!(error, "Contraseña incorrecta o archivo dañado");
+        assert!(list_entries(&destino).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_tampered_header() {
+        let origen = test_vault();
+        add_entry(&origen, "correo", "secreta", "2024-01-01T00:00:00+00:00");
+        let datos = export_vault(&origen, CLAVE, CLAVE_ARCHIVO).unwrap();
+
+        // Un byte de la sal: la cabecera está autenticada
+        let mut manipulado = datos.clone();
+        manipulado[25] ^= 1;
+        assert!(read_archive(&manipulado, CLAVE_ARCHIVO).is_err());
+
+        let mut manipulado = datos.clone();
+        manipulado[..8].copy_from_slice(b"OTROFORM");
+        assert_eq!(read_archive(&manipulado, CLAVE_ARCHIVO).err().unwrap(), "El archivo no es una exportación de Vecla");
+
+        // Parámetros de Argon2 desorbitados: se rechazan antes de derivar la clave
+        for pos in [9, 13, 17] {
+            let mut manipulado = datos.clone();
+            manipulado[pos..pos + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert_eq!(read_archive(&manipulado, CLAVE_ARCHIVO).err().unwrap(), "Parámetros de derivación fuera de rango");
+        }
+    }
+}
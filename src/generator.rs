@@ -0,0 +1,189 @@
+// Generador de contraseñas con políticas: longitud, clases de caracteres, al menos uno de
+// cada clase elegida, exclusión de caracteres ambiguos y símbolos a medida. Las políticas
+// con nombre se guardan como perfiles en la configuración (password_profiles).
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const SIMBOLOS_POR_DEFECTO: &str = "!@#$%^&*()-_=+";
+// Se confunden entre sí con muchas tipografías
+pub const AMBIGUOS: &str = "0OoIl1|";
+
+const MINUSCULAS: &str = "abcdefghijklmnopqrstuvwxyz";
+const MAYUSCULAS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITOS: &str = "0123456789";
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub symbol_set: String,       // símbolos permitidos
+    pub exclude_ambiguous: bool,  // sin 0/O, l/1/I...
+    pub require_each: bool,       // al menos un carácter de cada clase elegida
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            symbol_set: SIMBOLOS_POR_DEFECTO.to_string(),
+            exclude_ambiguous: false,
+            require_each: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn with_length(length: usize) -> Self {
+        Self { length, ..Self::default() }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.symbols && self.symbol_set.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err("Los símbolos no pueden incluir espacios ni caracteres de control".to_string());
+        }
+        let clases = self.classes();
+        if clases.is_empty() {
+            return Err("Elige al menos un tipo de carácter".to_string());
+        }
+        // Antes que el mínimo general, que lo taparía: con cuatro clases ambos coinciden
+        if self.require_each && clases.len() > self.length {
+            return Err(format!("Con {} tipos de caracteres obligatorios la longitud mínima es {}", clases.len(), clases.len()));
+        }
+        if !(4..=512).contains(&self.length) {
+            return Err("La longitud debe estar entre 4 y 512".to_string());
+        }
+        Ok(())
+    }
+
+    // Caracteres de cada clase elegida, sin ambiguos si se piden y sin repetidos
+    fn classes(&self) -> Vec<Vec<char>> {
+        let mut vistos = Vec::new();
+        let mut clases = Vec::new();
+        let elegidas = [
+            (self.lowercase, MINUSCULAS),
+            (self.uppercase, MAYUSCULAS),
+            (self.digits, DIGITOS),
+            (self.symbols, self.symbol_set.as_str()),
+        ];
+        for (_, caracteres) in elegidas.iter().filter(|(activa, _)| *activa) {
+            let clase: Vec<char> = caracteres
+                .chars()
+                .filter(|c| !(self.exclude_ambiguous && AMBIGUOS.contains(*c)))
+                .filter(|c| {
+                    let nuevo = !vistos.contains(c);
+                    vistos.push(*c);
+                    nuevo
+                })
+                .collect();
+            // Una clase que se queda vacía (p. ej. símbolos sin ninguno) no cuenta
+            if !clase.is_empty() {
+                clases.push(clase);
+            }
+        }
+        clases
+    }
+
+    // Tamaño del alfabeto y bits de entropía de una contraseña generada con esta política
+    pub fn entropy_bits(&self) -> f64 {
+        let alfabeto: usize = self.classes().iter().map(Vec::len).sum();
+        if alfabeto == 0 {
+            0.0
+        } else {
+            self.length as f64 * (alfabeto as f64).log2()
+        }
+    }
+
+    pub fn generate(&self) -> Result<String, String> {
+        self.validate()?;
+        let clases = self.classes();
+        let todos: Vec<char> = clases.concat();
+        let mut rng = rand::thread_rng();
+        let mut caracteres: Vec<char> = Vec::with_capacity(self.length);
+        if self.require_each {
+            for clase in &clases {
+                caracteres.push(clase[rng.gen_range(0..clase.len())]);
+            }
+        }
+        while caracteres.len() < self.length {
+            caracteres.push(todos[rng.gen_range(0..todos.len())]);
+        }
+        // Los obligatorios no pueden quedar siempre al principio
+        caracteres.shuffle(&mut rng);
+        Ok(caracteres.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains_any(texto: &str, caracteres: &str) -> bool {
+        texto.chars().any(|c| caracteres.contains(c))
+    }
+
+    #[test]
+    fn require_each_includes_every_chosen_class() {
+        let politica = PasswordPolicy { length: 4, symbol_set: "#".to_string(), ..PasswordPolicy::default() };
+        for _ in 0..200 {
+            let con = politica.generate().unwrap();
+            assert_eq!(con.chars().count(), 4);
+            assert!(contains_any(&con, MINUSCULAS));
+            assert!(contains_any(&con, MAYUSCULAS));
+            assert!(contains_any(&con, DIGITOS));
+            assert!(con.contains('#'));
+        }
+        // Las clases no elegidas no aparecen
+        let politica = PasswordPolicy { digits: false, symbols: false, ..PasswordPolicy::with_length(64) };
+        let con = politica.generate().unwrap();
+        assert!(!contains_any(&con, DIGITOS) && !contains_any(&con, SIMBOLOS_POR_DEFECTO));
+    }
+
+    #[test]
+    fn excludes_ambiguous_characters() {
+        let politica = PasswordPolicy {
+            exclude_ambiguous: true,
+            symbol_set: format!("{}|", SIMBOLOS_POR_DEFECTO),
+            ..PasswordPolicy::with_length(512)
+        };
+        for _ in 0..20 {
+            assert!(!contains_any(&politica.generate().unwrap(), AMBIGUOS));
+        }
+    }
+
+    #[test]
+    fn custom_symbols_overlapping_other_classes_are_deduplicated() {
+        let politica = PasswordPolicy { symbol_set: "aB3!!?".to_string(), ..PasswordPolicy::default() };
+        let clases = politica.classes();
+        assert_eq!(clases.len(), 4);
+        assert_eq!(clases[3], ['!', '?']);
+        assert_eq!(politica.entropy_bits(), 16.0 * 64f64.log2());
+
+        // Símbolos que ya están en otras clases no forman una clase obligatoria
+        let politica = PasswordPolicy { digits: false, symbol_set: "xY".to_string(), ..PasswordPolicy::default() };
+        assert_eq!(politica.classes().len(), 2);
+    }
+
+    #[test]
+    fn validate_rejects_invalid_policies() {
+        assert!(PasswordPolicy::default().validate().is_ok());
+        let error = PasswordPolicy::with_length(3).validate().unwrap_err();
+        assert_eq!(error, "Con 4 tipos de caracteres obligatorios la longitud mínima es 4");
+        let sin_obligatorias = PasswordPolicy { require_each: false, ..PasswordPolicy::with_length(3) };
+        assert_eq!(sin_obligatorias.validate().unwrap_err(), "La longitud debe estar entre 4 y 512");
+        assert!(PasswordPolicy::with_length(513).validate().is_err());
+        let ninguna =
+            PasswordPolicy { lowercase: false, uppercase: false, digits: false, symbols: false, ..PasswordPolicy::default() };
+        assert!(ninguna.validate().is_err());
+        let con_espacio = PasswordPolicy { symbol_set: "! ".to_string(), ..PasswordPolicy::default() };
+        assert!(con_espacio.validate().is_err());
+    }
+}
@@ -1,17 +1,38 @@
 use rusqlite::Connection;
-use crate::db::{PasswordEntry, insert_entry, list_entries, delete_entry, search_entries};
-use crate::crypto::{encrypt, decrypt, generate_password};
-use std::ffi::OsStr;
-use std::os::windows::ffi::OsStrExt;
-use std::ptr;
-use winapi::um::winuser::{OpenClipboard, EmptyClipboard, SetClipboardData, CloseClipboard};
-use winapi::um::winbase::{GlobalAlloc, GlobalLock, GlobalUnlock, GlobalFree};
-use winapi::um::winuser::CF_UNICODETEXT;
-use winapi::um::winnt::HANDLE;
-use winapi::um::minwinbase::GMEM_MOVEABLE;
-
-pub fn run(conn: Connection, master_key: String) {
+use vecla::db::{PasswordEntry, insert_entry, list_entries, delete_entry, search_entries};
+use vecla::crypto::{encrypt, decrypt, compose_master_key};
+use vecla::generator::PasswordPolicy;
+use vecla::passphrase::{self, PassphraseOptions, Wordlist};
+use vecla::kdbx;
+use vecla::config::Config;
+use vecla::backup::{BackupInfo, auto_backup, create_backup, list_backups, restore_backup, verify_backup};
+use std::path::{Path, PathBuf};
+use vecla::export::{
+    ImportMode, PlaintextFormat, PlaintextOptions, PLAINTEXT_FIELDS, export_vault, import_vault, export_plaintext,
+};
+use vecla::import::{ImportFormat, ImportedEntry, detect_format, parse, mark_duplicates, commit_import};
+use vecla::clipboard::{self, SecretClipboard};
+use vecla::screen_lock;
+use vecla::audit::{self, AuditReport, EntryRef};
+use vecla::strength::{self, Strength};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+use subtle::ConstantTimeEq;
+use unlock::LockScreen;
+
+mod unlock;
+
+// Lo que se muestra en lugar de una contraseña oculta (no revela su longitud)
+const MASCARA: &str = "••••••••";
+// Nombre en el selector de listas de la lista configurada en passphrase_wordlist
+const LISTA_ARCHIVO: &str = "archivo";
+
+pub fn run(config: Config) {
     let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Gestor de Contraseñas"),
         renderer: eframe::Renderer::default(),  // Elegir automáticamente el mejor renderer disponible
         follow_system_theme: true,              // Seguir el tema del sistema
         default_theme: eframe::Theme::Dark,     // Tema por defecto si no se detecta el del sistema
@@ -21,15 +42,137 @@ pub fn run(conn: Connection, master_key: String) {
     eframe::run_native(
         "Gestor de Contraseñas",
         options,
-        Box::new(|_cc| Box::new(PasswordApp::new(conn, master_key))),
+        Box::new(|cc| {
+            // El aviso llega desde otro hilo: se anota y se despierta la interfaz
+            let session_locked = Arc::new(AtomicBool::new(false));
+            let aviso = Arc::clone(&session_locked);
+            let ctx = cc.egui_ctx.clone();
+            screen_lock::watch(move || {
+                aviso.store(true, Ordering::SeqCst);
+                ctx.request_repaint();
+            });
+            Box::new(VeclaApp { lock: LockScreen::new(config), app: None, last_activity: Instant::now(), session_locked })
+        }),
     ).unwrap_or_else(|e| {
         eprintln!("Error al iniciar la GUI: {}. Intenta actualizar tus controladores gráficos.", e);
     });
 }
 
+// La ventana empieza en la pantalla de desbloqueo y pasa a la bóveda al abrirla
+struct VeclaApp {
+    lock: LockScreen,
+    app: Option<PasswordApp>,
+    last_activity: Instant,
+    session_locked: Arc<AtomicBool>, // la sesión del sistema se bloqueó
+}
+
+impl VeclaApp {
+    // Motivo para bloquear la bóveda abierta, si lo hay
+    fn lock_reason(&mut self, ctx: &egui::Context, app: &PasswordApp) -> Option<&'static str> {
+        let hubo_actividad = ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving() || i.pointer.any_down());
+        if hubo_actividad {
+            self.last_activity = Instant::now();
+        }
+        if self.session_locked.swap(false, Ordering::SeqCst) {
+            return Some("La bóveda se bloqueó al bloquearse la sesión.");
+        }
+        if app.config.lock_on_minimize && ctx.input(|i| i.viewport().minimized == Some(true)) {
+            return Some("La bóveda se bloqueó al minimizar la ventana.");
+        }
+        if app.config.lock_timeout > 0 {
+            let limite = Duration::from_secs(app.config.lock_timeout);
+            let inactivo = self.last_activity.elapsed();
+            if inactivo >= limite {
+                return Some("La bóveda se bloqueó por inactividad.");
+            }
+            // Volver a comprobarlo aunque no haya eventos
+            ctx.request_repaint_after(limite - inactivo);
+        }
+        None
+    }
+
+    // Cierra la bóveda (al soltar PasswordApp se borran la clave y los datos descifrados)
+    // y vuelve a la pantalla de desbloqueo
+    fn lock(&mut self, ctx: &egui::Context, motivo: &str) {
+        if let Some(app) = self.app.take() {
+            self.lock = LockScreen::locked(app.config.clone(), motivo);
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title("Gestor de Contraseñas".to_string()));
+    }
+}
+
+impl eframe::App for VeclaApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some(mut app) = self.app.take() {
+            let motivo = self.lock_reason(ctx, &app);
+            if motivo.is_none() {
+                app.update(ctx, frame);
+            }
+            let pedido = app.lock_requested.then_some("Bóveda bloqueada.");
+            self.app = Some(app);
+            if let Some(motivo) = motivo.or(pedido) {
+                self.lock(ctx, motivo);
+                ctx.request_repaint();
+            }
+            return;
+        }
+        // Un bloqueo de sesión sin bóveda abierta no tiene nada que cerrar
+        self.session_locked.store(false, Ordering::SeqCst);
+        if let Some(app) = self.lock.show(ctx) {
+            // El título muestra qué bóveda está abierta
+            let titulo = format!("Gestor de Contraseñas - {}", app.config.get_db_path().display());
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(titulo));
+            self.last_activity = Instant::now();
+            self.app = Some(app);
+        }
+    }
+
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        if let Some(app) = &mut self.app {
+            app.on_exit(gl);
+        }
+    }
+}
+
+// Barra de fortaleza con el tiempo estimado para adivinarla y, si es floja, el motivo
+// y cómo mejorarla
+fn strength_meter(ui: &mut egui::Ui, fortaleza: &Strength, ancho: f32) {
+    ui.vertical(|ui| {
+        ui.add(
+            egui::ProgressBar::new(fortaleza.score as f32 / 4.0)
+                .desired_width(ancho)
+                .fill(strength_color(fortaleza.score))
+                .text(format!("{} · se adivinaría en {}", fortaleza.label(), fortaleza.crack_time_label())),
+        );
+        if let Some(aviso) = fortaleza.warning {
+            ui.colored_label(egui::Color32::YELLOW, aviso);
+        }
+        for consejo in &fortaleza.suggestions {
+            ui.label(format!("• {}", consejo));
+        }
+    });
+}
+
+fn strength_color(score: u8) -> egui::Color32 {
+    match score {
+        0 => egui::Color32::from_rgb(200, 40, 40),
+        1 => egui::Color32::from_rgb(220, 120, 30),
+        2 => egui::Color32::from_rgb(210, 190, 40),
+        3 => egui::Color32::from_rgb(120, 190, 50),
+        _ => egui::Color32::from_rgb(40, 170, 70),
+    }
+}
+
+// Contraseña descifrada para mostrarla en la lista hasta que se oculte
+struct RevealedPassword {
+    texto: Zeroizing<String>,
+    desde: Instant,
+}
+
 pub struct PasswordApp {
     conn: Connection,
     master_key: String,
+    config: Config,
     entries: Vec<PasswordEntry>,
     // Form fields
     app: String,
@@ -38,15 +181,78 @@ pub struct PasswordApp {
     mail: String,
     con: String,
     notas: String,
+    carpeta: String,
     search_query: String,
     error: Option<String>,
+    // Importación
+    import_path: String,
+    import_format: Option<ImportFormat>, // None = detectar automáticamente
+    import_preview: Vec<ImportedEntry>,
+    import_skip_duplicates: bool,
+    info: Option<String>,
+    // KeePass
+    kdbx_path: String,
+    kdbx_password: String,
+    kdbx_key_file: String,
+    // Exportación cifrada
+    archive_path: String,
+    archive_password: String,
+    archive_password_confirm: String,
+    archive_mode: ImportMode,
+    // Exportación en texto plano
+    plain_path: String,
+    plain_format: PlaintextFormat,
+    plain_fields: [bool; PLAINTEXT_FIELDS.len()],
+    plain_folder: String,
+    plain_tag: String,
+    plain_confirm_open: bool,
+    plain_confirm_checked: bool,
+    // Copias de seguridad
+    backups: Vec<BackupInfo>,
+    restore_candidate: Option<PathBuf>,
+    // Pantalla de configuración (borrador hasta que se guarda)
+    settings: Config,
+    settings_backup_path: String,
+    settings_passphrase_wordlist: String,
+    // Portapapeles del sistema (se abre al copiar por primera vez)
+    clipboard: Option<SecretClipboard>,
+    // Contraseñas mostradas, por id de entrada
+    revealed: HashMap<String, RevealedPassword>,
+    // Entrada que espera la clave maestra para mostrarse (reveal_requires_key)
+    reveal_pending: Option<String>,
+    reveal_key: Zeroizing<String>,
+    reveal_error: Option<String>,
+    // Botón Bloquear o Ctrl+L
+    lock_requested: bool,
+    // Generador de contraseñas: política actual, perfil del que sale ("" = por defecto)
+    // y nombre con el que guardarla
+    generator: PasswordPolicy,
+    generator_profile: String,
+    generator_profile_name: String,
+    // "Generar" crea una frase de palabras en lugar de una contraseña aleatoria
+    generator_phrase: bool,
+    passphrase: PassphraseOptions,
+    passphrase_lang: String,
+    passphrase_list: Option<Wordlist>, // lista de passphrase_lang, cargada al usarla
+    // Informe de salud; se rehace tras cada cambio una vez pedido
+    audit: Option<AuditReport>,
 }
 
-impl PasswordApp {    pub fn new(conn: Connection, master_key: String) -> Self {
+impl PasswordApp {    pub fn new(conn: Connection, master_key: String, config: Config) -> Self {
         let entries = list_entries(&conn).unwrap_or_default();
+        // Copia al desbloquear la bóveda
+        let error = create_backup(&conn, &config).err().map(|e| format!("Error en la copia de seguridad: {}", e));
+        let backups = list_backups(&config).unwrap_or_default();
+        let settings = config.clone();
+        let generator = PasswordPolicy::with_length(config.default_password_length);
+        let settings_backup_path = config.backup_path.clone().unwrap_or_default();
+        let settings_passphrase_wordlist = config.passphrase_wordlist.clone().unwrap_or_default();
+        // Con una lista configurada (p. ej. la de la EFF) se usa por defecto
+        let passphrase_lang = if config.passphrase_wordlist.is_some() { LISTA_ARCHIVO } else { "es" }.to_string();
         Self {
             conn,
             master_key,
+            config,
             entries,
             app: String::new(),
             url: String::new(),
@@ -54,96 +260,672 @@ impl PasswordApp {    pub fn new(conn: Connection, master_key: String) -> Self {
             mail: String::new(),
             con: String::new(),
             notas: String::new(),
+            carpeta: String::new(),
             search_query: String::new(),
-            error: None,
+            error,
+            import_path: String::new(),
+            import_format: None,
+            import_preview: Vec::new(),
+            import_skip_duplicates: true,
+            info: None,
+            kdbx_path: String::new(),
+            kdbx_password: String::new(),
+            kdbx_key_file: String::new(),
+            archive_path: String::new(),
+            archive_password: String::new(),
+            archive_password_confirm: String::new(),
+            archive_mode: ImportMode::Merge,
+            plain_path: String::new(),
+            plain_format: PlaintextFormat::Json,
+            plain_fields: [true; PLAINTEXT_FIELDS.len()],
+            plain_folder: String::new(),
+            plain_tag: String::new(),
+            plain_confirm_open: false,
+            plain_confirm_checked: false,
+            backups,
+            restore_candidate: None,
+            settings,
+            settings_backup_path,
+            settings_passphrase_wordlist,
+            clipboard: None,
+            revealed: HashMap::new(),
+            reveal_pending: None,
+            reveal_key: Zeroizing::new(String::new()),
+            reveal_error: None,
+            lock_requested: false,
+            generator,
+            generator_profile: String::new(),
+            generator_profile_name: String::new(),
+            generator_phrase: false,
+            passphrase: PassphraseOptions::default(),
+            passphrase_lang,
+            passphrase_list: None,
+            audit: None,
         }
     }
 
     fn add_entry(&mut self) {
         match encrypt(&self.master_key, &self.con) {
             Ok(enc_con) => {
-                let entry = PasswordEntry::new(
+                let mut entry = PasswordEntry::new(
                     &self.app, &self.url, &self.usuario, &self.mail, &enc_con, &self.notas
                 );
+                entry.carpeta = self.carpeta.trim().trim_matches('/').to_string();
                 if let Err(e) = insert_entry(&self.conn, &entry) {
                     self.error = Some(format!("Error al guardar: {}", e));
                 } else {
                     self.entries = list_entries(&self.conn).unwrap_or_default();
                     self.app.clear(); self.url.clear(); self.usuario.clear();
-                    self.mail.clear(); self.con.clear(); self.notas.clear();
+                    self.mail.clear(); self.con.clear(); self.notas.clear(); self.carpeta.clear();
                     self.error = None;
+                    self.after_write();
                 }
             }
             Err(e) => self.error = Some(format!("Error cifrando: {}", e)),
         }
     }
 
+    // Se llama después de cada cambio guardado en la bóveda
+    fn after_write(&mut self) {
+        if self.audit.is_some() {
+            self.run_audit();
+        }
+        if let Err(e) = auto_backup(&self.conn, &self.config) {
+            self.error = Some(format!("Error en la copia de seguridad: {}", e));
+        }
+        self.backups = list_backups(&self.config).unwrap_or_default();
+    }
+
+    fn run_audit(&mut self) {
+        let entradas = list_entries(&self.conn).unwrap_or_default();
+        self.audit = Some(audit::audit(&entradas, &self.master_key, self.config.password_max_age));
+    }
+
+    fn show_audit(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Analizar").clicked() {
+                self.run_audit();
+            }
+            if let Some(informe) = &self.audit {
+                match informe.issues() {
+                    0 => ui.colored_label(egui::Color32::GREEN, format!("Sin problemas en {} entradas", informe.total)),
+                    n => ui.colored_label(egui::Color32::YELLOW, format!("{} avisos en {} entradas", n, informe.total)),
+                };
+            }
+        });
+        let Some(informe) = &self.audit else {
+            ui.label("Busca contraseñas débiles, reutilizadas o antiguas, entradas sin URL y duplicadas.");
+            return;
+        };
+        let etiqueta = |e: &EntryRef| {
+            if e.carpeta.is_empty() { e.label() } else { format!("{} [{}]", e.label(), e.carpeta) }
+        };
+        let grupos = |ui: &mut egui::Ui, grupos: &[Vec<EntryRef>]| {
+            for grupo in grupos {
+                ui.label(format!("• {}", grupo.iter().map(etiqueta).collect::<Vec<_>>().join(", ")));
+            }
+        };
+        egui::CollapsingHeader::new(format!("Contraseñas débiles ({})", informe.debiles.len())).show(ui, |ui| {
+            for d in &informe.debiles {
+                let aviso = d.aviso.map(|a| format!(" · {}", a)).unwrap_or_default();
+                ui.horizontal(|ui| {
+                    ui.colored_label(strength_color(d.puntuacion), d.nivel);
+                    ui.label(format!("{}: se adivinaría en {}{}", etiqueta(&d.entrada), d.tiempo, aviso));
+                });
+            }
+        });
+        egui::CollapsingHeader::new(format!("Contraseñas reutilizadas ({} grupos)", informe.reutilizadas.len()))
+            .show(ui, |ui| grupos(ui, &informe.reutilizadas));
+        let titulo = if informe.dias_maximos == 0 {
+            "Contraseñas antiguas (desactivado)".to_string()
+        } else {
+            format!("Sin cambiar en más de {} días ({})", informe.dias_maximos, informe.antiguas.len())
+        };
+        egui::CollapsingHeader::new(titulo).show(ui, |ui| {
+            for a in &informe.antiguas {
+                ui.label(format!("• {}: {} días", etiqueta(&a.entrada), a.dias));
+            }
+        });
+        egui::CollapsingHeader::new(format!("Sin URL ({})", informe.sin_url.len())).show(ui, |ui| {
+            for e in &informe.sin_url {
+                ui.label(format!("• {}", etiqueta(e)));
+            }
+        });
+        egui::CollapsingHeader::new(format!("Duplicadas ({} grupos)", informe.duplicadas.len()))
+            .show(ui, |ui| grupos(ui, &informe.duplicadas));
+        if !informe.ilegibles.is_empty() {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("No se pudieron descifrar: {}", informe.ilegibles.iter().map(etiqueta).collect::<Vec<_>>().join(", ")),
+            );
+        }
+    }
+
+    fn restore(&mut self, ruta: &Path) {
+        match restore_backup(&mut self.conn, &self.config, ruta) {
+            Ok(()) => {
+                self.info = Some(format!("Bóveda restaurada desde {}", ruta.display()));
+                self.entries = list_entries(&self.conn).unwrap_or_default();
+                if self.audit.is_some() {
+                    self.run_audit();
+                }
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+        self.backups = list_backups(&self.config).unwrap_or_default();
+    }
+
+    fn save_settings(&mut self) {
+        let mut nueva = self.settings.clone();
+        let ruta = self.settings_backup_path.trim();
+        nueva.backup_path = if ruta.is_empty() { None } else { Some(ruta.to_string()) };
+        let lista = self.settings_passphrase_wordlist.trim();
+        nueva.passphrase_wordlist = if lista.is_empty() { None } else { Some(lista.to_string()) };
+        let reiniciar = nueva.db_path != self.config.db_path;
+        let lista_cambiada = nueva.passphrase_wordlist != self.config.passphrase_wordlist;
+        match self.config.save_settings(&nueva) {
+            Ok(()) => {
+                if lista_cambiada {
+                    self.passphrase_lang =
+                        if nueva.passphrase_wordlist.is_some() { LISTA_ARCHIVO } else { "es" }.to_string();
+                    self.passphrase_list = None;
+                }
+                if self.generator_profile.is_empty() {
+                    self.generator.length = nueva.default_password_length;
+                }
+                self.backups = list_backups(&self.config).unwrap_or_default();
+                self.info = Some(if reiniciar {
+                    "Configuración guardada. La nueva bóveda se abrirá al reiniciar Vecla.".to_string()
+                } else {
+                    "Configuración guardada".to_string()
+                });
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
     fn delete_entry(&mut self, id: &str) {
+        self.revealed.remove(id);
         if let Err(e) = delete_entry(&self.conn, id) {
             self.error = Some(format!("Error al borrar: {}", e));
         } else {
             self.entries = list_entries(&self.conn).unwrap_or_default();
+            self.after_write();
+        }
+    }
+
+    fn preview_import(&mut self) {
+        self.import_preview.clear();
+        self.info = None;
+        let contenido = match std::fs::read_to_string(&self.import_path) {
+            Ok(c) => c,
+            Err(e) => {
+                self.error = Some(format!("No se pudo leer el archivo: {}", e));
+                return;
+            }
+        };
+        let formato = match self.import_format.or_else(|| detect_format(&contenido)) {
+            Some(f) => f,
+            None => {
+                self.error = Some("No se reconoce el formato del archivo".to_string());
+                return;
+            }
+        };
+        let resultado = parse(formato, &contenido)
+            .and_then(|mut entradas| {
+                mark_duplicates(&self.conn, &self.master_key, &mut entradas)?;
+                Ok(entradas)
+            });
+        match resultado {
+            Ok(entradas) => {
+                self.import_format = Some(formato);
+                self.import_preview = entradas;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Error al leer la importación: {}", e)),
+        }
+    }
+
+    fn confirm_import(&mut self) {
+        match commit_import(&mut self.conn, &self.master_key, &self.import_preview, self.import_skip_duplicates) {
+            Ok(n) => {
+                self.info = Some(format!("Se importaron {} entradas", n));
+                self.import_preview.clear();
+                self.entries = list_entries(&self.conn).unwrap_or_default();
+                self.error = None;
+                self.after_write();
+            }
+            Err(e) => self.error = Some(format!("Error al importar: {}", e)),
+        }
+    }
+
+    fn read_kdbx_key_file(&self) -> Result<Option<Vec<u8>>, String> {
+        if self.kdbx_key_file.trim().is_empty() {
+            return Ok(None);
+        }
+        std::fs::read(self.kdbx_key_file.trim())
+            .map(Some)
+            .map_err(|e| format!("No se pudo leer el archivo de clave: {}", e))
+    }
+
+    fn import_kdbx(&mut self) {
+        let resultado = self.read_kdbx_key_file().and_then(|key_file| {
+            let datos = std::fs::read(&self.kdbx_path).map_err(|e| format!("No se pudo leer el archivo: {}", e))?;
+            let db = kdbx::read(&datos, &self.kdbx_password, key_file.as_deref())?;
+            kdbx::import_into(&mut self.conn, &self.master_key, &db)
+        });
+        match resultado {
+            Ok((importadas, omitidas)) => {
+                self.info = Some(format!("KeePass: {} entradas importadas, {} ya existían", importadas, omitidas));
+                self.kdbx_password.clear();
+                self.entries = list_entries(&self.conn).unwrap_or_default();
+                self.error = None;
+                self.after_write();
+            }
+            Err(e) => self.error = Some(format!("Error al importar KeePass: {}", e)),
+        }
+    }
+
+    fn export_kdbx(&mut self) {
+        if self.kdbx_password.is_empty() && self.kdbx_key_file.trim().is_empty() {
+            self.error = Some("Indica una contraseña o un archivo de clave para el archivo KeePass".to_string());
+            return;
+        }
+        let resultado = self.read_kdbx_key_file().and_then(|key_file| {
+            let db = kdbx::export_from(&self.conn, &self.master_key)?;
+            let datos = kdbx::write(&db, &self.kdbx_password, key_file.as_deref())?;
+            std::fs::write(&self.kdbx_path, datos).map_err(|e| format!("No se pudo escribir el archivo: {}", e))
+        });
+        match resultado {
+            Ok(()) => {
+                self.info = Some(format!("Bóveda exportada a {}", self.kdbx_path));
+                self.kdbx_password.clear();
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Error al exportar KeePass: {}", e)),
+        }
+    }
+
+    fn export_archive(&mut self) {
+        if self.archive_password != self.archive_password_confirm {
+            self.error = Some("Las contraseñas no coinciden".to_string());
+            return;
+        }
+        let resultado = export_vault(&self.conn, &self.master_key, &self.archive_password)
+            .and_then(|datos| std::fs::write(&self.archive_path, datos).map_err(|e| format!("No se pudo escribir el archivo: {}", e)));
+        match resultado {
+            Ok(()) => {
+                self.info = Some(format!("Bóveda exportada a {}", self.archive_path));
+                self.archive_password.clear();
+                self.archive_password_confirm.clear();
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Error al exportar: {}", e)),
+        }
+    }
+
+    fn import_archive(&mut self) {
+        let resultado = std::fs::read(&self.archive_path)
+            .map_err(|e| format!("No se pudo leer el archivo: {}", e))
+            .and_then(|datos| import_vault(&mut self.conn, &self.master_key, &datos, &self.archive_password, self.archive_mode));
+        match resultado {
+            Ok(resumen) => {
+                self.info = Some(format!(
+                    "Importación completada: {} nuevas, {} actualizadas, {} sin cambios",
+                    resumen.nuevas, resumen.actualizadas, resumen.omitidas
+                ));
+                self.archive_password.clear();
+                self.archive_password_confirm.clear();
+                self.entries = list_entries(&self.conn).unwrap_or_default();
+                self.error = None;
+                self.after_write();
+            }
+            Err(e) => self.error = Some(format!("Error al importar: {}", e)),
+        }
+    }
+
+    fn export_plaintext(&mut self) {
+        let opcional = |texto: &str| Some(texto.trim().to_string()).filter(|t| !t.is_empty());
+        let opciones = PlaintextOptions {
+            formato: self.plain_format,
+            campos: PLAINTEXT_FIELDS
+                .iter()
+                .zip(self.plain_fields.iter())
+                .filter(|(_, activo)| **activo)
+                .map(|(campo, _)| campo.to_string())
+                .collect(),
+            carpeta: opcional(&self.plain_folder),
+            etiqueta: opcional(&self.plain_tag),
+        };
+        let resultado = export_plaintext(&self.conn, &self.master_key, &opciones)
+            .and_then(|contenido| std::fs::write(&self.plain_path, contenido).map_err(|e| format!("No se pudo escribir el archivo: {}", e)));
+        match resultado {
+            Ok(()) => {
+                self.info = Some(format!("Exportación SIN CIFRAR escrita en {}. Bórrala cuando termines.", self.plain_path));
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Error al exportar: {}", e)),
+        }
+    }
+
+    // Las contraseñas solo se descifran al mostrarlas o copiarlas
+    fn decrypt_password(&self, id: &str) -> Result<Zeroizing<String>, String> {
+        let entrada = self.entries.iter().find(|e| e.id == id).ok_or("La entrada ya no está en la lista")?;
+        decrypt(&self.master_key, &entrada.con)
+            .map(Zeroizing::new)
+            .map_err(|_| format!("No se pudo descifrar la contraseña de {}", entrada.app))
+    }
+
+    fn request_reveal(&mut self, id: &str) {
+        if self.config.reveal_requires_key {
+            self.reveal_pending = Some(id.to_string());
+            self.reveal_key.clear();
+            self.reveal_error = None;
+        } else {
+            self.reveal(id);
+        }
+    }
+
+    // La clave escrita, con el archivo de clave si lo hay, es la clave maestra. Se
+    // compara en tiempo constante.
+    fn reveal_key_matches(&self) -> bool {
+        match compose_master_key(&self.reveal_key, self.config.get_key_file_path().as_deref()) {
+            Ok(clave) => {
+                let clave = Zeroizing::new(clave);
+                bool::from(clave.as_bytes().ct_eq(self.master_key.as_bytes()))
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn reveal(&mut self, id: &str) {
+        match self.decrypt_password(id) {
+            Ok(texto) => {
+                self.revealed.insert(id.to_string(), RevealedPassword { texto, desde: Instant::now() });
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    // Oculta las contraseñas que llevan demasiado tiempo a la vista y pide repintar
+    // cuando toque ocultar la siguiente
+    fn hide_expired(&mut self, ctx: &egui::Context) {
+        let duracion = Duration::from_secs(self.config.reveal_timeout);
+        self.revealed.retain(|_, r| r.desde.elapsed() < duracion);
+        if let Some(proxima) = self.revealed.values().map(|r| duracion.saturating_sub(r.desde.elapsed())).min() {
+            ctx.request_repaint_after(proxima);
+        }
+    }
+
+    fn copy_password(&mut self, id: &str) {
+        let resultado = self.decrypt_password(id).and_then(|pass| self.copy_to_clipboard(&pass));
+        match resultado {
+            Ok(_) => self.error = None,
+            Err(e) => self.error = Some(format!("Error al copiar: {}", e)),
         }
     }
 
     fn copy_to_clipboard(&mut self, text: &str) -> Result<(), String> {
-        // Convertir el string a UTF-16 para Windows
-        let text_utf16: Vec<u16> = OsStr::new(text)
-            .encode_wide()
-            .chain(std::iter::once(0)) // Null-termination
-            .collect();
-        
-        unsafe {
-            // Abrir el portapapeles
-            if OpenClipboard(ptr::null_mut()) == 0 {
-                return Err("No se pudo abrir el portapapeles".to_string());
-            }
-            
-            // Vaciar el portapapeles
-            EmptyClipboard();
-            
-            // Asignar memoria global
-            let size = text_utf16.len() * std::mem::size_of::<u16>();
-            let h_glob = GlobalAlloc(GMEM_MOVEABLE, size);
-            if h_glob.is_null() {
-                CloseClipboard();
-                return Err("Error al asignar memoria".to_string());
-            }
-            
-            // Copiar el texto a la memoria global
-            let p_glob = GlobalLock(h_glob) as *mut u16;
-            if p_glob.is_null() {
-                GlobalFree(h_glob);
-                CloseClipboard();
-                return Err("Error al bloquear memoria".to_string());
-            }
-            
-            ptr::copy_nonoverlapping(text_utf16.as_ptr(), p_glob, text_utf16.len());
-            
-            GlobalUnlock(h_glob);
-            
-            // Establecer los datos en el portapapeles
-            let h_result = SetClipboardData(CF_UNICODETEXT, h_glob);
-            
-            CloseClipboard();
-            
-            if h_result.is_null() {
-                GlobalFree(h_glob);
-                return Err("No se pudo establecer el contenido del portapapeles".to_string());
-            }
-        }
-        
+        if self.clipboard.is_none() {
+            self.clipboard = Some(SecretClipboard::new(clipboard::system()?));
+        }
+        let limpiar_tras = self.config.clipboard_clear_after();
+        self.clipboard.as_ref().unwrap().copy(text, limpiar_tras)?;
+        if let Some(espera) = limpiar_tras {
+            self.info = Some(format!("Copiado; el portapapeles se vaciará en {} s", espera.as_secs()));
+        }
         Ok(())
     }
+
+    fn show_passphrase_options(&mut self, ui: &mut egui::Ui) {
+        let mut idioma = self.passphrase_lang.clone();
+        ui.horizontal(|ui| {
+            ui.label("Palabras:");
+            ui.add(egui::DragValue::new(&mut self.passphrase.words).clamp_range(3..=20));
+            ui.label("Lista:");
+            egui::ComboBox::from_id_source("passphrase_lang").selected_text(idioma.clone()).show_ui(ui, |ui| {
+                for nombre in passphrase::IDIOMAS {
+                    ui.selectable_value(&mut idioma, nombre.to_string(), nombre);
+                }
+                if self.config.passphrase_wordlist.is_some() {
+                    ui.selectable_value(&mut idioma, LISTA_ARCHIVO.to_string(), LISTA_ARCHIVO);
+                }
+            });
+            ui.label("Separador:");
+            ui.add(egui::TextEdit::singleline(&mut self.passphrase.separator).desired_width(30.0));
+        });
+        if idioma != self.passphrase_lang {
+            self.passphrase_lang = idioma;
+            self.passphrase_list = None;
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.passphrase.capitalize, "Mayúscula inicial");
+            ui.checkbox(&mut self.passphrase.add_digit, "Añadir un dígito");
+        });
+        let opciones = self.passphrase.clone();
+        match self.wordlist().and_then(|lista| opciones.validate().map(|_| (opciones.entropy_bits(lista), lista.len()))) {
+            Ok((bits, palabras)) => ui.label(format!("Entropía: ~{:.0} bits (lista de {} palabras)", bits, palabras)),
+            Err(e) => ui.colored_label(egui::Color32::YELLOW, e),
+        };
+    }
+
+    // Contraseña o frase según lo elegido en el generador
+    fn generate(&mut self) -> Result<String, String> {
+        if !self.generator_phrase {
+            return self.generator.generate();
+        }
+        let opciones = self.passphrase.clone();
+        opciones.generate(self.wordlist()?)
+    }
+
+    fn wordlist(&mut self) -> Result<&Wordlist, String> {
+        if self.passphrase_list.is_none() {
+            self.passphrase_list = Some(match self.config.get_passphrase_wordlist_path() {
+                Some(ruta) if self.passphrase_lang == LISTA_ARCHIVO => Wordlist::load(&ruta)?,
+                _ => Wordlist::bundled(&self.passphrase_lang)?,
+            });
+        }
+        Ok(self.passphrase_list.as_ref().unwrap())
+    }
+
+    fn show_generator(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.generator_phrase, false, "Contraseña aleatoria");
+            ui.radio_value(&mut self.generator_phrase, true, "Frase de contraseña");
+        });
+        if self.generator_phrase {
+            self.show_passphrase_options(ui);
+            return;
+        }
+        let mut elegido: Option<String> = None;
+        ui.horizontal(|ui| {
+            ui.label("Perfil:");
+            let actual = if self.generator_profile.is_empty() { "(por defecto)" } else { self.generator_profile.as_str() };
+            egui::ComboBox::from_id_source("generator_profile").selected_text(actual).show_ui(ui, |ui| {
+                if ui.selectable_label(self.generator_profile.is_empty(), "(por defecto)").clicked() {
+                    elegido = Some(String::new());
+                }
+                for nombre in self.config.password_profiles.keys() {
+                    if ui.selectable_label(*nombre == self.generator_profile, nombre).clicked() {
+                        elegido = Some(nombre.clone());
+                    }
+                }
+            });
+        });
+        if let Some(nombre) = elegido {
+            let perfil = (!nombre.is_empty()).then_some(nombre.as_str());
+            if let Ok(politica) = self.config.password_policy(perfil) {
+                self.generator = politica;
+                self.generator_profile_name = nombre.clone();
+                self.generator_profile = nombre;
+            }
+        }
+        let politica = &mut self.generator;
+        ui.horizontal(|ui| {
+            ui.label("Longitud:");
+            ui.add(egui::DragValue::new(&mut politica.length).clamp_range(4..=512));
+            ui.checkbox(&mut politica.lowercase, "a-z");
+            ui.checkbox(&mut politica.uppercase, "A-Z");
+            ui.checkbox(&mut politica.digits, "0-9");
+            ui.checkbox(&mut politica.symbols, "Símbolos:");
+            ui.add_enabled(politica.symbols, egui::TextEdit::singleline(&mut politica.symbol_set).desired_width(120.0));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut politica.exclude_ambiguous, "Excluir caracteres ambiguos (0/O, l/1/I)");
+            ui.checkbox(&mut politica.require_each, "Al menos uno de cada tipo");
+        });
+        match politica.validate() {
+            Ok(()) => ui.label(format!("Entropía: ~{:.0} bits", politica.entropy_bits())),
+            Err(e) => ui.colored_label(egui::Color32::YELLOW, e),
+        };
+        ui.horizontal(|ui| {
+            ui.label("Guardar como perfil:");
+            ui.text_edit_singleline(&mut self.generator_profile_name);
+            if ui.button("Guardar perfil").clicked() {
+                let nombre = self.generator_profile_name.trim().to_string();
+                match self.config.save_profile(&nombre, &self.generator) {
+                    Ok(()) => {
+                        self.settings.password_profiles = self.config.password_profiles.clone();
+                        self.info = Some(format!("Perfil \"{}\" guardado", nombre));
+                        self.generator_profile = nombre;
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if !self.generator_profile.is_empty() && ui.button("Borrar perfil").clicked() {
+                let nombre = std::mem::take(&mut self.generator_profile);
+                match self.config.delete_profile(&nombre) {
+                    Ok(()) => {
+                        self.settings.password_profiles = self.config.password_profiles.clone();
+                        self.info = Some(format!("Perfil \"{}\" borrado", nombre));
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        });
+    }
+}
+
+// Al bloquear o cerrar no quedan en memoria la clave maestra ni las contraseñas escritas
+impl Drop for PasswordApp {
+    fn drop(&mut self) {
+        if let Some(portapapeles) = &self.clipboard {
+            portapapeles.clear_now();
+        }
+        self.master_key.zeroize();
+        self.con.zeroize();
+        self.kdbx_password.zeroize();
+        self.archive_password.zeroize();
+        self.archive_password_confirm.zeroize();
+        for entrada in &mut self.import_preview {
+            entrada.con.zeroize();
+        }
+    }
 }
 
 impl eframe::App for PasswordApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::L)) {
+            self.lock_requested = true;
+        }
+        self.hide_expired(ctx);
+        if let Some(id) = self.reveal_pending.clone() {
+            egui::Window::new("Mostrar contraseña")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Escribe la clave maestra para mostrar la contraseña:");
+                    let campo = ui.add(egui::TextEdit::singleline(&mut *self.reveal_key).password(true));
+                    campo.request_focus();
+                    if let Some(error) = &self.reveal_error {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
+                    let enter = campo.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.horizontal(|ui| {
+                        if ui.button("Mostrar").clicked() || enter {
+                            if self.reveal_key_matches() {
+                                self.reveal_pending = None;
+                                self.reveal(&id);
+                            } else {
+                                self.reveal_error = Some(if self.config.key_file.is_some() {
+                                    "Clave maestra o archivo de clave incorrectos".to_string()
+                                } else {
+                                    "Clave maestra incorrecta".to_string()
+                                });
+                            }
+                            self.reveal_key.clear();
+                        }
+                        if ui.button("Cancelar").clicked() {
+                            self.reveal_pending = None;
+                            self.reveal_key.clear();
+                        }
+                    });
+                });
+        }
+        if let Some(ruta) = self.restore_candidate.clone() {
+            egui::Window::new("Restaurar copia de seguridad")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("Se reemplazará el contenido de la bóveda por {}", ruta.display()));
+                    ui.label("Antes se guardará una copia del estado actual.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Restaurar").clicked() {
+                            self.restore(&ruta);
+                            self.restore_candidate = None;
+                        }
+                        if ui.button("Cancelar").clicked() {
+                            self.restore_candidate = None;
+                        }
+                    });
+                });
+        }
+        if self.plain_confirm_open {
+            egui::Window::new("⚠ Exportación sin cifrar")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("El archivo contendrá las entradas seleccionadas SIN CIFRAR.");
+                    ui.label("Cualquiera con acceso a él podrá leer las contraseñas.");
+                    ui.label(format!("Destino: {}", self.plain_path));
+                    ui.checkbox(&mut self.plain_confirm_checked, "Entiendo el riesgo y quiero exportar en texto plano");
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(self.plain_confirm_checked, egui::Button::new("Exportar")).clicked() {
+                            self.export_plaintext();
+                            self.plain_confirm_open = false;
+                        }
+                        if ui.button("Cancelar").clicked() {
+                            self.plain_confirm_open = false;
+                        }
+                    });
+                });
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
+            let readonly = self.config.readonly;
             ui.heading("Gestor de Contraseñas");
+            ui.horizontal(|ui| {
+                ui.label(format!("Bóveda: {}", self.config.get_db_path().display()));
+                if Config::is_portable() {
+                    ui.label("(modo portable)");
+                }
+                if ui.button("🔒 Bloquear").on_hover_text("Ctrl+L").clicked() {
+                    self.lock_requested = true;
+                }
+            });
+            if readonly {
+                ui.colored_label(egui::Color32::YELLOW, "Bóveda abierta en solo lectura");
+            }
             if let Some(err) = &self.error {
                 ui.colored_label(egui::Color32::RED, err);
             }
+            if let Some(info) = &self.info {
+                ui.colored_label(egui::Color32::GREEN, info);
+            }
             ui.separator();
             ui.label("Agregar nueva entrada:");
             ui.horizontal(|ui| {
@@ -157,14 +939,238 @@ impl eframe::App for PasswordApp {
                 ui.label("Contraseña:"); 
                 ui.text_edit_singleline(&mut self.con);
                 if ui.button("Generar").clicked() {
-                    self.con = generate_password(16);
+                    match self.generate() {
+                        Ok(contrasena) => self.con = contrasena,
+                        Err(e) => self.error = Some(e),
+                    }
                 }
                 ui.label("Notas:"); 
                 ui.text_edit_singleline(&mut self.notas);
             });
-            if ui.button("Guardar").clicked() {
+            if !self.con.is_empty() {
+                // Lo primero que probaría un atacante son los datos de la propia entrada
+                let datos = [self.app.as_str(), self.url.as_str(), self.usuario.as_str(), self.mail.as_str()];
+                strength_meter(ui, &strength::estimate_with_inputs(&self.con, &datos), 300.0);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Carpeta:");
+                ui.text_edit_singleline(&mut self.carpeta);
+            });
+            egui::CollapsingHeader::new("Generador de contraseñas").show(ui, |ui| {
+                self.show_generator(ui);
+            });
+            if ui.add_enabled(!readonly, egui::Button::new("Guardar")).clicked() {
                 self.add_entry();
-            }ui.separator();
+            }
+            ui.separator();
+            egui::CollapsingHeader::new("Importar desde otro gestor").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Archivo:");
+                    ui.text_edit_singleline(&mut self.import_path);
+                    egui::ComboBox::from_id_source("import_format")
+                        .selected_text(self.import_format.map(|f| f.nombre()).unwrap_or("Detectar automáticamente"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.import_format, None, "Detectar automáticamente");
+                            for formato in ImportFormat::ALL {
+                                ui.selectable_value(&mut self.import_format, Some(formato), formato.nombre());
+                            }
+                        });
+                    if ui.button("Vista previa").clicked() {
+                        self.preview_import();
+                    }
+                });
+                if !self.import_preview.is_empty() {
+                    let duplicados = self.import_preview.iter().filter(|e| e.duplicado).count();
+                    ui.label(format!("{} entradas encontradas, {} duplicadas", self.import_preview.len(), duplicados));
+                    egui::ScrollArea::vertical().id_source("import_preview").max_height(150.0).show(ui, |ui| {
+                        for entrada in &self.import_preview {
+                            let mut texto = format!("{} | {} | {}", entrada.app, entrada.usuario, entrada.url);
+                            if !entrada.carpeta.is_empty() {
+                                texto.push_str(&format!(" [{}]", entrada.carpeta));
+                            }
+                            if entrada.duplicado {
+                                ui.colored_label(egui::Color32::YELLOW, format!("{} (duplicada)", texto));
+                            } else {
+                                ui.label(texto);
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.import_skip_duplicates, "Omitir duplicados");
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!readonly, egui::Button::new("Importar")).clicked() {
+                            self.confirm_import();
+                        }
+                        if ui.button("Cancelar").clicked() {
+                            self.import_preview.clear();
+                        }
+                    });
+                }
+            });
+            egui::CollapsingHeader::new("KeePass (KDBX 4)").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Archivo .kdbx:");
+                    ui.text_edit_singleline(&mut self.kdbx_path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Contraseña:");
+                    ui.add(egui::TextEdit::singleline(&mut self.kdbx_password).password(true));
+                    ui.label("Archivo de clave (opcional):");
+                    ui.text_edit_singleline(&mut self.kdbx_key_file);
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!readonly, egui::Button::new("Importar")).clicked() {
+                        self.import_kdbx();
+                    }
+                    if ui.button("Exportar").clicked() {
+                        self.export_kdbx();
+                    }
+                });
+            });
+            egui::CollapsingHeader::new("Exportar / importar copia cifrada").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Archivo:");
+                    ui.text_edit_singleline(&mut self.archive_path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Contraseña del archivo:");
+                    ui.add(egui::TextEdit::singleline(&mut self.archive_password).password(true));
+                    ui.label("Repetir (al exportar):");
+                    ui.add(egui::TextEdit::singleline(&mut self.archive_password_confirm).password(true));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Al importar:");
+                    ui.radio_value(&mut self.archive_mode, ImportMode::Merge, "Combinar con la bóveda");
+                    ui.radio_value(&mut self.archive_mode, ImportMode::Replace, "Reemplazar la bóveda");
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Exportar").clicked() {
+                        self.export_archive();
+                    }
+                    if ui.add_enabled(!readonly, egui::Button::new("Importar")).clicked() {
+                        self.import_archive();
+                    }
+                });
+            });
+            egui::CollapsingHeader::new("Exportar en texto plano (sin cifrar)").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Archivo:");
+                    ui.text_edit_singleline(&mut self.plain_path);
+                    ui.radio_value(&mut self.plain_format, PlaintextFormat::Json, "JSON");
+                    ui.radio_value(&mut self.plain_format, PlaintextFormat::Csv, "CSV");
+                });
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Campos:");
+                    for (campo, activo) in PLAINTEXT_FIELDS.iter().zip(self.plain_fields.iter_mut()) {
+                        ui.checkbox(activo, *campo);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Solo carpeta:");
+                    ui.text_edit_singleline(&mut self.plain_folder);
+                    ui.label("Solo etiqueta:");
+                    ui.text_edit_singleline(&mut self.plain_tag);
+                });
+                if ui.button("Exportar...").clicked() {
+                    self.plain_confirm_checked = false;
+                    self.plain_confirm_open = true;
+                }
+            });
+            egui::CollapsingHeader::new("Configuración").show(ui, |ui| {
+                if let Some(ruta) = &self.config.config_path {
+                    ui.label(format!("Archivo: {}", ruta.display()));
+                }
+                ui.label(format!("Las rutas relativas se resuelven desde {}", Config::base_dir().display()));
+                egui::Grid::new("settings_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Bóveda (db_path):");
+                    ui.text_edit_singleline(&mut self.settings.db_path);
+                    ui.end_row();
+                    ui.label("Carpeta de copias (vacío = por defecto):");
+                    ui.text_edit_singleline(&mut self.settings_backup_path);
+                    ui.end_row();
+                    ui.label("Lista de palabras para frases (vacío = incluidas):");
+                    ui.text_edit_singleline(&mut self.settings_passphrase_wordlist);
+                    ui.end_row();
+                    ui.label("Copias a conservar:");
+                    ui.add(egui::DragValue::new(&mut self.settings.backup_generations).clamp_range(1..=1000));
+                    ui.end_row();
+                    ui.label("Longitud de contraseña por defecto:");
+                    ui.add(egui::DragValue::new(&mut self.settings.default_password_length).clamp_range(4..=512));
+                    ui.end_row();
+                    ui.label("Copia automática tras los cambios (como mucho cada 15 min):");
+                    ui.checkbox(&mut self.settings.auto_save, "");
+                    ui.end_row();
+                    ui.label("Bloqueo del agente de terminal (segundos sin uso):");
+                    ui.add(egui::DragValue::new(&mut self.settings.agent_timeout).clamp_range(10..=86_400));
+                    ui.end_row();
+                    ui.label("Vaciar el portapapeles tras copiar (segundos, 0 = nunca):");
+                    ui.add(egui::DragValue::new(&mut self.settings.clipboard_timeout).clamp_range(0..=3600));
+                    ui.end_row();
+                    ui.label("Ocultar una contraseña mostrada tras (segundos):");
+                    ui.add(egui::DragValue::new(&mut self.settings.reveal_timeout).clamp_range(1..=600));
+                    ui.end_row();
+                    ui.label("Pedir la clave maestra para mostrar contraseñas:");
+                    ui.checkbox(&mut self.settings.reveal_requires_key, "");
+                    ui.end_row();
+                    ui.label("Bloquear tras (segundos sin actividad, 0 = nunca):");
+                    ui.add(egui::DragValue::new(&mut self.settings.lock_timeout).clamp_range(0..=86_400));
+                    ui.end_row();
+                    ui.label("Bloquear al minimizar la ventana:");
+                    ui.checkbox(&mut self.settings.lock_on_minimize, "");
+                    ui.end_row();
+                    ui.label("Avisar de contraseñas sin cambiar en (días, 0 = nunca):");
+                    ui.add(egui::DragValue::new(&mut self.settings.password_max_age).clamp_range(0..=36_500));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Guardar configuración").clicked() {
+                        self.save_settings();
+                    }
+                    if ui.button("Descartar cambios").clicked() {
+                        self.settings = self.config.clone();
+                        self.settings_backup_path = self.config.backup_path.clone().unwrap_or_default();
+                        self.settings_passphrase_wordlist = self.config.passphrase_wordlist.clone().unwrap_or_default();
+                    }
+                });
+            });
+            egui::CollapsingHeader::new("Salud de la bóveda").show(ui, |ui| {
+                self.show_audit(ui);
+            });
+            egui::CollapsingHeader::new("Copias de seguridad").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Crear copia ahora").clicked() {
+                        match create_backup(&self.conn, &self.config) {
+                            Ok(ruta) => self.info = Some(format!("Copia creada: {}", ruta.display())),
+                            Err(e) => self.error = Some(format!("Error en la copia de seguridad: {}", e)),
+                        }
+                        self.backups = list_backups(&self.config).unwrap_or_default();
+                    }
+                    ui.label(format!("Se conservan {} copias", self.config.backup_generations));
+                });
+                let mut restaurar = None;
+                let mut verificar = None;
+                for copia in &self.backups {
+                    ui.horizontal(|ui| {
+                        let fecha = copia.fecha.format("%Y-%m-%d %H:%M:%S UTC");
+                        ui.label(format!("{} ({} KB)", fecha, copia.tamano / 1024));
+                        if ui.button("Verificar").clicked() {
+                            verificar = Some(copia.ruta.clone());
+                        }
+                        if ui.add_enabled(!readonly, egui::Button::new("Restaurar")).clicked() {
+                            restaurar = Some(copia.ruta.clone());
+                        }
+                    });
+                }
+                if let Some(ruta) = verificar {
+                    match verify_backup(&ruta) {
+                        Ok(n) => self.info = Some(format!("Copia correcta: {} entradas", n)),
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+                if restaurar.is_some() {
+                    self.restore_candidate = restaurar;
+                }
+            });
+            ui.separator();
             ui.horizontal(|ui| {
                 ui.label("Buscar:");
                 ui.text_edit_singleline(&mut self.search_query);
@@ -185,34 +1191,67 @@ impl eframe::App for PasswordApp {
             });
             ui.separator();
             ui.label("Entradas guardadas:");
+            // Las acciones se aplican tras recorrer la lista, que no se puede modificar mientras tanto
+            let mut copiar: Option<String> = None;
+            let mut mostrar: Option<String> = None;
+            let mut ocultar: Option<String> = None;
+            let mut eliminar: Option<String> = None;
             egui::ScrollArea::vertical().show(ui, |ui| {
                 for entry in &self.entries {
                     ui.group(|ui| {
                         ui.label(format!("App: {} | Usuario: {} | Mail: {}", entry.app, entry.usuario, entry.mail));
                         ui.label(format!("URL: {}", entry.url));
-                        ui.label(format!("Notas: {}", entry.notas));                        let password_result = decrypt(&self.master_key, &entry.con);
-                        if let Ok(pass) = &password_result {
-                            ui.horizontal(|ui| {
-                                ui.label(format!("Contraseña: {}", pass));
-                                if ui.button("📋 Copiar").clicked() {
-                                    match self.copy_to_clipboard(pass) {
-                                        Ok(_) => self.error = None,
-                                        Err(e) => self.error = Some(format!("Error al copiar: {}", e)),
+                        if !entry.carpeta.is_empty() {
+                            ui.label(format!("Carpeta: {}", entry.carpeta));
+                        }
+                        ui.label(format!("Notas: {}", entry.notas));
+                        ui.horizontal(|ui| {
+                            match self.revealed.get(&entry.id) {
+                                Some(revelada) => {
+                                    ui.label(format!("Contraseña: {}", revelada.texto.as_str()));
+                                    if ui.button("🙈 Ocultar").clicked() {
+                                        ocultar = Some(entry.id.clone());
                                     }
                                 }
-                            });
-                        } else {
-                            ui.colored_label(egui::Color32::RED, "Error descifrando contraseña");
-                        }
+                                None => {
+                                    ui.label(format!("Contraseña: {}", MASCARA));
+                                    if ui.button("👁 Mostrar").clicked() {
+                                        mostrar = Some(entry.id.clone());
+                                    }
+                                }
+                            }
+                            if ui.button("📋 Copiar").clicked() {
+                                copiar = Some(entry.id.clone());
+                            }
+                        });
                         ui.horizontal(|ui| {
-                            if ui.button("Eliminar").clicked() {
-                                self.delete_entry(&entry.id);
+                            if ui.add_enabled(!readonly, egui::Button::new("Eliminar")).clicked() {
+                                eliminar = Some(entry.id.clone());
                             }
                         });
                     });
                     ui.separator();
                 }
             });
+            if let Some(id) = copiar {
+                self.copy_password(&id);
+            }
+            if let Some(id) = mostrar {
+                self.request_reveal(&id);
+            }
+            if let Some(id) = ocultar {
+                self.revealed.remove(&id);
+            }
+            if let Some(id) = eliminar {
+                self.delete_entry(&id);
+            }
         });
     }
-}
\ No newline at end of file
+
+    // Al cerrar no se deja la contraseña copiada en el portapapeles
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(portapapeles) = &self.clipboard {
+            portapapeles.clear_now();
+        }
+    }
+}
@@ -0,0 +1,333 @@
+// Pantalla de desbloqueo: elegir la bóveda y el archivo de clave, escribir la clave
+// maestra o crear una bóveda nueva. Sustituye a la pregunta en la terminal, de modo que
+// la interfaz no necesita consola.
+use std::path::{Path, PathBuf};
+use vecla::config::Config;
+use vecla::crypto::compose_master_key;
+use vecla::db::{check_master_key, open_db};
+use vecla::passphrase::{PassphraseOptions, Wordlist};
+use vecla::strength;
+use zeroize::Zeroizing;
+use super::{strength_meter, PasswordApp};
+
+#[derive(Clone, Copy, PartialEq)]
+enum PickerTarget {
+    Vault,
+    KeyFile,
+}
+
+// Explorador de archivos mínimo dentro de la ventana
+struct FilePicker {
+    target: PickerTarget,
+    dir: PathBuf,
+    entries: Vec<(String, bool)>, // nombre y si es una carpeta
+    error: Option<String>,
+}
+
+impl FilePicker {
+    fn open(target: PickerTarget, inicial: &str) -> Self {
+        let inicial = Path::new(inicial.trim());
+        let dir = if inicial.is_dir() {
+            inicial.to_path_buf()
+        } else {
+            inicial
+                .parent()
+                .filter(|d| d.is_dir())
+                .map(Path::to_path_buf)
+                .or_else(dirs::home_dir)
+                .unwrap_or_else(|| PathBuf::from("."))
+        };
+        let mut picker = Self { target, dir, entries: Vec::new(), error: None };
+        picker.load();
+        picker
+    }
+
+    fn load(&mut self) {
+        self.entries.clear();
+        self.error = None;
+        match std::fs::read_dir(&self.dir) {
+            Ok(lectura) => {
+                self.entries = lectura
+                    .filter_map(|e| e.ok())
+                    .map(|e| (e.file_name().to_string_lossy().to_string(), e.path().is_dir()))
+                    .filter(|(nombre, _)| !nombre.starts_with('.'))
+                    .collect();
+                // Carpetas primero, luego archivos, por nombre
+                self.entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.to_lowercase().cmp(&b.0.to_lowercase())));
+            }
+            Err(e) => self.error = Some(format!("No se pudo leer {}: {}", self.dir.display(), e)),
+        }
+    }
+
+    fn enter(&mut self, dir: PathBuf) {
+        self.dir = dir;
+        self.load();
+    }
+}
+
+pub struct LockScreen {
+    config: Config,
+    vault_path: String,
+    key_file: String,
+    password: Zeroizing<String>,
+    confirm: Zeroizing<String>,
+    suggested: Option<Zeroizing<String>>, // frase propuesta para una bóveda nueva
+    error: Option<String>,
+    info: Option<String>, // por qué se bloqueó la bóveda
+    legacy: Option<PathBuf>, // bóveda en la ubicación de versiones anteriores
+    picker: Option<FilePicker>,
+    focus_password: bool, // llevar el foco a la clave (al abrir y tras un error)
+}
+
+impl LockScreen {
+    pub fn new(config: Config) -> Self {
+        let vault_path = config.get_db_path().display().to_string();
+        let key_file = config.get_key_file_path().map(|r| r.display().to_string()).unwrap_or_default();
+        let legacy = config.legacy_vault();
+        Self {
+            config,
+            vault_path,
+            key_file,
+            password: Zeroizing::new(String::new()),
+            confirm: Zeroizing::new(String::new()),
+            suggested: None,
+            error: None,
+            info: None,
+            legacy,
+            picker: None,
+            focus_password: true,
+        }
+    }
+
+    // Pantalla para volver a abrir la bóveda que se acaba de bloquear
+    pub fn locked(config: Config, motivo: &str) -> Self {
+        let mut pantalla = Self::new(config);
+        pantalla.info = Some(motivo.to_string());
+        pantalla
+    }
+
+    // Configuración con la bóveda y el archivo de clave elegidos en pantalla
+    fn selected_config(&self) -> Config {
+        let mut config = self.config.clone().with_db_path(self.vault_path.trim());
+        let key_file = self.key_file.trim();
+        config.key_file = if key_file.is_empty() { None } else { Some(key_file.to_string()) };
+        config
+    }
+
+    fn unlock(&mut self) -> Result<PasswordApp, String> {
+        if self.vault_path.trim().is_empty() {
+            return Err("Elige una bóveda".to_string());
+        }
+        let config = self.selected_config();
+        let crear = !config.db_exists();
+        if crear {
+            if config.readonly {
+                return Err("La bóveda no existe y no se puede crear en modo solo lectura".to_string());
+            }
+            if self.password.is_empty() {
+                return Err("La clave maestra no puede estar vacía".to_string());
+            }
+            if *self.password != *self.confirm {
+                return Err("Las claves no coinciden".to_string());
+            }
+        }
+        let master_key = compose_master_key(&self.password, config.get_key_file_path().as_deref())?;
+        let conn = open_db(&config).map_err(|e| format!("No se pudo abrir {}: {}", config.get_db_path().display(), e))?;
+        if !check_master_key(&conn, &master_key).map_err(|e| e.to_string())? {
+            return Err(if config.key_file.is_some() {
+                "Clave maestra o archivo de clave incorrectos".to_string()
+            } else {
+                "Clave maestra incorrecta".to_string()
+            });
+        }
+        self.password.clear();
+        self.confirm.clear();
+        self.suggested = None;
+        Ok(PasswordApp::new(conn, master_key, config))
+    }
+
+    // Propone una frase de seis palabras como clave maestra y la escribe en los dos campos
+    fn suggest_passphrase(&mut self) -> Result<(), String> {
+        let lista = Wordlist::bundled("es")?;
+        let opciones = PassphraseOptions::default();
+        let frase = Zeroizing::new(opciones.generate(&lista)?);
+        self.password = frase.clone();
+        self.confirm = frase.clone();
+        self.suggested = Some(frase);
+        Ok(())
+    }
+
+    // Dibuja la pantalla; devuelve la aplicación cuando la bóveda queda desbloqueada
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PasswordApp> {
+        self.show_picker(ctx);
+        let mut desbloqueada = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(40.0);
+                ui.heading("🔒 Vecla");
+                ui.add_space(20.0);
+            });
+            let existe = self.selected_config().db_exists();
+            egui::Grid::new("unlock_grid").num_columns(2).spacing([8.0, 8.0]).show(ui, |ui| {
+                ui.label("Bóveda:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.vault_path).desired_width(360.0));
+                    if ui.button("Examinar...").clicked() {
+                        self.picker = Some(FilePicker::open(PickerTarget::Vault, &self.vault_path));
+                    }
+                });
+                ui.end_row();
+                ui.label("Archivo de clave:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.key_file).desired_width(360.0).hint_text("(opcional)"));
+                    if ui.button("Examinar...").clicked() {
+                        self.picker = Some(FilePicker::open(PickerTarget::KeyFile, &self.key_file));
+                    }
+                    if !self.key_file.is_empty() && ui.button("Quitar").clicked() {
+                        self.key_file.clear();
+                    }
+                });
+                ui.end_row();
+                ui.label("Clave maestra:");
+                let campo = ui.add(egui::TextEdit::singleline(&mut *self.password).password(true).desired_width(360.0));
+                if self.focus_password {
+                    campo.request_focus();
+                    self.focus_password = false;
+                }
+                ui.end_row();
+                if !existe {
+                    ui.label("Repite la clave:");
+                    ui.add(egui::TextEdit::singleline(&mut *self.confirm).password(true).desired_width(360.0));
+                    ui.end_row();
+                    ui.label("Fortaleza:");
+                    strength_meter(ui, &strength::estimate(&self.password), 360.0);
+                    ui.end_row();
+                }
+            });
+            ui.add_space(10.0);
+            if !existe {
+                self.show_legacy(ui);
+            }
+            let existe = self.selected_config().db_exists();
+            if !existe {
+                ui.label("La bóveda no existe: se creará una nueva protegida con esta clave. No hay forma de recuperarla si la olvidas.");
+                if ui.button("Sugerir una frase de contraseña").clicked() {
+                    if let Err(e) = self.suggest_passphrase() {
+                        self.error = Some(e);
+                    }
+                }
+                // Solo se muestra mientras siga siendo la clave escrita
+                if let Some(frase) = self.suggested.as_ref().filter(|f| **f == self.password) {
+                    ui.label(format!("Clave propuesta: {}", frase.as_str()));
+                    ui.label("Apúntala en un lugar seguro antes de crear la bóveda.");
+                }
+                if !self.confirm.is_empty() && *self.password != *self.confirm {
+                    ui.colored_label(egui::Color32::YELLOW, "Las claves no coinciden");
+                }
+            }
+            if let Some(info) = &self.info {
+                ui.label(info);
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            let texto = if existe { "Desbloquear" } else { "Crear bóveda" };
+            let enter = self.picker.is_none() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button(texto).clicked() || enter {
+                match self.unlock() {
+                    Ok(app) => desbloqueada = Some(app),
+                    Err(e) => {
+                        self.error = Some(e);
+                        self.password.clear();
+                        self.confirm.clear();
+                        self.focus_password = true;
+                    }
+                }
+            }
+        });
+        desbloqueada
+    }
+
+    // Ofrece mover o usar la bóveda que quedó donde la buscaban las versiones anteriores
+    fn show_legacy(&mut self, ui: &mut egui::Ui) {
+        let Some(antigua) = self.legacy.clone() else { return };
+        if self.selected_config().get_db_path() != self.config.get_db_path() {
+            return;
+        }
+        ui.colored_label(egui::Color32::YELLOW, self.config.legacy_vault_warning(&antigua));
+        ui.horizontal(|ui| {
+            if ui.button("Moverla a la nueva ubicación").clicked() {
+                match self.config.move_legacy_vault(&antigua) {
+                    Ok(()) => {
+                        self.info = Some(format!("Bóveda movida a {}", self.config.get_db_path().display()));
+                        self.legacy = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if ui.button("Abrirla donde está").clicked() {
+                self.vault_path = antigua.display().to_string();
+                self.legacy = None;
+            }
+        });
+        ui.add_space(10.0);
+    }
+
+    fn show_picker(&mut self, ctx: &egui::Context) {
+        let Some(picker) = &mut self.picker else { return };
+        let titulo = match picker.target {
+            PickerTarget::Vault => "Elegir bóveda",
+            PickerTarget::KeyFile => "Elegir archivo de clave",
+        };
+        let mut elegido: Option<PathBuf> = None;
+        let mut cerrar = false;
+        egui::Window::new(titulo).collapsible(false).default_height(400.0).show(ctx, |ui| {
+            ui.label(picker.dir.display().to_string());
+            if let Some(error) = &picker.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            let mut entrar: Option<PathBuf> = None;
+            if let Some(padre) = picker.dir.parent() {
+                if ui.button("⬆ Carpeta superior").clicked() {
+                    entrar = Some(padre.to_path_buf());
+                }
+            }
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for (nombre, es_dir) in &picker.entries {
+                    let etiqueta = if *es_dir { format!("📁 {}", nombre) } else { format!("📄 {}", nombre) };
+                    if ui.selectable_label(false, etiqueta).clicked() {
+                        if *es_dir {
+                            entrar = Some(picker.dir.join(nombre));
+                        } else {
+                            elegido = Some(picker.dir.join(nombre));
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                // Para crear una bóveda nueva basta con elegir la carpeta
+                if picker.target == PickerTarget::Vault && ui.button("Nueva bóveda aquí").clicked() {
+                    elegido = Some(picker.dir.join("vault.db"));
+                }
+                if ui.button("Cancelar").clicked() {
+                    cerrar = true;
+                }
+            });
+            if let Some(dir) = entrar {
+                picker.enter(dir);
+            }
+        });
+        if let Some(ruta) = elegido {
+            let ruta = ruta.display().to_string();
+            match picker.target {
+                PickerTarget::Vault => self.vault_path = ruta,
+                PickerTarget::KeyFile => self.key_file = ruta,
+            }
+            self.error = None;
+            cerrar = true;
+        }
+        if cerrar {
+            self.picker = None;
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use rusqlite::Connection;
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::db::{PasswordEntry, insert_entry, list_entries};
+use crate::crypto::{encrypt, decrypt};
+
+// Formatos de exportación soportados
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Chrome,        // Chrome / Edge / Brave (CSV)
+    Firefox,       // Firefox (CSV)
+    BitwardenCsv,
+    BitwardenJson,
+    LastPass,      // LastPass (CSV)
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 5] = [
+        ImportFormat::Chrome,
+        ImportFormat::Firefox,
+        ImportFormat::BitwardenCsv,
+        ImportFormat::BitwardenJson,
+        ImportFormat::LastPass,
+    ];
+
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            ImportFormat::Chrome => "Chrome / Edge (CSV)",
+            ImportFormat::Firefox => "Firefox (CSV)",
+            ImportFormat::BitwardenCsv => "Bitwarden (CSV)",
+            ImportFormat::BitwardenJson => "Bitwarden (JSON)",
+            ImportFormat::LastPass => "LastPass (CSV)",
+        }
+    }
+}
+
+// Entrada leída de un archivo externo, con la contraseña todavía en texto plano
+#[derive(Clone, Debug)]
+pub struct ImportedEntry {
+    pub app: String,
+    pub url: String,
+    pub usuario: String,
+    pub mail: String,
+    pub con: String,
+    pub notas: String,
+    pub carpeta: String,
+    pub fecha: Option<String>,
+    pub duplicado: bool,
+}
+
+impl ImportedEntry {
+    fn new(app: &str, url: &str, usuario: &str, con: &str, notas: &str) -> Self {
+        let app = if app.trim().is_empty() { host_from_url(url) } else { app.trim().to_string() };
+        // Si el usuario es un correo lo copiamos también al campo mail
+        let mail = if usuario.contains('@') { usuario.trim().to_string() } else { String::new() };
+        Self {
+            app,
+            url: url.trim().to_string(),
+            usuario: usuario.trim().to_string(),
+            mail,
+            con: con.to_string(),
+            notas: notas.to_string(),
+            carpeta: String::new(),
+            fecha: None,
+            duplicado: false,
+        }
+    }
+}
+
+// Detecta el formato a partir de la cabecera del CSV o de la estructura del JSON
+pub fn detect_format(contenido: &str) -> Option<ImportFormat> {
+    let contenido = contenido.trim_start_matches('\u{feff}').trim_start();
+    if contenido.starts_with('{') {
+        return Some(ImportFormat::BitwardenJson);
+    }
+    // La cabecera se lee con el lector CSV: los nombres pueden ir entre comillas y contener comas
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contenido.as_bytes());
+    let cabecera: Vec<String> = reader.headers().ok()?.iter().map(|c| c.trim().to_lowercase()).collect();
+    let tiene = |nombre: &str| cabecera.iter().any(|c| c == nombre);
+
+    if tiene("login_password") {
+        Some(ImportFormat::BitwardenCsv)
+    } else if tiene("httprealm") || tiene("formactionorigin") {
+        Some(ImportFormat::Firefox)
+    } else if tiene("grouping") || tiene("extra") {
+        Some(ImportFormat::LastPass)
+    } else if tiene("name") && tiene("password") {
+        Some(ImportFormat::Chrome)
+    } else {
+        None
+    }
+}
+
+pub fn parse(formato: ImportFormat, contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let contenido = contenido.trim_start_matches('\u{feff}');
+    match formato {
+        ImportFormat::Chrome => parse_chrome(contenido),
+        ImportFormat::Firefox => parse_firefox(contenido),
+        ImportFormat::BitwardenCsv => parse_bitwarden_csv(contenido),
+        ImportFormat::BitwardenJson => parse_bitwarden_json(contenido),
+        ImportFormat::LastPass => parse_lastpass(contenido),
+    }
+}
+
+// Lee un CSV con cabecera y devuelve cada fila como mapa columna -> valor
+fn read_csv(contenido: &str) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contenido.as_bytes());
+    let cabecera: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Cabecera CSV inválida: {}", e))?
+        .iter()
+        .map(|c| c.trim().to_lowercase())
+        .collect();
+    let mut filas = Vec::new();
+    for (i, registro) in reader.records().enumerate() {
+        let registro = registro.map_err(|e| format!("Error en la fila {}: {}", i + 2, e))?;
+        let fila = cabecera
+            .iter()
+            .cloned()
+            .zip(registro.iter().map(|v| v.to_string()))
+            .collect();
+        filas.push(fila);
+    }
+    Ok(filas)
+}
+
+fn campo<'a>(fila: &'a HashMap<String, String>, nombre: &str) -> &'a str {
+    fila.get(nombre).map(|s| s.as_str()).unwrap_or("")
+}
+
+fn parse_chrome(contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let filas = read_csv(contenido)?;
+    Ok(filas
+        .iter()
+        .map(|f| {
+            // Chrome usa "note" y Edge "notes"
+            let notas = if f.contains_key("note") { campo(f, "note") } else { campo(f, "notes") };
+            ImportedEntry::new(campo(f, "name"), campo(f, "url"), campo(f, "username"), campo(f, "password"), notas)
+        })
+        .collect())
+}
+
+fn parse_firefox(contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let filas = read_csv(contenido)?;
+    Ok(filas
+        .iter()
+        .map(|f| {
+            let mut entrada = ImportedEntry::new("", campo(f, "url"), campo(f, "username"), campo(f, "password"), "");
+            // Firefox guarda las fechas como milisegundos desde epoch
+            entrada.fecha = campo(f, "timepasswordchanged")
+                .parse::<i64>()
+                .ok()
+                .or_else(|| campo(f, "timecreated").parse::<i64>().ok())
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|d| d.to_rfc3339());
+            entrada
+        })
+        .collect())
+}
+
+fn parse_bitwarden_csv(contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let filas = read_csv(contenido)?;
+    Ok(filas
+        .iter()
+        .filter(|f| matches!(campo(f, "type"), "login" | "note" | ""))
+        .map(|f| {
+            // login_uri puede contener varias URLs separadas por coma
+            let url = campo(f, "login_uri").split(',').next().unwrap_or("");
+            let mut entrada = ImportedEntry::new(
+                campo(f, "name"), url, campo(f, "login_username"), campo(f, "login_password"), campo(f, "notes"),
+            );
+            entrada.carpeta = folder_path(campo(f, "folder"), '/');
+            entrada
+        })
+        .collect())
+}
+
+fn parse_bitwarden_json(contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let json: Value = serde_json::from_str(contenido).map_err(|e| format!("JSON inválido: {}", e))?;
+    if json.get("encrypted").and_then(Value::as_bool).unwrap_or(false) {
+        return Err("La exportación de Bitwarden está cifrada; exporta en formato JSON sin cifrar".to_string());
+    }
+    let items = json
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or("El JSON no contiene el campo \"items\"")?;
+    let texto = |v: &Value, clave: &str| v.get(clave).and_then(Value::as_str).unwrap_or("").to_string();
+    // Los elementos apuntan a su carpeta por id
+    let carpetas: HashMap<String, String> = json
+        .get("folders")
+        .and_then(Value::as_array)
+        .map(|lista| lista.iter().map(|c| (texto(c, "id"), texto(c, "name"))).collect())
+        .unwrap_or_default();
+
+    let mut entradas = Vec::new();
+    for item in items {
+        // Tipos de Bitwarden: 1 = login, 2 = nota segura; tarjetas e identidades se ignoran
+        let tipo = item.get("type").and_then(Value::as_i64).unwrap_or(1);
+        if tipo != 1 && tipo != 2 {
+            continue;
+        }
+        let login = item.get("login").cloned().unwrap_or(Value::Null);
+        let url = login
+            .get("uris")
+            .and_then(Value::as_array)
+            .and_then(|uris| uris.first())
+            .map(|u| texto(u, "uri"))
+            .unwrap_or_default();
+        let mut entrada = ImportedEntry::new(
+            &texto(item, "name"), &url, &texto(&login, "username"), &texto(&login, "password"), &texto(item, "notes"),
+        );
+        if let Some(carpeta) = carpetas.get(&texto(item, "folderId")) {
+            entrada.carpeta = folder_path(carpeta, '/');
+        }
+        let revision = texto(item, "revisionDate");
+        if !revision.is_empty() {
+            entrada.fecha = Some(revision);
+        }
+        entradas.push(entrada);
+    }
+    Ok(entradas)
+}
+
+fn parse_lastpass(contenido: &str) -> Result<Vec<ImportedEntry>, String> {
+    let filas = read_csv(contenido)?;
+    Ok(filas
+        .iter()
+        .map(|f| {
+            // LastPass marca las notas seguras con la URL "http://sn"
+            let url = match campo(f, "url") {
+                "http://sn" => "",
+                u => u,
+            };
+            let mut entrada = ImportedEntry::new(
+                campo(f, "name"), url, campo(f, "username"), campo(f, "password"), campo(f, "extra"),
+            );
+            // LastPass separa las subcarpetas con "\"
+            entrada.carpeta = folder_path(campo(f, "grouping"), '\\');
+            entrada
+        })
+        .collect())
+}
+
+// Ruta de carpeta de Vecla ("Trabajo/Correo") a partir de la del gestor de origen
+fn folder_path(carpeta: &str, separador: char) -> String {
+    carpeta.split(separador).map(str::trim).filter(|c| !c.is_empty()).collect::<Vec<_>>().join("/")
+}
+
+fn host_from_url(url: &str) -> String {
+    let sin_esquema = url.split("://").nth(1).unwrap_or(url);
+    sin_esquema
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or("")
+        .trim_start_matches("www.")
+        .to_string()
+}
+
+fn normalize_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_lowercase()
+}
+
+// Marca como duplicadas las entradas que ya existen en la bóveda (misma URL o app,
+// mismo usuario y misma contraseña) o que se repiten dentro del propio archivo
+pub fn mark_duplicates(conn: &Connection, master_key: &str, entradas: &mut [ImportedEntry]) -> Result<usize, String> {
+    let existentes = list_entries(conn).map_err(|e| e.to_string())?;
+    let mut vistas: Vec<(String, String, String, String)> = existentes
+        .iter()
+        .filter_map(|e| {
+            let con = decrypt(master_key, &e.con).ok()?;
+            Some((normalize_url(&e.url), e.app.to_lowercase(), e.usuario.clone(), con))
+        })
+        .collect();
+
+    let mut total = 0;
+    for entrada in entradas.iter_mut() {
+        let url = normalize_url(&entrada.url);
+        let app = entrada.app.to_lowercase();
+        entrada.duplicado = vistas.iter().any(|(u, a, usuario, con)| {
+            (*u == url || *a == app) && *usuario == entrada.usuario && *con == entrada.con
+        });
+        if entrada.duplicado {
+            total += 1;
+        } else {
+            vistas.push((url, app, entrada.usuario.clone(), entrada.con.clone()));
+        }
+    }
+    Ok(total)
+}
+
+// Cifra e inserta las entradas en una única transacción: o entran todas o ninguna
+pub fn commit_import(
+    conn: &mut Connection,
+    master_key: &str,
+    entradas: &[ImportedEntry],
+    omitir_duplicados: bool,
+) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut importadas = 0;
+    for entrada in entradas {
+        if omitir_duplicados && entrada.duplicado {
+            continue;
+        }
+        let enc_con = encrypt(master_key, &entrada.con)?;
+        let mut nueva = PasswordEntry::new(
+            &entrada.app, &entrada.url, &entrada.usuario, &entrada.mail, &enc_con, &entrada.notas,
+        );
+        nueva.carpeta = entrada.carpeta.clone();
+        // La fecha del archivo es la del último cambio de la contraseña (o la de creación)
+        if let Some(fecha) = &entrada.fecha {
+            nueva.fecha = fecha.clone();
+            nueva.con_modificada = fecha.clone();
+        }
+        insert_entry(&tx, &nueva).map_err(|e| format!("Error al importar \"{}\": {}", entrada.app, e))?;
+        importadas += 1;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(importadas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_vault;
+
+    const CHROME: &str = "name,url,username,password,note\n\
+        github.com,https://github.com/login,ana@example.com,gh-secreta,\"nota, con coma\"\n\
+        ,https://www.example.com/path?x=1,bob,ex-secreta,\n";
+    const EDGE: &str = "name,url,username,password,notes\nBanco,https://banco.example.com,ana,b-secreta,nota edge\n";
+    const FIREFOX: &str = "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\",\"timeCreated\",\"timeLastUsed\",\"timePasswordChanged\"\n\
+        \"https://www.mozilla.org\",\"ana\",\"ff-secreta\",,\"https://www.mozilla.org\",\"{1}\",\"1600000000000\",\"1600000000000\",\"1700000000000\"\n";
+    const BITWARDEN_CSV: &str = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+        Trabajo/Correo,,login,Correo,nota bw,,0,\"https://mail.example.com,https://alt.example.com\",ana,bw-secreta,\n\
+        ,,card,Tarjeta,,,0,,,,\n";
+    const BITWARDEN_JSON: &str = r#"{"encrypted": false, "folders": [{"id": "f1", "name": "Trabajo/Correo"}], "items": [
+        {"type": 1, "name": "Correo", "notes": "nota", "revisionDate": "2024-01-02T03:04:05.000Z", "folderId": "f1",
+         "login": {"username": "ana", "password": "bw-secreta", "uris": [{"uri": "https://mail.example.com"}]}},
+        {"type": 2, "name": "Nota segura", "notes": "texto"},
+        {"type": 3, "name": "Tarjeta", "card": {}}
+    ]}"#;
+    const LASTPASS: &str = "url,username,password,totp,extra,name,grouping,fav\n\
+        https://lp.example.com,ana,lp-secreta,,nota lp,LastPass,Personal,0\n\
+        http://sn,,,,contenido de la nota,Nota,Personal\\Notas,0\n";
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(detect_format(CHROME), Some(ImportFormat::Chrome));
+        assert_eq!(detect_format(EDGE), Some(ImportFormat::Chrome));
+        assert_eq!(detect_format(FIREFOX), Some(ImportFormat::Firefox));
+        assert_eq!(detect_format(BITWARDEN_CSV), Some(ImportFormat::BitwardenCsv));
+        assert_eq!(detect_format(BITWARDEN_JSON), Some(ImportFormat::BitwardenJson));
+        assert_eq!(detect_format(LASTPASS), Some(ImportFormat::LastPass));
+        assert_eq!(detect_format(&format!("\u{feff}{}", CHROME)), Some(ImportFormat::Chrome));
+        assert_eq!(detect_format("a,b,c\n1,2,3\n"), None);
+        assert_eq!(detect_format(""), None);
+    }
+
+    #[test]
+    fn detects_quoted_header_with_commas() {
+        // Partida por comas, "note, extra" parecería una cabecera de LastPass
+        let csv = "\"name\",\"url\",\"username\",\"password\",\"note, extra\"\nweb,https://a.com,ana,x,\n";
+        assert_eq!(detect_format(csv), Some(ImportFormat::Chrome));
+    }
+
+    #[test]
+    fn parses_chrome() {
+        let entradas = parse(ImportFormat::Chrome, CHROME).unwrap();
+        assert_eq!(entradas.len(), 2);
+        assert_eq!(entradas[0].app, "github.com");
+        assert_eq!(entradas[0].usuario, "ana@example.com");
+        assert_eq!(entradas[0].mail, "ana@example.com");
+        assert_eq!(entradas[0].con, "gh-secreta");
+        assert_eq!(entradas[0].notas, "nota, con coma");
+        // Sin nombre se usa el servidor de la URL
+        assert_eq!(entradas[1].app, "example.com");
+        assert_eq!(entradas[1].mail, "");
+        let edge = parse(ImportFormat::Chrome, EDGE).unwrap();
+        assert_eq!(edge[0].notas, "nota edge");
+    }
+
+    #[test]
+    fn parses_firefox() {
+        let entradas = parse(ImportFormat::Firefox, FIREFOX).unwrap();
+        assert_eq!(entradas.len(), 1);
+        assert_eq!(entradas[0].app, "mozilla.org");
+        assert_eq!(entradas[0].con, "ff-secreta");
+        assert_eq!(entradas[0].fecha.as_deref(), Some("2023-11-14T22:13:20+00:00"));
+    }
+
+    #[test]
+    fn parses_bitwarden_csv() {
+        let entradas = parse(ImportFormat::BitwardenCsv, BITWARDEN_CSV).unwrap();
+        assert_eq!(entradas.len(), 1);
+        assert_eq!(entradas[0].app, "Correo");
+        assert_eq!(entradas[0].url, "https://mail.example.com");
+        assert_eq!(entradas[0].con, "bw-secreta");
+        assert_eq!(entradas[0].notas, "nota bw");
+        assert_eq!(entradas[0].carpeta, "Trabajo/Correo");
+    }
+
+    #[test]
+    fn parses_bitwarden_json() {
+        let entradas = parse(ImportFormat::BitwardenJson, BITWARDEN_JSON).unwrap();
+        assert_eq!(entradas.len(), 2);
+        assert_eq!(entradas[0].url, "https://mail.example.com");
+        assert_eq!(entradas[0].con, "bw-secreta");
+        assert_eq!(entradas[0].fecha.as_deref(), Some("2024-01-02T03:04:05.000Z"));
+        assert_eq!(entradas[0].carpeta, "Trabajo/Correo");
+        assert_eq!(entradas[1].carpeta, "");
+        assert_eq!((entradas[1].app.as_str(), entradas[1].notas.as_str()), ("Nota segura", "texto"));
+        let cifrado = r#"{"encrypted": true, "items": []}"#;
+        assert!(parse(ImportFormat::BitwardenJson, cifrado).is_err());
+    }
+
+    #[test]
+    fn parses_lastpass() {
+        let entradas = parse(ImportFormat::LastPass, LASTPASS).unwrap();
+        assert_eq!(entradas.len(), 2);
+        assert_eq!(entradas[0].app, "LastPass");
+        assert_eq!(entradas[0].notas, "nota lp");
+        assert_eq!(entradas[0].carpeta, "Personal");
+        assert_eq!(entradas[1].url, "");
+        assert_eq!(entradas[1].notas, "contenido de la nota");
+        assert_eq!(entradas[1].carpeta, "Personal/Notas");
+    }
+
+    #[test]
+    fn marks_duplicates() {
+        let mut conn = test_vault();
+        let existente = PasswordEntry::new("GitHub", "https://github.com/login/", "ana@example.com", "", &encrypt("k", "gh-secreta").unwrap(), "");
+        insert_entry(&conn, &existente).unwrap();
+
+        let mut entradas = parse(ImportFormat::Chrome, CHROME).unwrap();
+        // Repetida dentro del propio archivo
+        entradas.push(entradas[1].clone());
+        // Misma cuenta con otra contraseña: no es duplicado
+        let mut cambiada = entradas[0].clone();
+        cambiada.con = "otra".to_string();
+        entradas.push(cambiada);
+
+        assert_eq!(mark_duplicates(&conn, "k", &mut entradas).unwrap(), 2);
+        let marcas: Vec<bool> = entradas.iter().map(|e| e.duplicado).collect();
+        assert_eq!(marcas, [true, false, true, false]);
+
+        assert_eq!(commit_import(&mut conn, "k", &entradas, true).unwrap(), 2);
+        assert_eq!(list_entries(&conn).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn imports_folders() {
+        let mut conn = test_vault();
+        assert_eq!(folder_path(" /Trabajo// Correo /", '/'), "Trabajo/Correo");
+        let entradas = parse(ImportFormat::LastPass, LASTPASS).unwrap();
+        assert_eq!(commit_import(&mut conn, "k", &entradas, false).unwrap(), 2);
+        let mut carpetas: Vec<String> = list_entries(&conn).unwrap().into_iter().map(|e| e.carpeta).collect();
+        carpetas.sort();
+        assert_eq!(carpetas, ["Personal", "Personal/Notas"]);
+    }
+}
@@ -0,0 +1,1235 @@
+// Lectura y escritura de bases de datos KeePass en formato KDBX 4
+use aes::Aes256;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+use chacha20::ChaCha20;
+use chacha20::cipher::StreamCipher;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use hmac::{Hmac, Mac};
+use quick_xml::events::Event;
+use quick_xml::escape::escape;
+use rand::RngCore;
+use rusqlite::Connection;
+use salsa20::Salsa20;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use uuid::Uuid;
+use crate::crypto::{encrypt, decrypt, encrypt_bytes, decrypt_bytes};
+use crate::db::{
+    PasswordEntry, CustomField, Attachment, HistoryEntry, get_entry, insert_entry, insert_field,
+    insert_attachment, insert_history, list_entries, list_fields, list_attachments, list_history,
+};
+
+const FIRMA_1: u32 = 0x9AA2_D903;
+const FIRMA_2: u32 = 0xB54B_FB67;
+const VERSION_4: u32 = 0x0004_0000;
+
+const CIFRADO_AES256: [u8; 16] = [0x31, 0xc1, 0xf2, 0xe6, 0xbf, 0x71, 0x43, 0x50, 0xbe, 0x58, 0x05, 0x21, 0x6a, 0xfc, 0x5a, 0xff];
+const CIFRADO_CHACHA20: [u8; 16] = [0xd6, 0x03, 0x8a, 0x2b, 0x8b, 0x6f, 0x4c, 0xb5, 0xa5, 0x24, 0x33, 0x9a, 0x31, 0xdb, 0xb5, 0x9a];
+const KDF_AES: [u8; 16] = [0xc9, 0xd9, 0xf3, 0x9a, 0x62, 0x8a, 0x44, 0x60, 0xbf, 0x74, 0x0d, 0x08, 0xc1, 0x8a, 0x4f, 0xea];
+const KDF_ARGON2D: [u8; 16] = [0xef, 0x63, 0x6d, 0xdf, 0x8c, 0x29, 0x44, 0x4b, 0x91, 0xf7, 0xa9, 0xa4, 0x03, 0xe3, 0x0a, 0x0c];
+const KDF_ARGON2ID: [u8; 16] = [0x9e, 0x29, 0x8b, 0x19, 0x56, 0xdb, 0x47, 0x73, 0xb2, 0x3d, 0xfc, 0x3e, 0xc6, 0xf0, 0xa1, 0xe6];
+
+// Parámetros de Argon2 al exportar (similares a los de KeePassXC)
+const ARGON2_MEMORIA: u64 = 64 * 1024 * 1024;
+const ARGON2_ITERACIONES: u64 = 10;
+const ARGON2_PARALELISMO: u32 = 2;
+// Límites al importar, para que un archivo manipulado no agote la memoria ni bloquee la importación
+const AES_KDF_RONDAS_MAXIMAS: u64 = 100_000_000;
+const ARGON2_MEMORIA_MAXIMA_KIB: u32 = 1024 * 1024;
+const ARGON2_ITERACIONES_MAXIMAS: u32 = 1000;
+const ARGON2_PARALELISMO_MAXIMO: u32 = 64;
+
+// Segundos entre 0001-01-01 y 1970-01-01, base de las fechas de KDBX 4
+const SEGUNDOS_HASTA_EPOCH: i64 = 62_135_596_800;
+const TAMANO_BLOQUE: usize = 1024 * 1024;
+
+// Campos estándar de una entrada KeePass
+const CAMPOS_ESTANDAR: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+pub struct KdbxDatabase {
+    pub nombre: String,
+    pub raiz: KdbxGroup,
+}
+
+pub struct KdbxGroup {
+    pub uuid: [u8; 16],
+    pub nombre: String,
+    pub notas: String,
+    pub grupos: Vec<KdbxGroup>,
+    pub entradas: Vec<KdbxEntry>,
+}
+
+pub struct KdbxEntry {
+    pub uuid: [u8; 16],
+    pub campos: Vec<KdbxField>,
+    pub etiquetas: String,
+    pub creado: DateTime<Utc>,
+    pub modificado: DateTime<Utc>,
+    pub adjuntos: Vec<(String, Vec<u8>)>,
+    pub historial: Vec<KdbxEntry>,
+}
+
+pub struct KdbxField {
+    pub clave: String,
+    pub valor: String,
+    pub protegido: bool,
+}
+
+impl KdbxGroup {
+    fn new(nombre: &str) -> Self {
+        Self {
+            uuid: *Uuid::new_v4().as_bytes(),
+            nombre: nombre.to_string(),
+            notas: String::new(),
+            grupos: Vec::new(),
+            entradas: Vec::new(),
+        }
+    }
+}
+
+impl KdbxEntry {
+    pub fn campo(&self, clave: &str) -> &str {
+        self.campos
+            .iter()
+            .find(|c| c.clave == clave)
+            .map(|c| c.valor.as_str())
+            .unwrap_or("")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Lectura de binarios
+// ---------------------------------------------------------------------------
+
+struct Lector<'a> {
+    datos: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lector<'a> {
+    fn new(datos: &'a [u8]) -> Self {
+        Self { datos, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let fin = self.pos.checked_add(n).filter(|&f| f <= self.datos.len())
+            .ok_or("Archivo KDBX truncado")?;
+        let trozo = &self.datos[self.pos..fin];
+        self.pos = fin;
+        Ok(trozo)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn resto(&self) -> &'a [u8] {
+        &self.datos[self.pos..]
+    }
+}
+
+// Diccionario de variantes usado para los parámetros del KDF
+enum Variante {
+    U32(u32),
+    U64(u64),
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    Texto(String),
+    Bytes(Vec<u8>),
+}
+
+fn read_variant_dictionary(datos: &[u8]) -> Result<HashMap<String, Variante>, String> {
+    let mut r = Lector::new(datos);
+    let version = u16::from_le_bytes(r.bytes(2)?.try_into().unwrap());
+    if version >> 8 != 1 {
+        return Err("Versión de diccionario KDF no soportada".to_string());
+    }
+    let mut dic = HashMap::new();
+    loop {
+        let tipo = r.u8()?;
+        if tipo == 0 {
+            break;
+        }
+        let largo_clave = r.i32()? as usize;
+        let clave = String::from_utf8_lossy(r.bytes(largo_clave)?).to_string();
+        let largo_valor = r.i32()? as usize;
+        let valor = r.bytes(largo_valor)?;
+        let variante = match tipo {
+            0x04 => Variante::U32(u32::from_le_bytes(valor.try_into().map_err(|_| "Valor KDF inválido")?)),
+            0x05 => Variante::U64(u64::from_le_bytes(valor.try_into().map_err(|_| "Valor KDF inválido")?)),
+            0x08 => Variante::Bool(valor.first().copied().unwrap_or(0) != 0),
+            0x0C => Variante::I32(i32::from_le_bytes(valor.try_into().map_err(|_| "Valor KDF inválido")?)),
+            0x0D => Variante::I64(i64::from_le_bytes(valor.try_into().map_err(|_| "Valor KDF inválido")?)),
+            0x18 => Variante::Texto(String::from_utf8_lossy(valor).to_string()),
+            0x42 => Variante::Bytes(valor.to_vec()),
+            _ => return Err(format!("Tipo de variante desconocido: {:#x}", tipo)),
+        };
+        dic.insert(clave, variante);
+    }
+    Ok(dic)
+}
+
+fn write_variant_dictionary(entradas: &[(&str, Variante)]) -> Vec<u8> {
+    let mut out = vec![0x00, 0x01];
+    for (clave, valor) in entradas {
+        let (tipo, bytes) = match valor {
+            Variante::U32(v) => (0x04, v.to_le_bytes().to_vec()),
+            Variante::U64(v) => (0x05, v.to_le_bytes().to_vec()),
+            Variante::Bool(v) => (0x08, vec![*v as u8]),
+            Variante::I32(v) => (0x0C, v.to_le_bytes().to_vec()),
+            Variante::I64(v) => (0x0D, v.to_le_bytes().to_vec()),
+            Variante::Texto(v) => (0x18, v.as_bytes().to_vec()),
+            Variante::Bytes(v) => (0x42, v.clone()),
+        };
+        out.push(tipo);
+        out.extend((clave.len() as i32).to_le_bytes());
+        out.extend(clave.as_bytes());
+        out.extend((bytes.len() as i32).to_le_bytes());
+        out.extend(bytes);
+    }
+    out.push(0);
+    out
+}
+
+fn variante_bytes<'a>(dic: &'a HashMap<String, Variante>, clave: &str) -> Result<&'a [u8], String> {
+    match dic.get(clave) {
+        Some(Variante::Bytes(b)) => Ok(b),
+        _ => Err(format!("Falta el parámetro KDF \"{}\"", clave)),
+    }
+}
+
+fn variante_u64(dic: &HashMap<String, Variante>, clave: &str) -> Result<u64, String> {
+    match dic.get(clave) {
+        Some(Variante::U64(v)) => Ok(*v),
+        Some(Variante::U32(v)) => Ok(*v as u64),
+        Some(Variante::I64(v)) => u64::try_from(*v).map_err(|_| format!("Parámetro KDF \"{}\" negativo", clave)),
+        Some(Variante::I32(v)) => u64::try_from(*v).map_err(|_| format!("Parámetro KDF \"{}\" negativo", clave)),
+        _ => Err(format!("Falta el parámetro KDF \"{}\"", clave)),
+    }
+}
+
+// Parámetro KDF como u32, rechazando valores por encima del límite en vez de truncarlos
+fn variante_u32_limitada(valor: u64, clave: &str, maximo: u32) -> Result<u32, String> {
+    match u32::try_from(valor) {
+        Ok(v) if v <= maximo => Ok(v),
+        _ => Err(format!("Parámetro KDF \"{}\" demasiado grande ({}, máximo {})", clave, valor, maximo)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Claves
+// ---------------------------------------------------------------------------
+
+// Clave compuesta: SHA-256 de la concatenación de los componentes (contraseña y archivo de clave)
+fn composite_key(password: &str, key_file: Option<&[u8]>) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    if !password.is_empty() || key_file.is_none() {
+        hasher.update(Sha256::digest(password.as_bytes()));
+    }
+    if let Some(archivo) = key_file {
+        hasher.update(key_file_key(archivo)?);
+    }
+    Ok(hasher.finalize().into())
+}
+
+// Interpreta un archivo de clave de KeePass (XML v1/v2, 32 bytes, 64 hex o cualquier otro archivo)
+pub(crate) fn key_file_key(archivo: &[u8]) -> Result<[u8; 32], String> {
+    if let Ok(texto) = std::str::from_utf8(archivo) {
+        if texto.contains("<KeyFile") {
+            let xml = parse_xml(texto)?;
+            let nodo_datos = xml
+                .hijo("Key")
+                .and_then(|k| k.hijo("Data"))
+                .ok_or("Archivo de clave XML sin <Data>")?;
+            let version = xml.hijo("Meta").map(|m| m.texto_de("Version")).unwrap_or_default();
+            let clave = if version.starts_with('2') {
+                let hex: String = nodo_datos.texto.chars().filter(|c| !c.is_whitespace()).collect();
+                decode_hex(&hex).ok_or("Archivo de clave XML con datos inválidos")?
+            } else {
+                STANDARD.decode(nodo_datos.texto.trim()).map_err(|e| e.to_string())?
+            };
+            return clave.try_into().map_err(|_| "Archivo de clave XML con longitud inválida".to_string());
+        }
+        let recortado = texto.trim();
+        if recortado.len() == 64 {
+            if let Some(clave) = decode_hex(recortado) {
+                return Ok(clave.try_into().unwrap());
+            }
+        }
+    }
+    if archivo.len() == 32 {
+        return Ok(archivo.try_into().unwrap());
+    }
+    Ok(Sha256::digest(archivo).into())
+}
+
+fn decode_hex(texto: &str) -> Option<Vec<u8>> {
+    if !texto.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..texto.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(texto.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn transform_key(parametros: &HashMap<String, Variante>, compuesta: &[u8; 32]) -> Result<[u8; 32], String> {
+    let uuid = variante_bytes(parametros, "$UUID")?;
+    if uuid == KDF_AES {
+        let semilla = variante_bytes(parametros, "S")?;
+        let rondas = variante_u64(parametros, "R")?;
+        if rondas > AES_KDF_RONDAS_MAXIMAS {
+            return Err(format!("Demasiadas rondas AES-KDF ({}, máximo {})", rondas, AES_KDF_RONDAS_MAXIMAS));
+        }
+        let cifrador = Aes256::new_from_slice(semilla).map_err(|_| "Semilla AES-KDF inválida")?;
+        let mut clave = *compuesta;
+        for _ in 0..rondas {
+            for mitad in clave.chunks_mut(16) {
+                cifrador.encrypt_block(mitad.into());
+            }
+        }
+        Ok(Sha256::digest(clave).into())
+    } else if uuid == KDF_ARGON2D || uuid == KDF_ARGON2ID {
+        let algoritmo = if uuid == KDF_ARGON2D { Algorithm::Argon2d } else { Algorithm::Argon2id };
+        let sal = variante_bytes(parametros, "S")?;
+        let memoria = variante_u32_limitada(variante_u64(parametros, "M")? / 1024, "M", ARGON2_MEMORIA_MAXIMA_KIB)?;
+        let iteraciones = variante_u32_limitada(variante_u64(parametros, "I")?, "I", ARGON2_ITERACIONES_MAXIMAS)?;
+        let paralelismo = variante_u32_limitada(variante_u64(parametros, "P")?, "P", ARGON2_PARALELISMO_MAXIMO)?;
+        let version = match variante_u64(parametros, "V").unwrap_or(0x13) {
+            0x10 => Version::V0x10,
+            _ => Version::V0x13,
+        };
+        let params = Params::new(memoria, iteraciones, paralelismo, Some(32))
+            .map_err(|e| format!("Parámetros Argon2 inválidos: {}", e))?;
+        let mut salida = [0u8; 32];
+        Argon2::new(algoritmo, version, params)
+            .hash_password_into(compuesta, sal, &mut salida)
+            .map_err(|e| e.to_string())?;
+        Ok(salida)
+    } else {
+        Err("Función de derivación de clave no soportada".to_string())
+    }
+}
+
+fn hmac_block_key(base: &[u8; 64], indice: u64) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(indice.to_le_bytes());
+    hasher.update(base);
+    hasher.finalize().into()
+}
+
+fn hmac_sha256(clave: &[u8], partes: &[&[u8]]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(clave).expect("HMAC acepta cualquier longitud");
+    for parte in partes {
+        mac.update(parte);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+// Flujo interno que protege los valores marcados como Protected="True"
+enum FlujoInterno {
+    Ninguno,
+    ChaCha(Box<ChaCha20>),
+    Salsa(Box<Salsa20>),
+}
+
+impl FlujoInterno {
+    fn new(id: u32, clave: &[u8]) -> Result<Self, String> {
+        match id {
+            0 => Ok(FlujoInterno::Ninguno),
+            2 => {
+                let clave = Sha256::digest(clave);
+                let nonce = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+                Ok(FlujoInterno::Salsa(Box::new(
+                    <Salsa20 as chacha20::cipher::KeyIvInit>::new(&clave, &nonce.into()),
+                )))
+            }
+            3 => {
+                let hash = Sha512::digest(clave);
+                Ok(FlujoInterno::ChaCha(Box::new(
+                    <ChaCha20 as chacha20::cipher::KeyIvInit>::new(hash[..32].into(), hash[32..44].into()),
+                )))
+            }
+            _ => Err(format!("Flujo interno no soportado: {}", id)),
+        }
+    }
+
+    fn aplicar(&mut self, datos: &mut [u8]) {
+        match self {
+            FlujoInterno::Ninguno => {}
+            FlujoInterno::ChaCha(c) => c.apply_keystream(datos),
+            FlujoInterno::Salsa(s) => s.apply_keystream(datos),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XML
+// ---------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Nodo {
+    nombre: String,
+    atributos: Vec<(String, String)>,
+    texto: String,
+    hijos: Vec<Nodo>,
+}
+
+impl Nodo {
+    fn hijo(&self, nombre: &str) -> Option<&Nodo> {
+        self.hijos.iter().find(|h| h.nombre == nombre)
+    }
+
+    fn hijos_llamados<'a>(&'a self, nombre: &'a str) -> impl Iterator<Item = &'a Nodo> + 'a {
+        self.hijos.iter().filter(move |h| h.nombre == nombre)
+    }
+
+    fn texto_de(&self, nombre: &str) -> String {
+        self.hijo(nombre).map(|h| h.texto.clone()).unwrap_or_default()
+    }
+
+    fn atributo(&self, nombre: &str) -> Option<&str> {
+        self.atributos.iter().find(|(k, _)| k == nombre).map(|(_, v)| v.as_str())
+    }
+}
+
+fn parse_xml(xml: &str) -> Result<Nodo, String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut pila: Vec<Nodo> = vec![Nodo::default()];
+    let abrir = |e: &quick_xml::events::BytesStart| -> Result<Nodo, String> {
+        let mut nodo = Nodo {
+            nombre: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+            ..Default::default()
+        };
+        for atributo in e.attributes() {
+            let atributo = atributo.map_err(|e| e.to_string())?;
+            let valor = atributo.unescape_value().map_err(|e| e.to_string())?;
+            nodo.atributos.push((String::from_utf8_lossy(atributo.key.as_ref()).to_string(), valor.to_string()));
+        }
+        Ok(nodo)
+    };
+    loop {
+        match reader.read_event().map_err(|e| format!("XML inválido: {}", e))? {
+            Event::Start(e) => pila.push(abrir(&e)?),
+            Event::Empty(e) => {
+                let nodo = abrir(&e)?;
+                pila.last_mut().unwrap().hijos.push(nodo);
+            }
+            Event::End(_) => {
+                let nodo = pila.pop().unwrap();
+                pila.last_mut().ok_or("XML mal formado")?.hijos.push(nodo);
+            }
+            Event::Text(t) => {
+                let texto = t.unescape().map_err(|e| e.to_string())?;
+                pila.last_mut().unwrap().texto.push_str(&texto);
+            }
+            Event::CData(t) => {
+                pila.last_mut().unwrap().texto.push_str(&String::from_utf8_lossy(&t));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let documento = pila.pop().ok_or("XML mal formado")?;
+    documento.hijos.into_iter().next().ok_or_else(|| "XML vacío".to_string())
+}
+
+// Descifra los valores protegidos en orden de documento
+fn unprotect(nodo: &mut Nodo, flujo: &mut FlujoInterno) -> Result<(), String> {
+    if nodo.nombre == "Value" && nodo.atributo("Protected") == Some("True") {
+        let mut datos = STANDARD.decode(nodo.texto.trim()).map_err(|e| e.to_string())?;
+        flujo.aplicar(&mut datos);
+        nodo.texto = String::from_utf8_lossy(&datos).to_string();
+    }
+    for hijo in &mut nodo.hijos {
+        unprotect(hijo, flujo)?;
+    }
+    Ok(())
+}
+
+fn parse_time(texto: &str) -> DateTime<Utc> {
+    let texto = texto.trim();
+    if let Ok(fecha) = DateTime::parse_from_rfc3339(texto) {
+        return fecha.with_timezone(&Utc);
+    }
+    STANDARD.decode(texto)
+        .ok()
+        .and_then(|b| <[u8; 8]>::try_from(b.as_slice()).ok())
+        .and_then(|b| DateTime::from_timestamp(i64::from_le_bytes(b) - SEGUNDOS_HASTA_EPOCH, 0))
+        .unwrap_or_else(Utc::now)
+}
+
+fn format_time(fecha: &DateTime<Utc>) -> String {
+    STANDARD.encode((fecha.timestamp() + SEGUNDOS_HASTA_EPOCH).to_le_bytes())
+}
+
+fn parse_uuid(texto: &str) -> [u8; 16] {
+    STANDARD.decode(texto.trim())
+        .ok()
+        .and_then(|b| <[u8; 16]>::try_from(b.as_slice()).ok())
+        .unwrap_or_else(|| *Uuid::new_v4().as_bytes())
+}
+
+fn group_from_xml(nodo: &Nodo, binarios: &[Vec<u8>], papelera: Option<[u8; 16]>) -> KdbxGroup {
+    KdbxGroup {
+        uuid: parse_uuid(&nodo.texto_de("UUID")),
+        nombre: nodo.texto_de("Name"),
+        notas: nodo.texto_de("Notes"),
+        entradas: nodo.hijos_llamados("Entry").map(|e| entry_from_xml(e, binarios)).collect(),
+        grupos: nodo
+            .hijos_llamados("Group")
+            .filter(|g| Some(parse_uuid(&g.texto_de("UUID"))) != papelera)
+            .map(|g| group_from_xml(g, binarios, papelera))
+            .collect(),
+    }
+}
+
+fn entry_from_xml(nodo: &Nodo, binarios: &[Vec<u8>]) -> KdbxEntry {
+    let tiempos = nodo.hijo("Times");
+    let tiempo = |nombre: &str| tiempos.map(|t| parse_time(&t.texto_de(nombre))).unwrap_or_else(Utc::now);
+    KdbxEntry {
+        uuid: parse_uuid(&nodo.texto_de("UUID")),
+        campos: nodo
+            .hijos_llamados("String")
+            .map(|s| KdbxField {
+                clave: s.texto_de("Key"),
+                valor: s.hijo("Value").map(|v| v.texto.clone()).unwrap_or_default(),
+                protegido: s.hijo("Value").and_then(|v| v.atributo("Protected")) == Some("True"),
+            })
+            .collect(),
+        etiquetas: nodo.texto_de("Tags"),
+        creado: tiempo("CreationTime"),
+        modificado: tiempo("LastModificationTime"),
+        adjuntos: nodo
+            .hijos_llamados("Binary")
+            .filter_map(|b| {
+                let referencia: usize = b.hijo("Value")?.atributo("Ref")?.parse().ok()?;
+                Some((b.texto_de("Key"), binarios.get(referencia)?.clone()))
+            })
+            .collect(),
+        historial: nodo
+            .hijo("History")
+            .map(|h| h.hijos_llamados("Entry").map(|e| entry_from_xml(e, binarios)).collect())
+            .unwrap_or_default(),
+    }
+}
+
+struct EscritorXml<'a> {
+    xml: String,
+    flujo: &'a mut FlujoInterno,
+    binarios: Vec<Vec<u8>>,
+}
+
+impl EscritorXml<'_> {
+    fn elemento(&mut self, nombre: &str, valor: &str) {
+        self.xml.push_str(&format!("<{0}>{1}</{0}>", nombre, escape(valor)));
+    }
+
+    fn tiempos(&mut self, creado: &DateTime<Utc>, modificado: &DateTime<Utc>) {
+        self.xml.push_str("<Times>");
+        self.elemento("CreationTime", &format_time(creado));
+        self.elemento("LastModificationTime", &format_time(modificado));
+        self.elemento("LastAccessTime", &format_time(modificado));
+        self.elemento("ExpiryTime", &format_time(modificado));
+        self.elemento("Expires", "False");
+        self.elemento("UsageCount", "0");
+        self.elemento("LocationChanged", &format_time(modificado));
+        self.xml.push_str("</Times>");
+    }
+
+    fn grupo(&mut self, grupo: &KdbxGroup) {
+        let ahora = Utc::now();
+        self.xml.push_str("<Group>");
+        self.elemento("UUID", &STANDARD.encode(grupo.uuid));
+        self.elemento("Name", &grupo.nombre);
+        self.elemento("Notes", &grupo.notas);
+        self.elemento("IconID", "48");
+        self.tiempos(&ahora, &ahora);
+        self.elemento("IsExpanded", "True");
+        for entrada in &grupo.entradas {
+            self.entrada(entrada, true);
+        }
+        for subgrupo in &grupo.grupos {
+            self.grupo(subgrupo);
+        }
+        self.xml.push_str("</Group>");
+    }
+
+    fn entrada(&mut self, entrada: &KdbxEntry, con_historial: bool) {
+        self.xml.push_str("<Entry>");
+        self.elemento("UUID", &STANDARD.encode(entrada.uuid));
+        self.elemento("IconID", "0");
+        self.tiempos(&entrada.creado, &entrada.modificado);
+        self.elemento("Tags", &entrada.etiquetas);
+        for campo in &entrada.campos {
+            self.xml.push_str("<String>");
+            self.elemento("Key", &campo.clave);
+            if campo.protegido {
+                let mut datos = campo.valor.as_bytes().to_vec();
+                self.flujo.aplicar(&mut datos);
+                self.xml.push_str(&format!("<Value Protected=\"True\">{}</Value>", STANDARD.encode(&datos)));
+            } else {
+                self.elemento("Value", &campo.valor);
+            }
+            self.xml.push_str("</String>");
+        }
+        for (nombre, datos) in &entrada.adjuntos {
+            self.binarios.push(datos.clone());
+            self.xml.push_str("<Binary>");
+            self.elemento("Key", nombre);
+            self.xml.push_str(&format!("<Value Ref=\"{}\"/>", self.binarios.len() - 1));
+            self.xml.push_str("</Binary>");
+        }
+        if con_historial && !entrada.historial.is_empty() {
+            self.xml.push_str("<History>");
+            for anterior in &entrada.historial {
+                self.entrada(anterior, false);
+            }
+            self.xml.push_str("</History>");
+        }
+        self.xml.push_str("</Entry>");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Formato de archivo
+// ---------------------------------------------------------------------------
+
+pub fn read(datos: &[u8], password: &str, key_file: Option<&[u8]>) -> Result<KdbxDatabase, String> {
+    let mut r = Lector::new(datos);
+    if r.u32()? != FIRMA_1 || r.u32()? != FIRMA_2 {
+        return Err("El archivo no es una base de datos KeePass".to_string());
+    }
+    let version = r.u32()?;
+    if version >> 16 != 4 {
+        return Err(format!("Versión KDBX {}.{} no soportada (solo KDBX 4)", version >> 16, version & 0xFFFF));
+    }
+
+    // Cabecera externa
+    let mut cifrado = None;
+    let mut comprimido = false;
+    let mut semilla = None;
+    let mut iv = None;
+    let mut parametros_kdf = None;
+    loop {
+        let id = r.u8()?;
+        let largo = r.u32()? as usize;
+        let valor = r.bytes(largo)?;
+        match id {
+            0 => break,
+            2 => cifrado = Some(valor.to_vec()),
+            3 => comprimido = valor.first().copied().unwrap_or(0) == 1,
+            4 => semilla = Some(valor.to_vec()),
+            7 => iv = Some(valor.to_vec()),
+            11 => parametros_kdf = Some(read_variant_dictionary(valor)?),
+            _ => {}
+        }
+    }
+    let cabecera = &datos[..r.pos];
+    let hash_cabecera = r.bytes(32)?;
+    let hmac_cabecera = r.bytes(32)?;
+    if Sha256::digest(cabecera).as_slice() != hash_cabecera {
+        return Err("La cabecera del archivo está dañada".to_string());
+    }
+
+    let cifrado = cifrado.ok_or("Falta el algoritmo de cifrado en la cabecera")?;
+    let semilla = semilla.ok_or("Falta la semilla maestra en la cabecera")?;
+    let iv = iv.ok_or("Falta el vector de inicialización en la cabecera")?;
+    let parametros_kdf = parametros_kdf.ok_or("Faltan los parámetros KDF en la cabecera")?;
+
+    let compuesta = composite_key(password, key_file)?;
+    let transformada = transform_key(&parametros_kdf, &compuesta)?;
+    let base_hmac: [u8; 64] = Sha512::new()
+        .chain_update(&semilla)
+        .chain_update(transformada)
+        .chain_update([1u8])
+        .finalize()
+        .into();
+    if hmac_sha256(&hmac_block_key(&base_hmac, u64::MAX), &[cabecera]) != hmac_cabecera {
+        return Err("Clave incorrecta o archivo dañado".to_string());
+    }
+
+    // Bloques autenticados con HMAC
+    let mut cuerpo = Vec::new();
+    for indice in 0u64.. {
+        let hmac = r.bytes(32)?;
+        let tamano = r.i32()?;
+        let bloque = r.bytes(tamano.max(0) as usize)?;
+        let esperado = hmac_sha256(
+            &hmac_block_key(&base_hmac, indice),
+            &[&indice.to_le_bytes(), &tamano.to_le_bytes(), bloque],
+        );
+        if esperado != hmac {
+            return Err(format!("El bloque {} está dañado", indice));
+        }
+        if tamano == 0 {
+            break;
+        }
+        cuerpo.extend_from_slice(bloque);
+    }
+
+    let clave: [u8; 32] = Sha256::new().chain_update(&semilla).chain_update(transformada).finalize().into();
+    let descifrado = if cifrado == CIFRADO_CHACHA20 {
+        let mut cipher = <ChaCha20 as chacha20::cipher::KeyIvInit>::new_from_slices(&clave, &iv)
+            .map_err(|_| "Vector de inicialización inválido")?;
+        cipher.apply_keystream(&mut cuerpo);
+        cuerpo
+    } else if cifrado == CIFRADO_AES256 {
+        cbc::Decryptor::<Aes256>::new_from_slices(&clave, &iv)
+            .map_err(|_| "Vector de inicialización inválido")?
+            .decrypt_padded_vec_mut::<Pkcs7>(&cuerpo)
+            .map_err(|_| "Error al descifrar el contenido")?
+    } else {
+        return Err("Algoritmo de cifrado no soportado (solo AES-256 y ChaCha20)".to_string());
+    };
+    let contenido = if comprimido {
+        let mut salida = Vec::new();
+        GzDecoder::new(descifrado.as_slice())
+            .read_to_end(&mut salida)
+            .map_err(|e| format!("Error al descomprimir: {}", e))?;
+        salida
+    } else {
+        descifrado
+    };
+
+    // Cabecera interna: flujo de protección y adjuntos
+    let mut r = Lector::new(&contenido);
+    let mut id_flujo = 0;
+    let mut clave_flujo = Vec::new();
+    let mut binarios = Vec::new();
+    loop {
+        let id = r.u8()?;
+        let largo = r.u32()? as usize;
+        let valor = r.bytes(largo)?;
+        match id {
+            0 => break,
+            1 => id_flujo = u32::from_le_bytes(valor.try_into().map_err(|_| "Cabecera interna inválida")?),
+            2 => clave_flujo = valor.to_vec(),
+            // El primer byte de cada adjunto son banderas de protección en memoria
+            3 => binarios.push(valor.get(1..).unwrap_or_default().to_vec()),
+            _ => {}
+        }
+    }
+    let mut flujo = FlujoInterno::new(id_flujo, &clave_flujo)?;
+    let xml = std::str::from_utf8(r.resto()).map_err(|e| e.to_string())?;
+    let mut documento = parse_xml(xml)?;
+    unprotect(&mut documento, &mut flujo)?;
+
+    let meta = documento.hijo("Meta");
+    let papelera = meta
+        .filter(|m| m.texto_de("RecycleBinEnabled") == "True")
+        .map(|m| parse_uuid(&m.texto_de("RecycleBinUUID")));
+    let raiz = documento
+        .hijo("Root")
+        .and_then(|r| r.hijo("Group"))
+        .ok_or("La base de datos no contiene un grupo raíz")?;
+    Ok(KdbxDatabase {
+        nombre: meta.map(|m| m.texto_de("DatabaseName")).unwrap_or_default(),
+        raiz: group_from_xml(raiz, &binarios, papelera),
+    })
+}
+
+pub fn write(db: &KdbxDatabase, password: &str, key_file: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let mut rng = rand::thread_rng();
+    let mut semilla = [0u8; 32];
+    let mut iv = [0u8; 12];
+    let mut sal = [0u8; 32];
+    let mut clave_flujo = [0u8; 64];
+    rng.fill_bytes(&mut semilla);
+    rng.fill_bytes(&mut iv);
+    rng.fill_bytes(&mut sal);
+    rng.fill_bytes(&mut clave_flujo);
+
+    let parametros_kdf = write_variant_dictionary(&[
+        ("$UUID", Variante::Bytes(KDF_ARGON2D.to_vec())),
+        ("S", Variante::Bytes(sal.to_vec())),
+        ("P", Variante::U32(ARGON2_PARALELISMO)),
+        ("M", Variante::U64(ARGON2_MEMORIA)),
+        ("I", Variante::U64(ARGON2_ITERACIONES)),
+        ("V", Variante::U32(0x13)),
+    ]);
+
+    let mut cabecera = Vec::new();
+    cabecera.extend(FIRMA_1.to_le_bytes());
+    cabecera.extend(FIRMA_2.to_le_bytes());
+    cabecera.extend(VERSION_4.to_le_bytes());
+    let campos: [(u8, &[u8]); 6] = [
+        (2, &CIFRADO_CHACHA20),
+        (3, &1u32.to_le_bytes()),
+        (4, &semilla),
+        (7, &iv),
+        (11, &parametros_kdf),
+        (0, b"\r\n\r\n"),
+    ];
+    for (id, valor) in campos {
+        cabecera.push(id);
+        cabecera.extend((valor.len() as u32).to_le_bytes());
+        cabecera.extend(valor);
+    }
+
+    let parametros = read_variant_dictionary(&parametros_kdf)?;
+    let compuesta = composite_key(password, key_file)?;
+    let transformada = transform_key(&parametros, &compuesta)?;
+    let base_hmac: [u8; 64] = Sha512::new()
+        .chain_update(semilla)
+        .chain_update(transformada)
+        .chain_update([1u8])
+        .finalize()
+        .into();
+
+    // XML con los valores protegidos por el flujo interno ChaCha20
+    let mut flujo = FlujoInterno::new(3, &clave_flujo)?;
+    let mut escritor = EscritorXml { xml: String::new(), flujo: &mut flujo, binarios: Vec::new() };
+    escritor.xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile><Meta>");
+    escritor.elemento("Generator", "Vecla");
+    escritor.elemento("DatabaseName", &db.nombre);
+    escritor.xml.push_str(
+        "<MemoryProtection><ProtectTitle>False</ProtectTitle><ProtectUserName>False</ProtectUserName>\
+         <ProtectPassword>True</ProtectPassword><ProtectURL>False</ProtectURL><ProtectNotes>False</ProtectNotes>\
+         </MemoryProtection>",
+    );
+    escritor.elemento("RecycleBinEnabled", "False");
+    escritor.xml.push_str("</Meta><Root>");
+    escritor.grupo(&db.raiz);
+    escritor.xml.push_str("</Root></KeePassFile>");
+    let EscritorXml { xml, binarios, .. } = escritor;
+
+    let mut interno = Vec::new();
+    let mut campo_interno = |id: u8, valor: &[u8]| {
+        interno.push(id);
+        interno.extend((valor.len() as u32).to_le_bytes());
+        interno.extend(valor);
+    };
+    campo_interno(1, &3u32.to_le_bytes());
+    campo_interno(2, &clave_flujo);
+    for binario in &binarios {
+        let mut valor = vec![0u8];
+        valor.extend(binario);
+        campo_interno(3, &valor);
+    }
+    campo_interno(0, &[]);
+    interno.extend(xml.as_bytes());
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&interno).map_err(|e| e.to_string())?;
+    let mut cuerpo = gz.finish().map_err(|e| e.to_string())?;
+
+    let clave: [u8; 32] = Sha256::new().chain_update(semilla).chain_update(transformada).finalize().into();
+    let mut cipher = <ChaCha20 as chacha20::cipher::KeyIvInit>::new(&clave.into(), &iv.into());
+    cipher.apply_keystream(&mut cuerpo);
+
+    let mut salida = cabecera.clone();
+    salida.extend(Sha256::digest(&cabecera));
+    salida.extend(hmac_sha256(&hmac_block_key(&base_hmac, u64::MAX), &[&cabecera]));
+    let mut bloques: Vec<&[u8]> = cuerpo.chunks(TAMANO_BLOQUE).collect();
+    bloques.push(&[]);
+    for (indice, bloque) in bloques.into_iter().enumerate() {
+        let indice = indice as u64;
+        let tamano = bloque.len() as i32;
+        salida.extend(hmac_sha256(
+            &hmac_block_key(&base_hmac, indice),
+            &[&indice.to_le_bytes(), &tamano.to_le_bytes(), bloque],
+        ));
+        salida.extend(tamano.to_le_bytes());
+        salida.extend(bloque);
+    }
+    Ok(salida)
+}
+
+// ---------------------------------------------------------------------------
+// Conversión entre KeePass y la bóveda
+// ---------------------------------------------------------------------------
+
+fn es_campo_mail(clave: &str) -> bool {
+    matches!(clave.to_lowercase().as_str(), "mail" | "email" | "e-mail")
+}
+
+fn entry_to_vecla(entrada: &KdbxEntry, id: &str, carpeta: &str, master_key: &str) -> Result<PasswordEntry, String> {
+    let mail = entrada
+        .campos
+        .iter()
+        .find(|c| es_campo_mail(&c.clave))
+        .map(|c| c.valor.as_str())
+        .unwrap_or("");
+    let mut nueva = PasswordEntry::new(
+        entrada.campo("Title"),
+        entrada.campo("URL"),
+        entrada.campo("UserName"),
+        mail,
+        &encrypt(master_key, entrada.campo("Password"))?,
+        entrada.campo("Notes"),
+    );
+    nueva.id = id.to_string();
+    nueva.carpeta = carpeta.to_string();
+    nueva.etiquetas = entrada
+        .etiquetas
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(",");
+    nueva.fecha = entrada.creado.to_rfc3339();
+    nueva.modificado = entrada.modificado.to_rfc3339();
+    nueva.con_modificada = password_changed(entrada).to_rfc3339();
+    Ok(nueva)
+}
+
+// KeePass no guarda cuándo cambió la contraseña: es la modificación de la versión más
+// antigua que ya tenía la contraseña actual, sin cambios desde entonces
+fn password_changed(entrada: &KdbxEntry) -> DateTime<Utc> {
+    let mut versiones: Vec<&KdbxEntry> = entrada.historial.iter().collect();
+    versiones.sort_by_key(|v| v.modificado);
+    versiones
+        .iter()
+        .rev()
+        .take_while(|v| v.campo("Password") == entrada.campo("Password"))
+        .last()
+        .map_or(entrada.modificado, |v| v.modificado)
+}
+
+fn import_group(
+    conn: &Connection,
+    master_key: &str,
+    grupo: &KdbxGroup,
+    carpeta: &str,
+    totales: &mut (usize, usize),
+) -> Result<(), String> {
+    for entrada in &grupo.entradas {
+        let id = Uuid::from_bytes(entrada.uuid).to_string();
+        // Si ya se importó antes (mismo UUID) no se duplica
+        if get_entry(conn, &id).map_err(|e| e.to_string())?.is_some() {
+            totales.1 += 1;
+            continue;
+        }
+        let nueva = entry_to_vecla(entrada, &id, carpeta, master_key)?;
+        insert_entry(conn, &nueva).map_err(|e| e.to_string())?;
+        for campo in entrada.campos.iter().filter(|c| !CAMPOS_ESTANDAR.contains(&c.clave.as_str()) && !es_campo_mail(&c.clave)) {
+            let campo = CustomField {
+                nombre: campo.clave.clone(),
+                valor: encrypt(master_key, &campo.valor)?,
+                protegido: campo.protegido,
+            };
+            insert_field(conn, &id, &campo).map_err(|e| e.to_string())?;
+        }
+        for (nombre, datos) in &entrada.adjuntos {
+            let adjunto = Attachment {
+                id: Uuid::new_v4().to_string(),
+                nombre: nombre.clone(),
+                datos: encrypt_bytes(master_key, datos)?,
+            };
+            insert_attachment(conn, &id, &adjunto).map_err(|e| e.to_string())?;
+        }
+        for anterior in &entrada.historial {
+            let version = entry_to_vecla(anterior, &Uuid::new_v4().to_string(), carpeta, master_key)?;
+            insert_history(conn, &HistoryEntry { entry_id: id.clone(), entrada: version }).map_err(|e| e.to_string())?;
+        }
+        totales.0 += 1;
+    }
+    for subgrupo in &grupo.grupos {
+        let ruta = if carpeta.is_empty() { subgrupo.nombre.clone() } else { format!("{}/{}", carpeta, subgrupo.nombre) };
+        import_group(conn, master_key, subgrupo, &ruta, totales)?;
+    }
+    Ok(())
+}
+
+// Importa la base de datos en una transacción. Los grupos se convierten en carpetas
+// (sin incluir el grupo raíz). Devuelve (importadas, omitidas por existir ya).
+pub fn import_into(conn: &mut Connection, master_key: &str, db: &KdbxDatabase) -> Result<(usize, usize), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut totales = (0, 0);
+    import_group(&tx, master_key, &db.raiz, "", &mut totales)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(totales)
+}
+
+fn vecla_to_entry(entrada: &PasswordEntry, master_key: &str) -> Result<KdbxEntry, String> {
+    let campo = |clave: &str, valor: &str, protegido: bool| KdbxField {
+        clave: clave.to_string(),
+        valor: valor.to_string(),
+        protegido,
+    };
+    let mut campos = vec![
+        campo("Title", &entrada.app, false),
+        campo("UserName", &entrada.usuario, false),
+        campo("Password", &decrypt(master_key, &entrada.con)?, true),
+        campo("URL", &entrada.url, false),
+        campo("Notes", &entrada.notas, false),
+    ];
+    if !entrada.mail.is_empty() {
+        campos.push(campo("Mail", &entrada.mail, false));
+    }
+    let fecha = |texto: &str| {
+        DateTime::parse_from_rfc3339(texto).map(|f| f.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now())
+    };
+    Ok(KdbxEntry {
+        uuid: Uuid::parse_str(&entrada.id).map(|u| *u.as_bytes()).unwrap_or_else(|_| *Uuid::new_v4().as_bytes()),
+        campos,
+        etiquetas: entrada.etiquetas.split(',').filter(|t| !t.is_empty()).collect::<Vec<_>>().join(";"),
+        creado: fecha(&entrada.fecha),
+        modificado: fecha(&entrada.modificado),
+        adjuntos: Vec::new(),
+        historial: Vec::new(),
+    })
+}
+
+// Busca (o crea) el grupo correspondiente a una ruta de carpeta
+fn group_for_path<'a>(raiz: &'a mut KdbxGroup, carpeta: &str) -> &'a mut KdbxGroup {
+    let mut grupo = raiz;
+    for nombre in carpeta.split('/').filter(|n| !n.is_empty()) {
+        let indice = match grupo.grupos.iter().position(|g| g.nombre == nombre) {
+            Some(i) => i,
+            None => {
+                grupo.grupos.push(KdbxGroup::new(nombre));
+                grupo.grupos.len() - 1
+            }
+        };
+        grupo = &mut grupo.grupos[indice];
+    }
+    grupo
+}
+
+pub fn export_from(conn: &Connection, master_key: &str) -> Result<KdbxDatabase, String> {
+    let mut raiz = KdbxGroup::new("Vecla");
+    for entrada in list_entries(conn).map_err(|e| e.to_string())? {
+        let mut exportada = vecla_to_entry(&entrada, master_key)?;
+        for campo in list_fields(conn, &entrada.id).map_err(|e| e.to_string())? {
+            exportada.campos.push(KdbxField {
+                clave: campo.nombre,
+                valor: decrypt(master_key, &campo.valor)?,
+                protegido: campo.protegido,
+            });
+        }
+        for adjunto in list_attachments(conn, &entrada.id).map_err(|e| e.to_string())? {
+            exportada.adjuntos.push((adjunto.nombre, decrypt_bytes(master_key, &adjunto.datos)?));
+        }
+        for anterior in list_history(conn, &entrada.id).map_err(|e| e.to_string())? {
+            let mut version = vecla_to_entry(&anterior.entrada, master_key)?;
+            version.uuid = exportada.uuid;
+            exportada.historial.push(version);
+        }
+        group_for_path(&mut raiz, &entrada.carpeta).entradas.push(exportada);
+    }
+    Ok(KdbxDatabase { nombre: "Vecla".to_string(), raiz })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_vault;
+
+    const CLAVE: &str = "maestra";
+    // Generadas por tests/fixtures/make_kdbx.py
+    const AES_KDF: &[u8] = include_bytes!("../tests/fixtures/aes-kdf.kdbx");
+    const ARGON2: &[u8] = include_bytes!("../tests/fixtures/argon2.kdbx");
+    const ARGON2_CLAVE: &[u8] = include_bytes!("../tests/fixtures/argon2.keyx");
+
+    fn campo(clave: &str, valor: &str, protegido: bool) -> KdbxField {
+        KdbxField { clave: clave.to_string(), valor: valor.to_string(), protegido }
+    }
+
+    fn sample() -> KdbxDatabase {
+        let fecha = |texto: &str| DateTime::parse_from_rfc3339(texto).unwrap().with_timezone(&Utc);
+        let anterior = KdbxEntry {
+            uuid: [7; 16],
+            campos: vec![campo("Title", "Banco", false), campo("Password", "vieja", true)],
+            etiquetas: String::new(),
+            creado: fecha("2023-01-01T00:00:00Z"),
+            modificado: fecha("2023-01-01T00:00:00Z"),
+            adjuntos: Vec::new(),
+            historial: Vec::new(),
+        };
+        let mut raiz = KdbxGroup::new("Raíz");
+        let mut grupo = KdbxGroup::new("Finanzas");
+        grupo.entradas.push(KdbxEntry {
+            uuid: [7; 16],
+            campos: vec![
+                campo("Title", "Banco <&>", false),
+                campo("UserName", "ana", false),
+                campo("Password", "ñandú-2024", true),
+                campo("URL", "https://banco.example.com", false),
+                campo("Notes", "línea 1\nlínea 2", false),
+                campo("PIN", "1234", true),
+            ],
+            etiquetas: "dinero;web".to_string(),
+            creado: fecha("2023-01-01T00:00:00Z"),
+            modificado: fecha("2024-02-03T04:05:06Z"),
+            adjuntos: vec![("clave.bin".to_string(), vec![0, 1, 2, 255])],
+            historial: vec![anterior],
+        });
+        raiz.grupos.push(grupo);
+        KdbxDatabase { nombre: "Prueba".to_string(), raiz }
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let datos = write(&sample(), "secreta", None).unwrap();
+        let leida = read(&datos, "secreta", None).unwrap();
+        assert_eq!(leida.nombre, "Prueba");
+        let grupo = &leida.raiz.grupos[0];
+        assert_eq!(grupo.nombre, "Finanzas");
+        let entrada = &grupo.entradas[0];
+        assert_eq!(entrada.uuid, [7; 16]);
+        assert_eq!(entrada.campo("Title"), "Banco <&>");
+        assert_eq!(entrada.campo("Password"), "ñandú-2024");
+        assert_eq!(entrada.campo("Notes"), "línea 1\nlínea 2");
+        assert!(entrada.campos.iter().find(|c| c.clave == "PIN").unwrap().protegido);
+        assert_eq!(entrada.etiquetas, "dinero;web");
+        assert_eq!(entrada.modificado.to_rfc3339(), "2024-02-03T04:05:06+00:00");
+        assert_eq!(entrada.adjuntos, [("clave.bin".to_string(), vec![0, 1, 2, 255])]);
+        assert_eq!(entrada.historial.len(), 1);
+        assert_eq!(entrada.historial[0].campo("Password"), "vieja");
+    }
+
+    #[test]
+    fn key_file_is_required() {
+        let datos = write(&sample(), "secreta", Some(ARGON2_CLAVE)).unwrap();
+        assert!(read(&datos, "secreta", Some(ARGON2_CLAVE)).is_ok());
+        assert_eq!(read(&datos, "secreta", None).err().unwrap(), "Clave incorrecta o archivo dañado");
+        assert!(read(&datos, "secreta", Some(b"otro archivo")).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_password() {
+        let datos = write(&sample(), "secreta", None).unwrap();
+        assert_eq!(read(&datos, "otra", None).err().unwrap(), "Clave incorrecta o archivo dañado");
+        assert_eq!(read(AES_KDF, "otra", None).err().unwrap(), "Clave incorrecta o archivo dañado");
+        assert_eq!(read(ARGON2, "vecla-argon2", None).err().unwrap(), "Clave incorrecta o archivo dañado");
+    }
+
+    #[test]
+    fn rejects_tampered_blocks() {
+        let mut datos = write(&sample(), "secreta", None).unwrap();
+        let ultimo = datos.len() - 40;
+        datos[ultimo] ^= 1;
+        assert!(read(&datos, "secreta", None).is_err());
+    }
+
+    // Contenido común de los dos archivos de prueba
+    fn check_fixture(db: &KdbxDatabase) {
+        assert_eq!(db.nombre, "Prueba");
+        let correo = &db.raiz.entradas[0];
+        assert_eq!(correo.campo("Title"), "Correo");
+        assert_eq!(correo.campo("Password"), "nueva-2024");
+        assert_eq!(correo.campo("PIN"), "4321");
+        assert_eq!(correo.modificado.to_rfc3339(), "2024-03-01T12:00:00+00:00");
+        assert_eq!(correo.adjuntos, [("clave.txt".to_string(), b"adjunto\x00\x01\xff".to_vec())]);
+        assert_eq!(correo.historial[0].campo("Password"), "vieja-2023");
+        // La papelera no se importa
+        let grupos: Vec<&str> = db.raiz.grupos.iter().map(|g| g.nombre.as_str()).collect();
+        assert_eq!(grupos, ["Trabajo"]);
+        assert_eq!(db.raiz.grupos[0].entradas[0].campo("Password"), "vpn-clave");
+    }
+
+    #[test]
+    fn reads_aes_kdf_fixture() {
+        check_fixture(&read(AES_KDF, "vecla-aes", None).unwrap());
+    }
+
+    #[test]
+    fn reads_argon2_fixture_with_key_file() {
+        check_fixture(&read(ARGON2, "vecla-argon2", Some(ARGON2_CLAVE)).unwrap());
+    }
+
+    fn argon2(memoria: u64, iteraciones: u64, paralelismo: u32) -> HashMap<String, Variante> {
+        HashMap::from([
+            ("$UUID".to_string(), Variante::Bytes(KDF_ARGON2ID.to_vec())),
+            ("S".to_string(), Variante::Bytes(vec![0; 32])),
+            ("M".to_string(), Variante::U64(memoria)),
+            ("I".to_string(), Variante::U64(iteraciones)),
+            ("P".to_string(), Variante::U32(paralelismo)),
+        ])
+    }
+
+    #[test]
+    fn rejects_oversized_kdf_parameters() {
+        let compuesta = [0u8; 32];
+        // 2^32 + 1 iteraciones se truncarían a 1 con un cast
+        assert!(transform_key(&argon2(64 * 1024, (1 << 32) + 1, 1), &compuesta).is_err());
+        assert!(transform_key(&argon2(64 * 1024, 1001, 1), &compuesta).is_err());
+        assert!(transform_key(&argon2(4 * 1024 * 1024 * 1024, 1, 1), &compuesta).is_err());
+        assert!(transform_key(&argon2(64 * 1024, 1, 65), &compuesta).is_err());
+        assert!(transform_key(&argon2(64 * 1024, 1, 1), &compuesta).is_ok());
+
+        let mut negativo = argon2(64 * 1024, 1, 1);
+        negativo.insert("I".to_string(), Variante::I64(-1));
+        assert!(transform_key(&negativo, &compuesta).is_err());
+
+        let aes = HashMap::from([
+            ("$UUID".to_string(), Variante::Bytes(KDF_AES.to_vec())),
+            ("S".to_string(), Variante::Bytes(vec![0; 32])),
+            ("R".to_string(), Variante::U64(u64::MAX)),
+        ]);
+        assert!(transform_key(&aes, &compuesta).is_err());
+    }
+
+    #[test]
+    fn import_into_vault() {
+        let mut conn = test_vault();
+        let db = read(AES_KDF, "vecla-aes", None).unwrap();
+        assert_eq!(import_into(&mut conn, CLAVE, &db).unwrap(), (2, 0));
+        // Una segunda importación no duplica nada
+        assert_eq!(import_into(&mut conn, CLAVE, &db).unwrap(), (0, 2));
+
+        let entradas = list_entries(&conn).unwrap();
+        let correo = entradas.iter().find(|e| e.app == "Correo").unwrap();
+        assert_eq!(correo.usuario, "ana");
+        assert_eq!(correo.mail, "ana@example.com");
+        assert_eq!(correo.etiquetas, "web,correo");
+        assert_eq!(correo.carpeta, "");
+        assert_eq!(decrypt(CLAVE, &correo.con).unwrap(), "nueva-2024");
+        let campos = list_fields(&conn, &correo.id).unwrap();
+        assert_eq!(campos.len(), 1);
+        assert_eq!((campos[0].nombre.as_str(), campos[0].protegido), ("PIN", true));
+        assert_eq!(decrypt(CLAVE, &campos[0].valor).unwrap(), "4321");
+        let adjuntos = list_attachments(&conn, &correo.id).unwrap();
+        assert_eq!(decrypt_bytes(CLAVE, &adjuntos[0].datos).unwrap(), b"adjunto\x00\x01\xff");
+        let historial = list_history(&conn, &correo.id).unwrap();
+        assert_eq!(decrypt(CLAVE, &historial[0].entrada.con).unwrap(), "vieja-2023");
+
+        let vpn = entradas.iter().find(|e| e.app == "VPN").unwrap();
+        assert_eq!(vpn.carpeta, "Trabajo");
+    }
+
+    #[test]
+    fn export_from_vault() {
+        let mut origen = test_vault();
+        import_into(&mut origen, CLAVE, &sample()).unwrap();
+        let datos = write(&export_from(&origen, CLAVE).unwrap(), "secreta", None).unwrap();
+
+        let mut destino = test_vault();
+        import_into(&mut destino, CLAVE, &read(&datos, "secreta", None).unwrap()).unwrap();
+        let entradas = list_entries(&destino).unwrap();
+        assert_eq!(entradas.len(), 1);
+        let banco = &entradas[0];
+        assert_eq!(banco.id, Uuid::from_bytes([7; 16]).to_string());
+        assert_eq!(banco.carpeta, "Finanzas");
+        assert_eq!(banco.etiquetas, "dinero,web");
+        assert_eq!(banco.modificado, "2024-02-03T04:05:06+00:00");
+        assert_eq!(decrypt(CLAVE, &banco.con).unwrap(), "ñandú-2024");
+        let campos = list_fields(&destino, &banco.id).unwrap();
+        assert_eq!(decrypt(CLAVE, &campos[0].valor).unwrap(), "1234");
+        let adjuntos = list_attachments(&destino, &banco.id).unwrap();
+        assert_eq!(adjuntos[0].nombre, "clave.bin");
+        assert_eq!(decrypt_bytes(CLAVE, &adjuntos[0].datos).unwrap(), [0, 1, 2, 255]);
+        let historial = list_history(&destino, &banco.id).unwrap();
+        assert_eq!(historial.len(), 1);
+        assert_eq!(decrypt(CLAVE, &historial[0].entrada.con).unwrap(), "vieja");
+    }
+}
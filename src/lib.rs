@@ -0,0 +1,21 @@
+// Núcleo de Vecla, compartido por la interfaz gráfica (vecla) y la de terminal (vecla-cli)
+pub mod db;
+pub mod crypto;
+pub mod config;
+pub mod import;
+pub mod kdbx;
+pub mod export;
+pub mod backup;
+pub mod api;
+pub mod clipboard;
+pub mod strength;
+pub mod generator;
+pub mod passphrase;
+pub mod audit;
+pub mod screen_lock;
+#[cfg(unix)]
+pub mod agent;
+#[cfg(unix)]
+pub mod ssh_agent;
+#[cfg(unix)]
+pub mod secret_service;
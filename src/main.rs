@@ -1,19 +1,201 @@
-mod db;
-mod crypto;
 mod gui;
-mod config;
+
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+use vecla::config::Config;
+use vecla::export::{PlaintextFormat, PlaintextOptions, PLAINTEXT_FIELDS};
+
+#[derive(Parser)]
+#[command(name = "vecla", version, about = "Gestor de contraseñas portable")]
+struct Args {
+    /// Bóveda a abrir (por defecto, la indicada en la configuración)
+    #[arg(long, env = "VECLA_VAULT", value_name = "RUTA")]
+    vault: Option<String>,
+
+    /// Archivo de clave de la bóveda (por defecto, key_file de la configuración)
+    #[arg(long, env = "VECLA_KEY_FILE", value_name = "RUTA")]
+    key_file: Option<String>,
+
+    /// Archivo de configuración a usar
+    #[arg(long, env = "VECLA_CONFIG", value_name = "RUTA")]
+    config: Option<PathBuf>,
+
+    /// Abre la bóveda en solo lectura
+    #[arg(long, env = "VECLA_READONLY", value_parser = clap::builder::FalseyValueParser::new())]
+    readonly: bool,
+
+    /// Abre la interfaz gráfica (comportamiento por defecto)
+    #[arg(long, conflicts_with = "headless")]
+    gui: bool,
+
+    /// No abre la interfaz: ejecuta la operación pedida o muestra un resumen de la bóveda
+    #[arg(long)]
+    headless: bool,
+
+    /// Exporta las entradas SIN CIFRAR al archivo indicado
+    #[arg(long, value_name = "RUTA", help_heading = "Exportación en texto plano")]
+    export_plaintext: Option<PathBuf>,
+
+    /// Confirma que se entiende que la exportación no estará cifrada
+    #[arg(long, requires = "export_plaintext", help_heading = "Exportación en texto plano")]
+    i_understand_plaintext: bool,
+
+    #[arg(long, value_enum, default_value = "json", help_heading = "Exportación en texto plano")]
+    format: FormatArg,
+
+    /// Campos a exportar, separados por coma (por defecto todos)
+    #[arg(long, value_delimiter = ',', help_heading = "Exportación en texto plano")]
+    fields: Vec<String>,
+
+    /// Exporta solo esta carpeta y sus subcarpetas
+    #[arg(long, help_heading = "Exportación en texto plano")]
+    folder: Option<String>,
+
+    /// Exporta solo las entradas con esta etiqueta
+    #[arg(long, help_heading = "Exportación en texto plano")]
+    tag: Option<String>,
+
+    /// Lista las copias de seguridad disponibles
+    #[arg(long, help_heading = "Copias de seguridad")]
+    list_backups: bool,
+
+    /// Restaura una copia (ruta o número de --list-backups)
+    #[arg(long, value_name = "COPIA", help_heading = "Copias de seguridad")]
+    restore_backup: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Json,
+    Csv,
+}
 
 fn main() {
+    let args = Args::parse();
+
     // Cargar configuración
-    let config = crate::config::Config::new();
-    
-    // Solicitar clave maestra
-    let master_key = crate::crypto::prompt_master_key();
-    
-    // Inicializar base de datos
-    let conn = crate::db::init_db(&config.get_db_path())
-        .expect("No se pudo abrir la base de datos");
-    
-    // Lanzar GUI
-    crate::gui::run(conn, master_key);
-}
\ No newline at end of file
+    let config = match Config::load_with_args(args.config.as_deref(), args.vault.as_deref(), args.key_file.as_deref(), args.readonly) {
+        Ok((config, avisos)) => {
+            for aviso in avisos {
+                eprintln!("Aviso: {}", aviso);
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Las órdenes de terminal están en vecla-cli. La interfaz ofrece mover una bóveda que
+    // siga en la ubicación antigua; sin interfaz es un error para no crear una vacía.
+    let sin_interfaz = args.headless || args.export_plaintext.is_some() || args.list_backups || args.restore_backup.is_some();
+    let resultado = if sin_interfaz {
+        config.check_legacy_vault().and_then(|_| {
+            if args.export_plaintext.is_some() {
+                export_plaintext_cli(&config, &args)
+            } else if args.list_backups || args.restore_backup.is_some() {
+                backups_cli(&config, &args)
+            } else {
+                summary_cli(&config)
+            }
+        })
+    } else {
+        // La interfaz pide la clave maestra en su pantalla de desbloqueo
+        gui::run(config);
+        Ok(())
+    };
+    if let Err(e) = resultado {
+        eprintln!("Error: {}", e);
+        std::process::exit(2);
+    }
+}
+
+// Clave maestra desde la terminal, con el archivo de clave de la configuración si lo hay
+fn prompt_master_key(config: &Config) -> Result<String, String> {
+    vecla::crypto::compose_master_key(&vecla::crypto::prompt_master_key(), config.get_key_file_path().as_deref())
+}
+
+// vecla --headless: comprueba la clave y muestra qué bóveda se ha abierto
+fn summary_cli(config: &Config) -> Result<(), String> {
+    let conn = vecla::db::open_db(config).map_err(|e| e.to_string())?;
+    let master_key = prompt_master_key(config)?;
+    let entradas = vecla::db::list_entries(&conn).map_err(|e| e.to_string())?;
+    let ilegibles = entradas
+        .iter()
+        .filter(|e| vecla::crypto::decrypt(&master_key, &e.con).is_err())
+        .count();
+    println!("Bóveda: {}", config.get_db_path().display());
+    println!("Portable: {}", if Config::is_portable() { "sí" } else { "no" });
+    println!("Modo: {}", if config.readonly { "solo lectura" } else { "lectura y escritura" });
+    println!("Entradas: {}", entradas.len());
+    if ilegibles > 0 {
+        println!("Entradas que no se pueden descifrar con esta clave: {}", ilegibles);
+    }
+    Ok(())
+}
+
+// vecla --export-plaintext <ruta> --i-understand-plaintext [--format json|csv]
+//       [--fields app,usuario,con] [--folder <carpeta>] [--tag <etiqueta>]
+fn export_plaintext_cli(config: &Config, args: &Args) -> Result<(), String> {
+    let ruta = args.export_plaintext.as_ref().ok_or("Falta la ruta de destino para --export-plaintext")?;
+    if !args.i_understand_plaintext {
+        return Err(
+            "La exportación en texto plano deja todas las contraseñas sin cifrar en disco.\n\
+             Si es lo que quieres, repite el comando añadiendo --i-understand-plaintext".to_string(),
+        );
+    }
+    let formato = match args.format {
+        FormatArg::Json => PlaintextFormat::Json,
+        FormatArg::Csv => PlaintextFormat::Csv,
+    };
+    let campos = if args.fields.is_empty() {
+        PLAINTEXT_FIELDS.iter().map(|c| c.to_string()).collect()
+    } else {
+        args.fields.iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+    };
+    let opciones = PlaintextOptions { formato, campos, carpeta: args.folder.clone(), etiqueta: args.tag.clone() };
+
+    let conn = vecla::db::open_db(config).map_err(|e| e.to_string())?;
+    let master_key = prompt_master_key(config)?;
+    // Con una clave incorrecta las entradas se exportarían vacías o con basura
+    if !vecla::db::check_master_key(&conn, &master_key).map_err(|e| e.to_string())? {
+        return Err("Clave maestra incorrecta".to_string());
+    }
+    let contenido = vecla::export::export_plaintext(&conn, &master_key, &opciones)?;
+    std::fs::write(ruta, contenido).map_err(|e| format!("No se pudo escribir {}: {}", ruta.display(), e))?;
+    eprintln!("Exportación en texto plano escrita en {}. Bórrala de forma segura cuando termines.", ruta.display());
+    Ok(())
+}
+
+// vecla --list-backups
+// vecla --restore-backup <ruta | número de la lista>
+fn backups_cli(config: &Config, args: &Args) -> Result<(), String> {
+    let copias = vecla::backup::list_backups(config)?;
+
+    let Some(elegida) = &args.restore_backup else {
+        if copias.is_empty() {
+            println!("No hay copias en {}", vecla::backup::backup_dir(config).display());
+        }
+        for (i, copia) in copias.iter().enumerate() {
+            let estado = match vecla::backup::verify_backup(&copia.ruta) {
+                Ok(n) => format!("{} entradas", n),
+                Err(e) => e,
+            };
+            println!("{:>3}  {}  ({})", i + 1, copia.ruta.display(), estado);
+        }
+        return Ok(());
+    };
+
+    if config.readonly {
+        return Err("No se puede restaurar una copia en modo solo lectura".to_string());
+    }
+    let ruta = match elegida.parse::<usize>() {
+        Ok(n) => copias.get(n.wrapping_sub(1)).map(|c| c.ruta.clone()).ok_or(format!("No existe la copia número {}", n))?,
+        Err(_) => PathBuf::from(elegida),
+    };
+    let mut conn = vecla::db::open_db(config).map_err(|e| e.to_string())?;
+    vecla::backup::restore_backup(&mut conn, config, &ruta)?;
+    println!("Bóveda restaurada desde {}", ruta.display());
+    Ok(())
+}
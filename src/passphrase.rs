@@ -0,0 +1,190 @@
+// Frases de contraseña al estilo Diceware: palabras elegidas al azar de una lista. Son
+// más fáciles de recordar y de escribir a mano que una contraseña aleatoria (claves
+// maestras, wifi...). Vecla incluye una lista en español y otra en inglés de palabras
+// comunes, sin tildes ni eñes para poder escribirlas con cualquier teclado; también se
+// puede usar cualquier lista Diceware (p. ej. la lista larga de la EFF) desde un archivo.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::path::Path;
+
+const LISTA_ES: &str = include_str!("wordlists/es.txt");
+const LISTA_EN: &str = include_str!("wordlists/en.txt");
+
+pub const IDIOMAS: [&str; 2] = ["es", "en"];
+// Por debajo de esto cada palabra aporta muy poca entropía
+const MINIMO_PALABRAS_LISTA: usize = 100;
+
+pub struct Wordlist {
+    words: Vec<String>,
+}
+
+impl Wordlist {
+    // Lista incluida en Vecla: "es" o "en"
+    pub fn bundled(idioma: &str) -> Result<Self, String> {
+        match idioma {
+            "es" => Self::parse(LISTA_ES),
+            "en" => Self::parse(LISTA_EN),
+            otro => Err(format!("Idioma de lista desconocido: {} (usa {})", otro, IDIOMAS.join(" o "))),
+        }
+    }
+
+    // Archivo con una palabra por línea o en formato Diceware ("11111<tab>palabra")
+    pub fn load(ruta: &Path) -> Result<Self, String> {
+        let texto = std::fs::read_to_string(ruta).map_err(|e| format!("No se pudo leer {}: {}", ruta.display(), e))?;
+        Self::parse(&texto).map_err(|e| format!("{}: {}", ruta.display(), e))
+    }
+
+    fn parse(texto: &str) -> Result<Self, String> {
+        let mut words: Vec<String> = texto
+            .lines()
+            .filter_map(|linea| {
+                let mut partes = linea.split_whitespace();
+                let primera = partes.next()?;
+                // En formato Diceware la primera columna son las tiradas de dados
+                match partes.next() {
+                    Some(palabra) if primera.chars().all(|c| c.is_ascii_digit()) => Some(palabra.to_string()),
+                    _ => Some(primera.to_string()),
+                }
+            })
+            .collect();
+        words.sort();
+        words.dedup();
+        if words.len() < MINIMO_PALABRAS_LISTA {
+            return Err(format!(
+                "la lista tiene {} palabras distintas y se necesitan al menos {}",
+                words.len(),
+                MINIMO_PALABRAS_LISTA
+            ));
+        }
+        Ok(Self { words })
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool, // primera letra de cada palabra en mayúscula
+    pub add_digit: bool,  // un dígito al azar al final de una palabra al azar
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self { words: 6, separator: "-".to_string(), capitalize: false, add_digit: false }
+    }
+}
+
+impl PassphraseOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(3..=20).contains(&self.words) {
+            return Err("El número de palabras debe estar entre 3 y 20".to_string());
+        }
+        if self.separator.chars().any(char::is_control) {
+            return Err("El separador no puede tener caracteres de control".to_string());
+        }
+        Ok(())
+    }
+
+    // Bits de entropía suponiendo que el atacante conoce la lista y las opciones.
+    // Poner mayúsculas a todas las palabras no añade nada; el dígito aporta su valor
+    // y la palabra en la que va.
+    pub fn entropy_bits(&self, lista: &Wordlist) -> f64 {
+        let mut bits = self.words as f64 * (lista.len() as f64).log2();
+        if self.add_digit {
+            bits += 10f64.log2() + (self.words as f64).log2();
+        }
+        bits
+    }
+
+    pub fn generate(&self, lista: &Wordlist) -> Result<String, String> {
+        self.validate()?;
+        let mut rng = rand::thread_rng();
+        let mut palabras: Vec<String> = (0..self.words)
+            .map(|_| {
+                let palabra = lista.words.choose(&mut rng).cloned().unwrap_or_default();
+                if self.capitalize {
+                    capitalize(&palabra)
+                } else {
+                    palabra
+                }
+            })
+            .collect();
+        if self.add_digit {
+            let i = rng.gen_range(0..palabras.len());
+            let digito = rng.gen_range(0..10);
+            palabras[i].push_str(&digito.to_string());
+        }
+        Ok(palabras.join(&self.separator))
+    }
+}
+
+fn capitalize(palabra: &str) -> String {
+    let mut letras = palabra.chars();
+    match letras.next() {
+        Some(primera) => primera.to_uppercase().chain(letras).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lista en formato Diceware: tiradas de dados, tabulador y palabra
+    fn diceware(palabras: usize) -> String {
+        (0..palabras).map(|i| format!("{:05}\tpalabra{}\n", 11111 + i, i)).collect()
+    }
+
+    #[test]
+    fn parses_diceware_lists_without_the_dice_column() {
+        let lista = Wordlist::parse(&diceware(216)).unwrap();
+        assert_eq!(lista.len(), 216);
+        assert!(lista.words.iter().all(|p| p.starts_with("palabra")));
+
+        // Una palabra por línea, con repetidas y líneas en blanco
+        let texto: String = (0..150).map(|i| format!("word{}\n\n word{} \n", i % 120, i % 120)).collect();
+        assert_eq!(Wordlist::parse(&texto).unwrap().len(), 120);
+    }
+
+    #[test]
+    fn rejects_short_lists() {
+        let error = Wordlist::parse(&diceware(MINIMO_PALABRAS_LISTA - 1)).err().unwrap();
+        assert!(error.contains(&format!("{} palabras distintas", MINIMO_PALABRAS_LISTA - 1)));
+        // Las repetidas no cuentan
+        let repetidas = diceware(50).repeat(3);
+        assert!(Wordlist::parse(&repetidas).is_err());
+        assert!(Wordlist::parse(&diceware(MINIMO_PALABRAS_LISTA)).is_ok());
+    }
+
+    #[test]
+    fn entropy_counts_the_added_digit() {
+        let lista = Wordlist::parse(&diceware(128)).unwrap();
+        let opciones = PassphraseOptions { words: 4, ..PassphraseOptions::default() };
+        assert_eq!(opciones.entropy_bits(&lista), 28.0);
+        let con_digito = PassphraseOptions { add_digit: true, capitalize: true, ..opciones.clone() };
+        // Dígito (log2 10) más la palabra que lo lleva (log2 4); las mayúsculas no suman
+        assert!((con_digito.entropy_bits(&lista) - (28.0 + 10f64.log2() + 2.0)).abs() < 1e-9);
+
+        let frase = con_digito.generate(&lista).unwrap();
+        let palabras: Vec<&str> = frase.split('-').collect();
+        assert_eq!(palabras.len(), 4);
+        assert!(palabras.iter().all(|p| p.starts_with("Palabra")));
+        assert_eq!(palabras.iter().filter(|p| !lista.words.contains(&p.to_lowercase())).count(), 1);
+    }
+
+    #[test]
+    fn bundled_lists_are_valid() {
+        for idioma in IDIOMAS {
+            assert!(Wordlist::bundled(idioma).unwrap().len() >= MINIMO_PALABRAS_LISTA);
+        }
+        assert!(Wordlist::bundled("fr").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// Aviso cuando el usuario bloquea la sesión del sistema, para bloquear también la bóveda.
+// En Linux se escuchan las señales Lock de logind y ActiveChanged del salvapantallas por
+// D-Bus; en Windows se comprueba cada poco si el escritorio de entrada sigue accesible.
+// En otros sistemas no se detecta.
+
+// Llama a on_lock (desde otro hilo) cada vez que se bloquea la sesión
+pub fn watch(on_lock: impl Fn() + Send + Sync + 'static) {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    dbus::watch(std::sync::Arc::new(on_lock));
+    #[cfg(windows)]
+    windows::watch(on_lock);
+    #[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+    let _ = on_lock;
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod dbus {
+    use std::sync::Arc;
+    use zbus::blocking::{Connection, MessageIterator};
+
+    type Aviso = Arc<dyn Fn() + Send + Sync>;
+
+    // Salvapantallas de freedesktop (KDE, XFCE...) y de GNOME
+    const SALVAPANTALLAS: [&str; 2] = ["org.freedesktop.ScreenSaver", "org.gnome.ScreenSaver"];
+
+    pub fn watch(on_lock: Aviso) {
+        let aviso = Arc::clone(&on_lock);
+        std::thread::spawn(move || {
+            let Ok(bus) = Connection::system() else { return };
+            let regla = "type='signal',sender='org.freedesktop.login1',interface='org.freedesktop.login1.Session',member='Lock'";
+            let Ok(mensajes) = MessageIterator::for_match_rule(regla, &bus, None) else { return };
+            for msg in mensajes.flatten() {
+                if msg.member().is_some_and(|m| m == "Lock") {
+                    aviso();
+                }
+            }
+        });
+        for interfaz in SALVAPANTALLAS {
+            let aviso = Arc::clone(&on_lock);
+            std::thread::spawn(move || {
+                let Ok(bus) = Connection::session() else { return };
+                let regla = format!("type='signal',interface='{}',member='ActiveChanged'", interfaz);
+                let Ok(mensajes) = MessageIterator::for_match_rule(regla.as_str(), &bus, None) else { return };
+                for msg in mensajes.flatten() {
+                    if msg.body::<bool>().unwrap_or(false) {
+                        aviso();
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::time::Duration;
+    use winapi::um::winuser::{CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_SWITCHDESKTOP};
+
+    // Con la sesión bloqueada el escritorio de entrada es el de Winlogon y no se puede
+    // abrir ni activar
+    fn session_locked() -> bool {
+        unsafe {
+            let escritorio = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+            if escritorio.is_null() {
+                return true;
+            }
+            let activo = SwitchDesktop(escritorio) != 0;
+            CloseDesktop(escritorio);
+            !activo
+        }
+    }
+
+    pub fn watch(on_lock: impl Fn() + Send + Sync + 'static) {
+        std::thread::spawn(move || {
+            let mut antes = false;
+            loop {
+                let ahora = session_locked();
+                if ahora && !antes {
+                    on_lock();
+                }
+                antes = ahora;
+                std::thread::sleep(Duration::from_secs(2));
+            }
+        });
+    }
+}
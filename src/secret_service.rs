@@ -0,0 +1,933 @@
+// Proveedor de org.freedesktop.secrets (API Secret Service) sobre el bus de sesión, para
+// que las aplicaciones que usan libsecret guarden y busquen sus secretos en la bóveda.
+//
+// Cada colección es una carpeta bajo "Secret Service/" y cada elemento una entrada de esa
+// carpeta: la etiqueta es la app, el secreto la contraseña y los atributos se guardan en
+// la tabla atributos. El alias "default" es la colección "login". Toda la bóveda se
+// bloquea y desbloquea a la vez, con la clave maestra.
+//
+// Los mensajes se atienden a mano en lugar de registrar objetos en el ObjectServer de
+// zbus, así las entradas que se crean o borran desde la interfaz aparecen sin más.
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::{Connection as Bus, MessageIterator};
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value};
+use zbus::{Message, MessageType};
+use zeroize::Zeroize;
+use crate::config::Config;
+use crate::crypto::{compose_master_key, decrypt, encrypt};
+use crate::db::{
+    PasswordEntry, check_master_key, delete_entry, insert_entry, list_attributes, list_entries, open_db,
+    set_attributes, update_entry,
+};
+
+const NOMBRE_BUS: &str = "org.freedesktop.secrets";
+const RUTA_SERVICIO: &str = "/org/freedesktop/secrets";
+const RUTA_COLECCIONES: &str = "/org/freedesktop/secrets/collection/";
+const RUTA_ALIAS: &str = "/org/freedesktop/secrets/aliases/";
+const RUTA_SESIONES: &str = "/org/freedesktop/secrets/session/";
+const RUTA_PROMPTS: &str = "/org/freedesktop/secrets/prompt/";
+
+const IFACE_SERVICIO: &str = "org.freedesktop.Secret.Service";
+const IFACE_COLECCION: &str = "org.freedesktop.Secret.Collection";
+const IFACE_ITEM: &str = "org.freedesktop.Secret.Item";
+const IFACE_SESION: &str = "org.freedesktop.Secret.Session";
+const IFACE_PROMPT: &str = "org.freedesktop.Secret.Prompt";
+const IFACE_PROPIEDADES: &str = "org.freedesktop.DBus.Properties";
+const IFACE_INTROSPECCION: &str = "org.freedesktop.DBus.Introspectable";
+
+const PROP_LABEL_COLECCION: &str = "org.freedesktop.Secret.Collection.Label";
+const PROP_LABEL_ITEM: &str = "org.freedesktop.Secret.Item.Label";
+const PROP_ATRIBUTOS_ITEM: &str = "org.freedesktop.Secret.Item.Attributes";
+
+// Carpeta de la bóveda bajo la que viven las colecciones
+pub const CARPETA_RAIZ: &str = "Secret Service";
+const COLECCION_DEFECTO: &str = "login";
+
+// Secreto tal como viaja por el bus: sesión, parámetros, valor y tipo de contenido
+#[derive(Serialize, Deserialize, Type)]
+struct Secreto {
+    sesion: OwnedObjectPath,
+    parametros: Vec<u8>,
+    valor: Vec<u8>,
+    tipo: String,
+}
+
+// Error D-Bus devuelto al cliente
+struct Fallo {
+    nombre: &'static str,
+    mensaje: String,
+}
+
+impl Fallo {
+    fn new(nombre: &'static str, mensaje: impl Into<String>) -> Self {
+        Self { nombre, mensaje: mensaje.into() }
+    }
+
+    fn locked() -> Self {
+        Self::new("org.freedesktop.Secret.Error.IsLocked", "La bóveda está bloqueada")
+    }
+
+    fn no_object(ruta: &str) -> Self {
+        Self::new("org.freedesktop.Secret.Error.NoSuchObject", format!("No existe {}", ruta))
+    }
+}
+
+impl From<String> for Fallo {
+    fn from(mensaje: String) -> Self {
+        Self::new("org.freedesktop.DBus.Error.Failed", mensaje)
+    }
+}
+
+impl From<rusqlite::Error> for Fallo {
+    fn from(e: rusqlite::Error) -> Self {
+        e.to_string().into()
+    }
+}
+
+impl From<zbus::Error> for Fallo {
+    fn from(e: zbus::Error) -> Self {
+        Self::new("org.freedesktop.DBus.Error.InvalidArgs", e.to_string())
+    }
+}
+
+impl From<zbus::zvariant::Error> for Fallo {
+    fn from(e: zbus::zvariant::Error) -> Self {
+        Self::new("org.freedesktop.DBus.Error.InvalidArgs", e.to_string())
+    }
+}
+
+type Resultado<T> = Result<T, Fallo>;
+
+// Objeto al que va dirigido un mensaje
+enum Objeto {
+    Servicio,
+    Coleccion(String),
+    Item(String, Box<PasswordEntry>),
+    Sesion,
+    Prompt(String),
+}
+
+struct Estado {
+    config: Config,
+    conn: Connection,
+    master_key: Option<String>,
+    sesiones: HashSet<String>,
+    prompts: HashMap<String, Vec<OwnedObjectPath>>, // objetos que desbloquea cada prompt
+    nuevas: BTreeSet<String>,                        // colecciones creadas que aún no tienen elementos
+    contador: u64,
+}
+
+impl Estado {
+    fn next_path(&mut self, base: &str) -> String {
+        self.contador += 1;
+        format!("{}{}", base, self.contador)
+    }
+
+    fn unlocked(&self) -> bool {
+        self.master_key.is_some()
+    }
+
+    fn key(&self) -> Resultado<&str> {
+        self.master_key.as_deref().ok_or_else(Fallo::locked)
+    }
+
+    fn lock(&mut self) {
+        if let Some(mut clave) = self.master_key.take() {
+            clave.zeroize();
+        }
+    }
+
+    fn writable(&self) -> Resultado<()> {
+        if self.config.readonly {
+            return Err(Fallo::new("org.freedesktop.DBus.Error.AccessDenied", "La bóveda está abierta en solo lectura"));
+        }
+        Ok(())
+    }
+
+    // Copia de seguridad tras los cambios, igual que en la interfaz gráfica
+    fn after_write(&self) -> Resultado<()> {
+        crate::backup::auto_backup(&self.conn, &self.config)?;
+        Ok(())
+    }
+
+    fn collections(&self) -> Resultado<Vec<String>> {
+        let mut nombres = self.nuevas.clone();
+        nombres.insert(COLECCION_DEFECTO.to_string());
+        for entrada in list_entries(&self.conn)? {
+            if let Some(nombre) = collection_of(&entrada) {
+                nombres.insert(nombre);
+            }
+        }
+        Ok(nombres.into_iter().collect())
+    }
+
+    fn items(&self, coleccion: &str) -> Resultado<Vec<PasswordEntry>> {
+        let carpeta = folder(coleccion);
+        Ok(list_entries(&self.conn)?.into_iter().filter(|e| e.carpeta == carpeta).collect())
+    }
+
+    // Elementos de la colección (o de todas) cuyos atributos incluyen los buscados
+    fn search(&self, coleccion: Option<&str>, buscados: &HashMap<String, String>) -> Resultado<Vec<OwnedObjectPath>> {
+        let colecciones = match coleccion {
+            Some(c) => vec![c.to_string()],
+            None => self.collections()?,
+        };
+        let mut encontrados = Vec::new();
+        for coleccion in colecciones {
+            for entrada in self.items(&coleccion)? {
+                let atributos = list_attributes(&self.conn, &entrada.id)?;
+                if buscados.iter().all(|(k, v)| atributos.get(k) == Some(v)) {
+                    encontrados.push(item_path(&coleccion, &entrada.id));
+                }
+            }
+        }
+        Ok(encontrados)
+    }
+
+    fn resolve(&self, ruta: &str) -> Resultado<Objeto> {
+        if ruta == RUTA_SERVICIO {
+            return Ok(Objeto::Servicio);
+        }
+        if ruta.starts_with(RUTA_SESIONES) && self.sesiones.contains(ruta) {
+            return Ok(Objeto::Sesion);
+        }
+        if ruta.starts_with(RUTA_PROMPTS) && self.prompts.contains_key(ruta) {
+            return Ok(Objeto::Prompt(ruta.to_string()));
+        }
+        let resto = match (ruta.strip_prefix(RUTA_COLECCIONES), ruta.strip_prefix(RUTA_ALIAS)) {
+            (Some(resto), _) => resto.to_string(),
+            (None, Some(alias)) if alias.split('/').next() == Some("default") => {
+                alias.replacen("default", COLECCION_DEFECTO, 1)
+            }
+            _ => return Err(Fallo::no_object(ruta)),
+        };
+        let (elemento_coleccion, elemento_item) = match resto.split_once('/') {
+            Some((c, i)) => (c, Some(i)),
+            None => (resto.as_str(), None),
+        };
+        let coleccion = self
+            .collections()?
+            .into_iter()
+            .find(|c| path_element(c) == elemento_coleccion)
+            .ok_or_else(|| Fallo::no_object(ruta))?;
+        match elemento_item {
+            None => Ok(Objeto::Coleccion(coleccion)),
+            Some(elemento) => {
+                let entrada = self
+                    .items(&coleccion)?
+                    .into_iter()
+                    .find(|e| path_element(&e.id) == elemento)
+                    .ok_or_else(|| Fallo::no_object(ruta))?;
+                Ok(Objeto::Item(coleccion, Box::new(entrada)))
+            }
+        }
+    }
+
+    fn secret(&self, entrada: &PasswordEntry, sesion: &ObjectPath) -> Resultado<Secreto> {
+        if !self.sesiones.contains(sesion.as_str()) {
+            return Err(Fallo::new("org.freedesktop.Secret.Error.NoSession", "La sesión no existe"));
+        }
+        let valor = decrypt(self.key()?, &entrada.con)?;
+        Ok(Secreto {
+            sesion: sesion.to_owned().into(),
+            parametros: Vec::new(),
+            valor: valor.into_bytes(),
+            tipo: "text/plain".to_string(),
+        })
+    }
+}
+
+fn folder(coleccion: &str) -> String {
+    format!("{}/{}", CARPETA_RAIZ, coleccion)
+}
+
+fn collection_of(entrada: &PasswordEntry) -> Option<String> {
+    let resto = entrada.carpeta.strip_prefix(CARPETA_RAIZ)?.strip_prefix('/')?;
+    (!resto.is_empty() && !resto.contains('/')).then(|| resto.to_string())
+}
+
+// Los elementos de una ruta D-Bus solo admiten [A-Za-z0-9_]. El resto de bytes (y el propio
+// "_") se escriben como _xx en hexadecimal, para que "a-b" y "a_b" no compartan ruta.
+fn path_element(nombre: &str) -> String {
+    let mut elemento = String::with_capacity(nombre.len());
+    for b in nombre.bytes() {
+        if b.is_ascii_alphanumeric() {
+            elemento.push(b as char);
+        } else {
+            elemento.push_str(&format!("_{:02x}", b));
+        }
+    }
+    elemento
+}
+
+fn collection_path(coleccion: &str) -> OwnedObjectPath {
+    owned_path(&format!("{}{}", RUTA_COLECCIONES, path_element(coleccion)))
+}
+
+fn item_path(coleccion: &str, id: &str) -> OwnedObjectPath {
+    owned_path(&format!("{}{}/{}", RUTA_COLECCIONES, path_element(coleccion), path_element(id)))
+}
+
+// "/" significa "ninguno" (p. ej. sin prompt)
+fn owned_path(ruta: &str) -> OwnedObjectPath {
+    ObjectPath::try_from(ruta.to_string()).map(Into::into).unwrap_or_else(|_| none_path())
+}
+
+fn none_path() -> OwnedObjectPath {
+    ObjectPath::from_static_str_unchecked("/").into()
+}
+
+fn timestamp(fecha: &str) -> u64 {
+    DateTime::parse_from_rfc3339(fecha).map(|f| f.timestamp().max(0) as u64).unwrap_or(0)
+}
+
+fn owned<'a>(valor: impl Into<Value<'a>>) -> OwnedValue {
+    valor.into().into()
+}
+
+// Propiedades de un objeto, con la interfaz a la que pertenecen
+fn properties(estado: &Estado, objeto: &Objeto) -> Resultado<(&'static str, HashMap<String, OwnedValue>)> {
+    let mut propiedades = HashMap::new();
+    let interfaz = match objeto {
+        Objeto::Servicio => {
+            let rutas: Vec<OwnedObjectPath> = estado.collections()?.iter().map(|c| collection_path(c)).collect();
+            propiedades.insert("Collections".to_string(), owned(rutas));
+            IFACE_SERVICIO
+        }
+        Objeto::Coleccion(coleccion) => {
+            let entradas = estado.items(coleccion)?;
+            let rutas: Vec<OwnedObjectPath> = entradas.iter().map(|e| item_path(coleccion, &e.id)).collect();
+            let creada = entradas.iter().map(|e| timestamp(&e.fecha)).min().unwrap_or(0);
+            let modificada = entradas.iter().map(|e| timestamp(&e.modificado)).max().unwrap_or(0);
+            propiedades.insert("Items".to_string(), owned(rutas));
+            propiedades.insert("Label".to_string(), owned(coleccion.as_str()));
+            propiedades.insert("Locked".to_string(), owned(!estado.unlocked()));
+            propiedades.insert("Created".to_string(), owned(creada));
+            propiedades.insert("Modified".to_string(), owned(modificada));
+            IFACE_COLECCION
+        }
+        Objeto::Item(_, entrada) => {
+            let atributos: HashMap<String, String> = list_attributes(&estado.conn, &entrada.id)?.into_iter().collect();
+            propiedades.insert("Locked".to_string(), owned(!estado.unlocked()));
+            propiedades.insert("Attributes".to_string(), owned(atributos));
+            propiedades.insert("Label".to_string(), owned(entrada.app.as_str()));
+            propiedades.insert("Created".to_string(), owned(timestamp(&entrada.fecha)));
+            propiedades.insert("Modified".to_string(), owned(timestamp(&entrada.modificado)));
+            IFACE_ITEM
+        }
+        Objeto::Sesion => IFACE_SESION,
+        Objeto::Prompt(_) => IFACE_PROMPT,
+    };
+    Ok((interfaz, propiedades))
+}
+
+fn introspection(objeto: &Objeto) -> String {
+    let interfaz = match objeto {
+        Objeto::Servicio => INTROSPECCION_SERVICIO,
+        Objeto::Coleccion(_) => INTROSPECCION_COLECCION,
+        Objeto::Item(..) => INTROSPECCION_ITEM,
+        Objeto::Sesion => INTROSPECCION_SESION,
+        Objeto::Prompt(_) => INTROSPECCION_PROMPT,
+    };
+    format!(
+        "<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\" \
+         \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n<node>{}</node>",
+        interfaz
+    )
+}
+
+const INTROSPECCION_SERVICIO: &str = r#"
+  <interface name="org.freedesktop.Secret.Service">
+    <method name="OpenSession"><arg name="algorithm" type="s" direction="in"/><arg name="input" type="v" direction="in"/><arg name="output" type="v" direction="out"/><arg name="result" type="o" direction="out"/></method>
+    <method name="CreateCollection"><arg name="properties" type="a{sv}" direction="in"/><arg name="alias" type="s" direction="in"/><arg name="collection" type="o" direction="out"/><arg name="prompt" type="o" direction="out"/></method>
+    <method name="SearchItems"><arg name="attributes" type="a{ss}" direction="in"/><arg name="unlocked" type="ao" direction="out"/><arg name="locked" type="ao" direction="out"/></method>
+    <method name="Unlock"><arg name="objects" type="ao" direction="in"/><arg name="unlocked" type="ao" direction="out"/><arg name="prompt" type="o" direction="out"/></method>
+    <method name="Lock"><arg name="objects" type="ao" direction="in"/><arg name="locked" type="ao" direction="out"/><arg name="Prompt" type="o" direction="out"/></method>
+    <method name="GetSecrets"><arg name="items" type="ao" direction="in"/><arg name="session" type="o" direction="in"/><arg name="secrets" type="a{o(oayays)}" direction="out"/></method>
+    <method name="ReadAlias"><arg name="name" type="s" direction="in"/><arg name="collection" type="o" direction="out"/></method>
+    <method name="SetAlias"><arg name="name" type="s" direction="in"/><arg name="collection" type="o" direction="in"/></method>
+    <property name="Collections" type="ao" access="read"/>
+    <signal name="CollectionCreated"><arg name="collection" type="o"/></signal>
+    <signal name="CollectionDeleted"><arg name="collection" type="o"/></signal>
+    <signal name="CollectionChanged"><arg name="collection" type="o"/></signal>
+  </interface>"#;
+
+const INTROSPECCION_COLECCION: &str = r#"
+  <interface name="org.freedesktop.Secret.Collection">
+    <method name="Delete"><arg name="prompt" type="o" direction="out"/></method>
+    <method name="SearchItems"><arg name="attributes" type="a{ss}" direction="in"/><arg name="results" type="ao" direction="out"/></method>
+    <method name="CreateItem"><arg name="properties" type="a{sv}" direction="in"/><arg name="secret" type="(oayays)" direction="in"/><arg name="replace" type="b" direction="in"/><arg name="item" type="o" direction="out"/><arg name="prompt" type="o" direction="out"/></method>
+    <property name="Items" type="ao" access="read"/>
+    <property name="Label" type="s" access="readwrite"/>
+    <property name="Locked" type="b" access="read"/>
+    <property name="Created" type="t" access="read"/>
+    <property name="Modified" type="t" access="read"/>
+    <signal name="ItemCreated"><arg name="item" type="o"/></signal>
+    <signal name="ItemDeleted"><arg name="item" type="o"/></signal>
+    <signal name="ItemChanged"><arg name="item" type="o"/></signal>
+  </interface>"#;
+
+const INTROSPECCION_ITEM: &str = r#"
+  <interface name="org.freedesktop.Secret.Item">
+    <method name="Delete"><arg name="Prompt" type="o" direction="out"/></method>
+    <method name="GetSecret"><arg name="session" type="o" direction="in"/><arg name="secret" type="(oayays)" direction="out"/></method>
+    <method name="SetSecret"><arg name="secret" type="(oayays)" direction="in"/></method>
+    <property name="Locked" type="b" access="read"/>
+    <property name="Attributes" type="a{ss}" access="readwrite"/>
+    <property name="Label" type="s" access="readwrite"/>
+    <property name="Created" type="t" access="read"/>
+    <property name="Modified" type="t" access="read"/>
+  </interface>"#;
+
+const INTROSPECCION_SESION: &str = r#"
+  <interface name="org.freedesktop.Secret.Session">
+    <method name="Close"/>
+  </interface>"#;
+
+const INTROSPECCION_PROMPT: &str = r#"
+  <interface name="org.freedesktop.Secret.Prompt">
+    <method name="Prompt"><arg name="window-id" type="s" direction="in"/></method>
+    <method name="Dismiss"/>
+    <signal name="Completed"><arg name="dismissed" type="b"/><arg name="result" type="v"/></signal>
+  </interface>"#;
+
+struct Servicio {
+    bus: Bus,
+    estado: Arc<Mutex<Estado>>,
+}
+
+impl Servicio {
+    fn reply<B: Serialize + Type>(&self, msg: &Message, cuerpo: &B) -> Resultado<()> {
+        self.bus.reply(msg, cuerpo)?;
+        Ok(())
+    }
+
+    fn signal<B: Serialize + Type>(&self, ruta: &str, interfaz: &str, nombre: &str, cuerpo: &B) {
+        let _ = self.bus.emit_signal(None::<&str>, ruta, interfaz, nombre, cuerpo);
+    }
+
+    fn handle(&self, msg: &Message) -> Resultado<()> {
+        let ruta = msg.path().map(|r| r.to_string()).unwrap_or_default();
+        let interfaz = msg.interface().map(|i| i.to_string()).unwrap_or_default();
+        let miembro = msg.member().map(|m| m.to_string()).unwrap_or_default();
+        let mut estado = self.estado.lock().unwrap();
+        let objeto = estado.resolve(&ruta)?;
+
+        match (interfaz.as_str(), miembro.as_str()) {
+            (IFACE_INTROSPECCION, "Introspect") => return self.reply(msg, &introspection(&objeto)),
+            (IFACE_PROPIEDADES, "Get") => {
+                let (iface, nombre): (String, String) = msg.body()?;
+                let (propia, mut propiedades) = properties(&estado, &objeto)?;
+                let valor = propiedades
+                    .remove(&nombre)
+                    .filter(|_| iface.is_empty() || iface == propia)
+                    .ok_or_else(|| Fallo::new("org.freedesktop.DBus.Error.UnknownProperty", nombre))?;
+                return self.reply(msg, &Value::from(valor));
+            }
+            (IFACE_PROPIEDADES, "GetAll") => {
+                let iface: String = msg.body()?;
+                let (propia, propiedades) = properties(&estado, &objeto)?;
+                let propiedades = if iface.is_empty() || iface == propia { propiedades } else { HashMap::new() };
+                return self.reply(msg, &propiedades);
+            }
+            (IFACE_PROPIEDADES, "Set") => {
+                let (_, nombre, valor): (String, String, OwnedValue) = msg.body()?;
+                self.set_property(&mut estado, &objeto, &nombre, valor)?;
+                return self.reply(msg, &());
+            }
+            _ => {}
+        }
+
+        match objeto {
+            Objeto::Servicio => self.handle_service(&mut estado, msg, &miembro),
+            Objeto::Coleccion(coleccion) => self.handle_collection(&mut estado, msg, &miembro, &coleccion),
+            Objeto::Item(coleccion, entrada) => self.handle_item(&mut estado, msg, &miembro, &coleccion, *entrada),
+            Objeto::Sesion => match miembro.as_str() {
+                "Close" => {
+                    estado.sesiones.remove(&ruta);
+                    self.reply(msg, &())
+                }
+                otro => Err(unknown_method(otro)),
+            },
+            Objeto::Prompt(prompt) => self.handle_prompt(&mut estado, msg, &miembro, prompt),
+        }
+    }
+
+    fn handle_service(&self, estado: &mut Estado, msg: &Message, miembro: &str) -> Resultado<()> {
+        match miembro {
+            "OpenSession" => {
+                // Solo transporte en claro; libsecret lo usa si no hay cifrado disponible
+                let (algoritmo, _): (String, OwnedValue) = msg.body()?;
+                if algoritmo != "plain" {
+                    return Err(Fallo::new(
+                        "org.freedesktop.DBus.Error.NotSupported",
+                        format!("Algoritmo no soportado: {}", algoritmo),
+                    ));
+                }
+                let sesion = estado.next_path(RUTA_SESIONES);
+                estado.sesiones.insert(sesion.clone());
+                self.reply(msg, &(Value::from(""), owned_path(&sesion)))
+            }
+            "CreateCollection" => {
+                estado.writable()?;
+                let (propiedades, _alias): (HashMap<String, OwnedValue>, String) = msg.body()?;
+                let etiqueta = propiedades
+                    .get(PROP_LABEL_COLECCION)
+                    .and_then(|v| String::try_from(v.clone()).ok())
+                    .filter(|e| !e.trim().is_empty() && !e.contains('/'))
+                    .ok_or_else(|| Fallo::new("org.freedesktop.DBus.Error.InvalidArgs", "Falta una etiqueta válida"))?;
+                let etiqueta = etiqueta.trim().to_string();
+                let ruta = collection_path(&etiqueta);
+                if !estado.collections()?.contains(&etiqueta) {
+                    estado.nuevas.insert(etiqueta);
+                    self.signal(RUTA_SERVICIO, IFACE_SERVICIO, "CollectionCreated", &ruta);
+                }
+                self.reply(msg, &(ruta, none_path()))
+            }
+            "SearchItems" => {
+                let buscados: HashMap<String, String> = msg.body()?;
+                let encontrados = estado.search(None, &buscados)?;
+                if estado.unlocked() {
+                    self.reply(msg, &(encontrados, Vec::<OwnedObjectPath>::new()))
+                } else {
+                    self.reply(msg, &(Vec::<OwnedObjectPath>::new(), encontrados))
+                }
+            }
+            "Unlock" => {
+                let objetos: Vec<OwnedObjectPath> = msg.body()?;
+                if !estado.unlocked() {
+                    // Si el agente de desbloqueo tiene la clave no hace falta preguntar
+                    if let Some(clave) = crate::agent::cached_key(&estado.config.get_db_path()) {
+                        if check_master_key(&estado.conn, &clave)? {
+                            estado.master_key = Some(clave);
+                        }
+                    }
+                }
+                if estado.unlocked() {
+                    return self.reply(msg, &(objetos, none_path()));
+                }
+                let prompt = estado.next_path(RUTA_PROMPTS);
+                estado.prompts.insert(prompt.clone(), objetos);
+                self.reply(msg, &(Vec::<OwnedObjectPath>::new(), owned_path(&prompt)))
+            }
+            "Lock" => {
+                let objetos: Vec<OwnedObjectPath> = msg.body()?;
+                estado.lock();
+                self.reply(msg, &(objetos, none_path()))
+            }
+            "GetSecrets" => {
+                let (items, sesion): (Vec<OwnedObjectPath>, OwnedObjectPath) = msg.body()?;
+                let mut secretos = HashMap::new();
+                for item in items {
+                    if let Ok(Objeto::Item(_, entrada)) = estado.resolve(item.as_str()) {
+                        secretos.insert(item, estado.secret(&entrada, &sesion)?);
+                    }
+                }
+                self.reply(msg, &secretos)
+            }
+            "ReadAlias" => {
+                let nombre: String = msg.body()?;
+                let ruta = if nombre == "default" { collection_path(COLECCION_DEFECTO) } else { none_path() };
+                self.reply(msg, &ruta)
+            }
+            "SetAlias" => Err(Fallo::new(
+                "org.freedesktop.DBus.Error.NotSupported",
+                "El alias default es siempre la colección login",
+            )),
+            otro => Err(unknown_method(otro)),
+        }
+    }
+
+    fn handle_collection(&self, estado: &mut Estado, msg: &Message, miembro: &str, coleccion: &str) -> Resultado<()> {
+        let ruta = collection_path(coleccion);
+        match miembro {
+            "SearchItems" => {
+                let buscados: HashMap<String, String> = msg.body()?;
+                self.reply(msg, &estado.search(Some(coleccion), &buscados)?)
+            }
+            "CreateItem" => {
+                estado.writable()?;
+                let (propiedades, secreto, reemplazar): (HashMap<String, OwnedValue>, Secreto, bool) = msg.body()?;
+                let clave = estado.key()?.to_string();
+                if !estado.sesiones.contains(secreto.sesion.as_str()) {
+                    return Err(Fallo::new("org.freedesktop.Secret.Error.NoSession", "La sesión no existe"));
+                }
+                let etiqueta = propiedades
+                    .get(PROP_LABEL_ITEM)
+                    .and_then(|v| String::try_from(v.clone()).ok())
+                    .unwrap_or_default();
+                let atributos: BTreeMap<String, String> = propiedades
+                    .get(PROP_ATRIBUTOS_ITEM)
+                    .map(|v| HashMap::<String, String>::try_from(v.clone()))
+                    .transpose()?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                let valor = String::from_utf8(secreto.valor)
+                    .map_err(|_| Fallo::new("org.freedesktop.DBus.Error.NotSupported", "Solo se admiten secretos de texto"))?;
+                let con = encrypt(&clave, &valor)?;
+
+                // Con replace se actualiza el elemento con los mismos atributos, si existe
+                let mut existente = None;
+                if reemplazar {
+                    for entrada in estado.items(coleccion)? {
+                        if list_attributes(&estado.conn, &entrada.id)? == atributos {
+                            existente = Some(entrada);
+                            break;
+                        }
+                    }
+                }
+                let (id, senal) = match existente {
+                    Some(mut entrada) => {
+                        entrada.app = etiqueta;
+                        entrada.con = con;
+                        entrada.modificado = Utc::now().to_rfc3339();
+                        update_entry(&estado.conn, &entrada)?;
+                        (entrada.id, "ItemChanged")
+                    }
+                    None => {
+                        let mut entrada = PasswordEntry::new(&etiqueta, "", "", "", &con, "");
+                        entrada.carpeta = folder(coleccion);
+                        insert_entry(&estado.conn, &entrada)?;
+                        (entrada.id, "ItemCreated")
+                    }
+                };
+                set_attributes(&estado.conn, &id, &atributos)?;
+                estado.nuevas.remove(coleccion);
+                estado.after_write()?;
+                let item = item_path(coleccion, &id);
+                self.signal(ruta.as_str(), IFACE_COLECCION, senal, &item);
+                self.reply(msg, &(item, none_path()))
+            }
+            "Delete" => {
+                estado.writable()?;
+                estado.key()?;
+                for entrada in estado.items(coleccion)? {
+                    delete_entry(&estado.conn, &entrada.id)?;
+                }
+                estado.nuevas.remove(coleccion);
+                estado.after_write()?;
+                self.signal(RUTA_SERVICIO, IFACE_SERVICIO, "CollectionDeleted", &ruta);
+                self.reply(msg, &none_path())
+            }
+            otro => Err(unknown_method(otro)),
+        }
+    }
+
+    fn handle_item(
+        &self,
+        estado: &mut Estado,
+        msg: &Message,
+        miembro: &str,
+        coleccion: &str,
+        mut entrada: PasswordEntry,
+    ) -> Resultado<()> {
+        let ruta = item_path(coleccion, &entrada.id);
+        match miembro {
+            "GetSecret" => {
+                let sesion: OwnedObjectPath = msg.body()?;
+                self.reply(msg, &estado.secret(&entrada, &sesion)?)
+            }
+            "SetSecret" => {
+                estado.writable()?;
+                let secreto: Secreto = msg.body()?;
+                let valor = String::from_utf8(secreto.valor)
+                    .map_err(|_| Fallo::new("org.freedesktop.DBus.Error.NotSupported", "Solo se admiten secretos de texto"))?;
+                entrada.con = encrypt(estado.key()?, &valor)?;
+                entrada.modificado = Utc::now().to_rfc3339();
+                update_entry(&estado.conn, &entrada)?;
+                estado.after_write()?;
+                self.signal(collection_path(coleccion).as_str(), IFACE_COLECCION, "ItemChanged", &ruta);
+                self.reply(msg, &())
+            }
+            "Delete" => {
+                estado.writable()?;
+                estado.key()?;
+                delete_entry(&estado.conn, &entrada.id)?;
+                // La colección sigue existiendo aunque se quede sin elementos
+                if estado.items(coleccion)?.is_empty() {
+                    estado.nuevas.insert(coleccion.to_string());
+                }
+                estado.after_write()?;
+                self.signal(collection_path(coleccion).as_str(), IFACE_COLECCION, "ItemDeleted", &ruta);
+                self.reply(msg, &none_path())
+            }
+            otro => Err(unknown_method(otro)),
+        }
+    }
+
+    fn set_property(&self, estado: &mut Estado, objeto: &Objeto, nombre: &str, valor: OwnedValue) -> Resultado<()> {
+        let Objeto::Item(coleccion, entrada) = objeto else {
+            return Err(Fallo::new("org.freedesktop.DBus.Error.PropertyReadOnly", nombre.to_string()));
+        };
+        estado.writable()?;
+        estado.key()?;
+        match nombre {
+            "Label" => {
+                let mut entrada = (**entrada).clone();
+                entrada.app = String::try_from(valor)?;
+                entrada.modificado = Utc::now().to_rfc3339();
+                update_entry(&estado.conn, &entrada)?;
+            }
+            "Attributes" => {
+                let atributos: BTreeMap<String, String> = HashMap::<String, String>::try_from(valor)?.into_iter().collect();
+                set_attributes(&estado.conn, &entrada.id, &atributos)?;
+            }
+            otro => return Err(Fallo::new("org.freedesktop.DBus.Error.PropertyReadOnly", otro.to_string())),
+        }
+        estado.after_write()?;
+        let ruta = item_path(coleccion, &entrada.id);
+        self.signal(collection_path(coleccion).as_str(), IFACE_COLECCION, "ItemChanged", &ruta);
+        Ok(())
+    }
+
+    fn handle_prompt(&self, estado: &mut Estado, msg: &Message, miembro: &str, prompt: String) -> Resultado<()> {
+        match miembro {
+            "Prompt" => {
+                self.reply(msg, &())?;
+                // Se pregunta en otro hilo para seguir atendiendo al resto de clientes
+                let bus = self.bus.clone();
+                let estado = Arc::clone(&self.estado);
+                std::thread::spawn(move || {
+                    let clave = ask_master_key(&estado);
+                    let mut estado = estado.lock().unwrap();
+                    let objetos = estado.prompts.remove(&prompt).unwrap_or_default();
+                    let desbloqueados = match clave {
+                        Some(clave) => {
+                            estado.master_key = Some(clave);
+                            Some(objetos)
+                        }
+                        None => None,
+                    };
+                    complete(&bus, &prompt, desbloqueados);
+                });
+                Ok(())
+            }
+            "Dismiss" => {
+                estado.prompts.remove(&prompt);
+                self.reply(msg, &())?;
+                complete(&self.bus, &prompt, None);
+                Ok(())
+            }
+            otro => Err(unknown_method(otro)),
+        }
+    }
+}
+
+fn unknown_method(miembro: &str) -> Fallo {
+    Fallo::new("org.freedesktop.DBus.Error.UnknownMethod", format!("Método desconocido: {}", miembro))
+}
+
+// Señal Completed del prompt: con los objetos desbloqueados o descartado
+fn complete(bus: &Bus, prompt: &str, desbloqueados: Option<Vec<OwnedObjectPath>>) {
+    let descartado = desbloqueados.is_none();
+    let resultado = Value::from(desbloqueados.unwrap_or_default());
+    let _ = bus.emit_signal(None::<&str>, prompt, IFACE_PROMPT, "Completed", &(descartado, resultado));
+}
+
+// Pide la clave maestra con $VECLA_ASKPASS o $SSH_ASKPASS y la comprueba. Sin programa
+// para preguntar, el prompt se descarta.
+fn ask_master_key(estado: &Mutex<Estado>) -> Option<String> {
+    let askpass = std::env::var_os("VECLA_ASKPASS").or_else(|| std::env::var_os("SSH_ASKPASS"))?;
+    let salida = Command::new(askpass).arg("Clave maestra de Vecla:").output().ok()?;
+    if !salida.status.success() {
+        return None;
+    }
+    let mut texto = String::from_utf8(salida.stdout).ok()?;
+    let contrasena = texto.trim_end_matches(['\r', '\n']).to_string();
+    texto.zeroize();
+    let estado = estado.lock().unwrap();
+    let clave = compose_master_key(&contrasena, estado.config.get_key_file_path().as_deref()).ok()?;
+    check_master_key(&estado.conn, &clave).ok()?.then_some(clave)
+}
+
+// Ocupa el nombre org.freedesktop.secrets en el bus de sesión y atiende las peticiones.
+// Sin clave maestra la bóveda empieza bloqueada.
+pub fn serve(config: Config, master_key: Option<String>, reemplazar: bool) -> Result<(), String> {
+    let conn = open_db(&config).map_err(|e| e.to_string())?;
+    let bus = Bus::session().map_err(|e| format!("No se pudo conectar al bus de sesión: {}", e))?;
+    let mut flags = RequestNameFlags::DoNotQueue | RequestNameFlags::AllowReplacement;
+    if reemplazar {
+        flags |= RequestNameFlags::ReplaceExisting;
+    }
+    match bus.request_name_with_flags(NOMBRE_BUS, flags) {
+        Ok(RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner) => {}
+        Ok(_) | Err(zbus::Error::NameTaken) => {
+            return Err(format!("Otro proveedor ya ocupa {} (¿gnome-keyring o KWallet?); usa --replace", NOMBRE_BUS));
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+
+    let estado = Estado {
+        config,
+        conn,
+        master_key,
+        sesiones: HashSet::new(),
+        prompts: HashMap::new(),
+        nuevas: BTreeSet::new(),
+        contador: 0,
+    };
+    handle_messages(bus, estado);
+    Ok(())
+}
+
+// Atiende las llamadas que llegan por la conexión hasta que se cierra
+fn handle_messages(bus: Bus, estado: Estado) {
+    let servicio = Servicio { bus: bus.clone(), estado: Arc::new(Mutex::new(estado)) };
+    for msg in MessageIterator::from(&bus) {
+        let Ok(msg) = msg else { continue };
+        if msg.message_type() != MessageType::MethodCall {
+            continue;
+        }
+        if let Err(fallo) = servicio.handle(&msg) {
+            let _ = bus.reply_error(&msg, fallo.nombre, &fallo.mensaje);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_vault;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixStream;
+    use zbus::blocking::ConnectionBuilder;
+    use zbus::Guid;
+
+    const CLAVE: &str = "k1";
+
+    // Servicio y cliente unidos por un par de sockets, sin pasar por el bus de sesión
+    fn private_bus(dir: &std::path::Path) -> Bus {
+        let mut config = Config::new().with_db_path(&dir.join("vault.db").to_string_lossy());
+        config.auto_save = false;
+        let estado = Estado {
+            config,
+            conn: test_vault(),
+            master_key: Some(CLAVE.to_string()),
+            sesiones: HashSet::new(),
+            prompts: HashMap::new(),
+            nuevas: BTreeSet::new(),
+            contador: 0,
+        };
+        let (servidor, cliente) = UnixStream::pair().unwrap();
+        std::thread::spawn(move || {
+            let guid = Guid::generate();
+            let bus = ConnectionBuilder::unix_stream(servidor).server(&guid).p2p().build().unwrap();
+            handle_messages(bus, estado);
+        });
+        ConnectionBuilder::unix_stream(cliente).p2p().build().unwrap()
+    }
+
+    fn call<B>(bus: &Bus, ruta: &str, interfaz: &str, metodo: &str, cuerpo: &B) -> zbus::Result<Arc<Message>>
+    where
+        B: Serialize + Type,
+    {
+        bus.call_method(None::<&str>, ruta, Some(interfaz), metodo, cuerpo)
+    }
+
+    fn create_collection(bus: &Bus, etiqueta: &str) -> OwnedObjectPath {
+        let propiedades = HashMap::from([(PROP_LABEL_COLECCION, Value::from(etiqueta))]);
+        let respuesta = call(bus, RUTA_SERVICIO, IFACE_SERVICIO, "CreateCollection", &(propiedades, "")).unwrap();
+        let (ruta, _): (OwnedObjectPath, OwnedObjectPath) = respuesta.body().unwrap();
+        ruta
+    }
+
+    fn search(bus: &Bus, buscados: &HashMap<&str, &str>) -> (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) {
+        call(bus, RUTA_SERVICIO, IFACE_SERVICIO, "SearchItems", buscados).unwrap().body().unwrap()
+    }
+
+    #[test]
+    fn path_elements_are_reversible() {
+        assert_eq!(path_element("login"), "login");
+        assert_ne!(path_element("a-b"), path_element("a_b"));
+        assert_ne!(path_element("a b"), path_element("a_20b"));
+        assert!(path_element("Contraseñas web").chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+
+    #[test]
+    fn session_item_and_lock_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vecla-secret-service-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let bus = private_bus(&dir);
+
+        // Solo transporte en claro
+        let dh = ("dh-ietf1024-sha256-aes128-cbc-pkcs7", Value::from(""));
+        assert!(call(&bus, RUTA_SERVICIO, IFACE_SERVICIO, "OpenSession", &dh).is_err());
+        let respuesta = call(&bus, RUTA_SERVICIO, IFACE_SERVICIO, "OpenSession", &("plain", Value::from(""))).unwrap();
+        let (_, sesion): (OwnedValue, OwnedObjectPath) = respuesta.body().unwrap();
+
+        // Colecciones con nombres que antes compartían ruta
+        let guion = create_collection(&bus, "a-b");
+        let subrayado = create_collection(&bus, "a_b");
+        assert_ne!(guion, subrayado);
+        let etiqueta = |ruta: &OwnedObjectPath| -> String {
+            let respuesta = call(&bus, ruta.as_str(), IFACE_PROPIEDADES, "Get", &(IFACE_COLECCION, "Label")).unwrap();
+            let valor: OwnedValue = respuesta.body().unwrap();
+            String::try_from(valor).unwrap()
+        };
+        assert_eq!(etiqueta(&guion), "a-b");
+        assert_eq!(etiqueta(&subrayado), "a_b");
+
+        // CreateItem en la colección por defecto
+        let atributos = HashMap::from([("servicio", "correo"), ("usuario", "ana")]);
+        let propiedades = HashMap::from([
+            (PROP_LABEL_ITEM, Value::from("Correo")),
+            (PROP_ATRIBUTOS_ITEM, Value::from(atributos.clone())),
+        ]);
+        let secreto = Secreto {
+            sesion: sesion.clone(),
+            parametros: Vec::new(),
+            valor: b"s3creto".to_vec(),
+            tipo: "text/plain".to_string(),
+        };
+        let coleccion = collection_path(COLECCION_DEFECTO);
+        let cuerpo = (propiedades, secreto, false);
+        let respuesta = call(&bus, coleccion.as_str(), IFACE_COLECCION, "CreateItem", &cuerpo).unwrap();
+        let (item, prompt): (OwnedObjectPath, OwnedObjectPath) = respuesta.body().unwrap();
+        assert_eq!(prompt, none_path());
+
+        // SearchItems y GetSecrets
+        assert_eq!(search(&bus, &HashMap::from([("servicio", "correo")])), (vec![item.clone()], vec![]));
+        assert_eq!(search(&bus, &HashMap::from([("servicio", "otro")])), (vec![], vec![]));
+        let get_secrets = || {
+            call(&bus, RUTA_SERVICIO, IFACE_SERVICIO, "GetSecrets", &(vec![item.clone()], sesion.clone()))
+        };
+        let secretos: HashMap<OwnedObjectPath, Secreto> = get_secrets().unwrap().body().unwrap();
+        assert_eq!(secretos[&item].valor, b"s3creto");
+
+        // Lock: el elemento pasa a la lista de bloqueados y el secreto deja de leerse
+        call(&bus, RUTA_SERVICIO, IFACE_SERVICIO, "Lock", &(Vec::<OwnedObjectPath>::new())).unwrap();
+        assert_eq!(search(&bus, &HashMap::from([("servicio", "correo")])), (vec![], vec![item.clone()]));
+        assert!(get_secrets().is_err());
+
+        // Unlock pide la clave con un prompt; el askpass devuelve la clave maestra
+        let askpass = dir.join("askpass");
+        std::fs::write(&askpass, format!("#!/bin/sh\necho {}\n", CLAVE)).unwrap();
+        std::fs::set_permissions(&askpass, std::fs::Permissions::from_mode(0o700)).unwrap();
+        std::env::set_var("VECLA_ASKPASS", &askpass);
+        let respuesta = call(&bus, RUTA_SERVICIO, IFACE_SERVICIO, "Unlock", &(vec![item.clone()])).unwrap();
+        let (desbloqueados, prompt): (Vec<OwnedObjectPath>, OwnedObjectPath) = respuesta.body().unwrap();
+        assert!(desbloqueados.is_empty());
+        assert_ne!(prompt, none_path());
+
+        let mensajes = MessageIterator::from(&bus);
+        call(&bus, prompt.as_str(), IFACE_PROMPT, "Prompt", &("")).unwrap();
+        let completado = mensajes
+            .filter_map(Result::ok)
+            .find(|m| m.message_type() == MessageType::Signal && m.member().is_some_and(|n| n == "Completed"))
+            .unwrap();
+        let (descartado, resultado): (bool, OwnedValue) = completado.body().unwrap();
+        assert!(!descartado);
+        assert_eq!(Vec::<OwnedObjectPath>::try_from(resultado).unwrap(), vec![item.clone()]);
+
+        let secretos: HashMap<OwnedObjectPath, Secreto> = get_secrets().unwrap().body().unwrap();
+        assert_eq!(secretos[&item].valor, b"s3creto");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,759 @@
+// Estimación de la fortaleza de una contraseña al estilo de zxcvbn: se busca la forma más
+// barata de adivinarla combinando palabras de diccionario (con mayúsculas, sustituciones
+// l33t o al revés), datos de la propia entrada, patrones de teclado, secuencias,
+// repeticiones y fechas; lo que no encaja en ningún patrón cuenta como fuerza bruta.
+// El resultado son los intentos necesarios, una nota de 0 a 4, el tiempo estimado para
+// descifrarla y consejos para mejorarla.
+use chrono::Datelike;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Contraseñas más usadas, de más a menos frecuente
+const CONTRASENAS: &str = include_str!("wordlists/passwords.txt");
+// Palabras comunes (las mismas de las frases de contraseña); no están ordenadas por uso
+const PALABRAS: [&str; 2] = [include_str!("wordlists/es.txt"), include_str!("wordlists/en.txt")];
+const RANGO_PALABRA: f64 = 1000.0;
+
+// Solo se analiza el principio de contraseñas muy largas; el resto cuenta como fuerza bruta
+const LARGO_MAXIMO: usize = 100;
+const LARGO_MAXIMO_PALABRA: usize = 30;
+const FUERZA_BRUTA_POR_CARACTER: f64 = 10.0;
+const MINIMO_UN_CARACTER: f64 = 10.0;
+const MINIMO_VARIOS_CARACTERES: f64 = 50.0;
+// Cuánto cuesta además encadenar un patrón más
+const MINIMO_POR_PATRON: f64 = 10_000.0;
+// Ataque sin conexión contra un hash lento (bcrypt, Argon2...)
+const INTENTOS_POR_SEGUNDO: f64 = 1e4;
+
+pub struct Strength {
+    pub guesses: f64,       // intentos para adivinarla
+    pub bits: f64,          // log2 de los intentos
+    pub score: u8,          // 0 (muy débil) a 4 (muy fuerte)
+    pub crack_seconds: f64, // tiempo estimado sin conexión contra un hash lento
+    pub warning: Option<&'static str>,
+    pub suggestions: Vec<&'static str>,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "Muy débil",
+            1 => "Débil",
+            2 => "Aceptable",
+            3 => "Fuerte",
+            _ => "Muy fuerte",
+        }
+    }
+
+    pub fn crack_time_label(&self) -> String {
+        let segundos = self.crack_seconds;
+        const MINUTO: f64 = 60.0;
+        const HORA: f64 = 60.0 * MINUTO;
+        const DIA: f64 = 24.0 * HORA;
+        const MES: f64 = 31.0 * DIA;
+        const ANO: f64 = 12.0 * MES;
+        let (cantidad, singular, plural) = match segundos {
+            s if s < 1.0 => return "menos de un segundo".to_string(),
+            s if s < MINUTO => (s, "segundo", "segundos"),
+            s if s < HORA => (s / MINUTO, "minuto", "minutos"),
+            s if s < DIA => (s / HORA, "hora", "horas"),
+            s if s < MES => (s / DIA, "día", "días"),
+            s if s < ANO => (s / MES, "mes", "meses"),
+            s if s < 100.0 * ANO => (s / ANO, "año", "años"),
+            _ => return "siglos".to_string(),
+        };
+        let cantidad = cantidad.round() as u64;
+        format!("{} {}", cantidad, if cantidad == 1 { singular } else { plural })
+    }
+}
+
+pub fn estimate(password: &str) -> Strength {
+    estimate_with_inputs(password, &[])
+}
+
+// user_inputs: datos que un atacante podría probar primero (app, usuario, mail...)
+pub fn estimate_with_inputs(password: &str, user_inputs: &[&str]) -> Strength {
+    let caracteres: Vec<char> = password.chars().collect();
+    if caracteres.is_empty() {
+        return Strength {
+            guesses: 1.0,
+            bits: 0.0,
+            score: 0,
+            crack_seconds: 0.0,
+            warning: None,
+            suggestions: vec!["Escribe una contraseña", "Una frase de varias palabras al azar es fácil de recordar y difícil de adivinar"],
+        };
+    }
+    let entradas = user_dictionary(user_inputs);
+    let analizados = &caracteres[..caracteres.len().min(LARGO_MAXIMO)];
+    let (mut guesses, secuencia) = most_guessable(analizados, &entradas);
+    // Lo que queda fuera del análisis solo puede sumar
+    guesses *= FUERZA_BRUTA_POR_CARACTER.powi((caracteres.len() - analizados.len()) as i32);
+    let score = match guesses {
+        g if g < 1e3 + 5.0 => 0,
+        g if g < 1e6 + 5.0 => 1,
+        g if g < 1e8 + 5.0 => 2,
+        g if g < 1e10 + 5.0 => 3,
+        _ => 4,
+    };
+    let (warning, suggestions) = feedback(score, &secuencia, analizados.len());
+    Strength {
+        guesses,
+        bits: guesses.log2(),
+        score,
+        crack_seconds: guesses / INTENTOS_POR_SEGUNDO,
+        warning,
+        suggestions,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Origen {
+    Contrasenas,
+    Palabras,
+    Entrada,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mayusculas {
+    Ninguna,
+    Inicial,
+    Todas,
+    Otras,
+}
+
+#[derive(Clone)]
+enum Tipo {
+    Diccionario { origen: Origen, rango: f64, l33t: bool, reves: bool, mayusculas: Mayusculas },
+    Teclado { giros: usize },
+    Secuencia,
+    Repeticion { base: usize },
+    Fecha,
+    FuerzaBruta,
+}
+
+#[derive(Clone)]
+struct Patron {
+    i: usize, // primer carácter
+    j: usize, // último carácter, incluido
+    guesses: f64,
+    tipo: Tipo,
+}
+
+// La combinación de patrones que cubre toda la contraseña con menos intentos. Como en
+// zxcvbn, una secuencia de l patrones cuesta l! · Π(intentos) + MINIMO_POR_PATRON^(l-1).
+fn most_guessable(caracteres: &[char], entradas: &HashMap<String, f64>) -> (f64, Vec<Patron>) {
+    let n = caracteres.len();
+    let mut por_fin: Vec<Vec<Patron>> = vec![Vec::new(); n];
+    for patron in find_patterns(caracteres, entradas) {
+        por_fin[patron.j].push(patron);
+    }
+    // mejor[k][l]: (total, producto, patrón, fin del anterior) para cubrir 0..=k con l patrones
+    let mut mejor: Vec<HashMap<usize, (f64, f64, Patron)>> = vec![HashMap::new(); n];
+    for k in 0..n {
+        let mut candidatos = por_fin[k].clone();
+        for i in 0..=k {
+            let largo = k - i + 1;
+            let minimo = if largo == 1 { MINIMO_UN_CARACTER } else { MINIMO_VARIOS_CARACTERES };
+            candidatos.push(Patron {
+                i,
+                j: k,
+                guesses: FUERZA_BRUTA_POR_CARACTER.powi(largo as i32).max(minimo),
+                tipo: Tipo::FuerzaBruta,
+            });
+        }
+        for patron in candidatos {
+            let anteriores: Vec<(usize, f64)> = if patron.i == 0 {
+                vec![(0, 1.0)]
+            } else {
+                mejor[patron.i - 1].iter().map(|(l, (_, producto, _))| (*l, *producto)).collect()
+            };
+            for (l, producto) in anteriores {
+                let l = l + 1;
+                let producto = producto * patron.guesses;
+                let total = factorial(l) * producto + MINIMO_POR_PATRON.powi(l as i32 - 1);
+                let mejora = mejor[k].get(&l).is_none_or(|(actual, _, _)| total < *actual);
+                if mejora {
+                    mejor[k].insert(l, (total, producto, patron.clone()));
+                }
+            }
+        }
+    }
+    let Some((mut l, (total, _, _))) = mejor[n - 1]
+        .iter()
+        .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+        .map(|(l, v)| (*l, v.clone()))
+    else {
+        return (1.0, Vec::new());
+    };
+    // Recorre hacia atrás la secuencia elegida
+    let mut secuencia = Vec::new();
+    let mut k = n - 1;
+    loop {
+        let patron = mejor[k][&l].2.clone();
+        let inicio = patron.i;
+        secuencia.push(patron);
+        if inicio == 0 {
+            break;
+        }
+        k = inicio - 1;
+        l -= 1;
+    }
+    secuencia.reverse();
+    (total, secuencia)
+}
+
+fn find_patterns(caracteres: &[char], entradas: &HashMap<String, f64>) -> Vec<Patron> {
+    let mut patrones = dictionary_patterns(caracteres, entradas);
+    patrones.extend(keyboard_patterns(caracteres));
+    patrones.extend(sequence_patterns(caracteres));
+    patrones.extend(repeat_patterns(caracteres, entradas));
+    patrones.extend(date_patterns(caracteres));
+    patrones
+}
+
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// Las listas no tienen tildes ni eñes: "contraseña" se busca como "contrasena"
+fn without_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'ä' | 'â' => 'a',
+        'é' | 'è' | 'ë' | 'ê' => 'e',
+        'í' | 'ì' | 'ï' | 'î' => 'i',
+        'ó' | 'ò' | 'ö' | 'ô' => 'o',
+        'ú' | 'ù' | 'ü' | 'û' => 'u',
+        'ñ' => 'n',
+        'ç' => 'c',
+        otro => otro,
+    }
+}
+
+struct Diccionarios {
+    contrasenas: HashMap<String, f64>, // palabra y puesto en la lista
+    palabras: HashSet<String>,
+}
+
+fn dictionaries() -> &'static Diccionarios {
+    static DICCIONARIOS: OnceLock<Diccionarios> = OnceLock::new();
+    DICCIONARIOS.get_or_init(|| Diccionarios {
+        contrasenas: CONTRASENAS
+            .lines()
+            .filter(|l| !l.is_empty())
+            .enumerate()
+            .map(|(i, l)| (l.to_string(), (i + 1) as f64))
+            .collect(),
+        palabras: PALABRAS.iter().flat_map(|lista| lista.lines()).map(str::to_string).collect(),
+    })
+}
+
+// Los datos de la entrada y sus partes (el mail entero, el usuario, el dominio...)
+fn user_dictionary(user_inputs: &[&str]) -> HashMap<String, f64> {
+    let mut entradas = HashMap::new();
+    let mut rango = 1.0;
+    for dato in user_inputs {
+        let dato: String = dato.trim().to_lowercase().chars().map(without_accent).collect();
+        let partes = std::iter::once(dato.as_str()).chain(dato.split(|c: char| !c.is_alphanumeric()));
+        for parte in partes.filter(|p| p.chars().count() >= 3) {
+            entradas.entry(parte.to_string()).or_insert_with(|| {
+                rango += 1.0;
+                rango - 1.0
+            });
+        }
+    }
+    entradas
+}
+
+// Sustituciones l33t; un mismo carácter puede sustituir a varias letras, así que se
+// prueban dos tablas
+const L33T: [&[(char, char)]; 2] = [
+    &[('4', 'a'), ('@', 'a'), ('8', 'b'), ('(', 'c'), ('{', 'c'), ('[', 'c'), ('<', 'c'), ('3', 'e'), ('6', 'g'), ('9', 'g'), ('1', 'i'), ('!', 'i'), ('|', 'i'), ('0', 'o'), ('$', 's'), ('5', 's'), ('+', 't'), ('7', 't'), ('%', 'x'), ('2', 'z')],
+    &[('1', 'l'), ('|', 'l'), ('7', 'l')],
+];
+
+fn dictionary_patterns(caracteres: &[char], entradas: &HashMap<String, f64>) -> Vec<Patron> {
+    let n = caracteres.len();
+    let minusculas: Vec<char> = caracteres.iter().map(|c| without_accent(lowercase(*c))).collect();
+    let mut patrones = Vec::new();
+    // Tal cual
+    for (i, j, origen, rango) in lookup(&minusculas, entradas) {
+        let mayusculas = case_of(&caracteres[i..=j]);
+        let guesses = rango * uppercase_variations(&caracteres[i..=j]);
+        patrones.push(Patron { i, j, guesses, tipo: Tipo::Diccionario { origen, rango, l33t: false, reves: false, mayusculas } });
+    }
+    // Al revés
+    let reves: Vec<char> = minusculas.iter().rev().copied().collect();
+    if reves != minusculas {
+        for (a, b, origen, rango) in lookup(&reves, entradas) {
+            let (i, j) = (n - 1 - b, n - 1 - a);
+            // Los palíndromos ya se encuentran tal cual
+            if reves[a..=b] == minusculas[i..=j] {
+                continue;
+            }
+            let mayusculas = case_of(&caracteres[i..=j]);
+            let guesses = rango * uppercase_variations(&caracteres[i..=j]) * 2.0;
+            patrones.push(Patron { i, j, guesses, tipo: Tipo::Diccionario { origen, rango, l33t: false, reves: true, mayusculas } });
+        }
+    }
+    // Con sustituciones l33t deshechas
+    for tabla in L33T {
+        let sustituido: Vec<char> = minusculas
+            .iter()
+            .map(|c| tabla.iter().find(|(l33t, _)| l33t == c).map_or(*c, |(_, letra)| *letra))
+            .collect();
+        if sustituido == minusculas {
+            continue;
+        }
+        for (i, j, origen, rango) in lookup(&sustituido, entradas) {
+            // Solo cuenta si usa alguna sustitución
+            if sustituido[i..=j] == minusculas[i..=j] {
+                continue;
+            }
+            let mayusculas = case_of(&caracteres[i..=j]);
+            let guesses = rango
+                * uppercase_variations(&caracteres[i..=j])
+                * l33t_variations(&minusculas[i..=j], &sustituido[i..=j]);
+            patrones.push(Patron { i, j, guesses, tipo: Tipo::Diccionario { origen, rango, l33t: true, reves: false, mayusculas } });
+        }
+    }
+    patrones
+}
+
+// Subcadenas de al menos 3 caracteres que están en algún diccionario (el de puesto más bajo)
+fn lookup(texto: &[char], entradas: &HashMap<String, f64>) -> Vec<(usize, usize, Origen, f64)> {
+    let diccionarios = dictionaries();
+    let mut encontradas = Vec::new();
+    for i in 0..texto.len() {
+        for j in (i + 2)..texto.len().min(i + LARGO_MAXIMO_PALABRA) {
+            let palabra: String = texto[i..=j].iter().collect();
+            let opciones = [
+                diccionarios.contrasenas.get(&palabra).map(|r| (Origen::Contrasenas, *r)),
+                entradas.get(&palabra).map(|r| (Origen::Entrada, *r)),
+                diccionarios.palabras.contains(&palabra).then_some((Origen::Palabras, RANGO_PALABRA)),
+            ];
+            if let Some((origen, rango)) = opciones.into_iter().flatten().min_by(|a, b| a.1.total_cmp(&b.1)) {
+                encontradas.push((i, j, origen, rango));
+            }
+        }
+    }
+    encontradas
+}
+
+fn case_of(palabra: &[char]) -> Mayusculas {
+    let letras: Vec<&char> = palabra.iter().filter(|c| c.is_alphabetic()).collect();
+    let mayusculas = letras.iter().filter(|c| c.is_uppercase()).count();
+    match mayusculas {
+        0 => Mayusculas::Ninguna,
+        m if m == letras.len() => Mayusculas::Todas,
+        1 if letras.first().is_some_and(|c| c.is_uppercase()) => Mayusculas::Inicial,
+        _ => Mayusculas::Otras,
+    }
+}
+
+fn uppercase_variations(palabra: &[char]) -> f64 {
+    match case_of(palabra) {
+        Mayusculas::Ninguna => 1.0,
+        Mayusculas::Inicial | Mayusculas::Todas => 2.0,
+        Mayusculas::Otras => {
+            let mayusculas = palabra.iter().filter(|c| c.is_uppercase()).count();
+            let minusculas = palabra.iter().filter(|c| c.is_lowercase()).count();
+            (1..=mayusculas.min(minusculas)).map(|k| binomial(mayusculas + minusculas, k)).sum()
+        }
+    }
+}
+
+fn l33t_variations(original: &[char], sustituido: &[char]) -> f64 {
+    let mut variaciones = 1.0;
+    let letras: HashSet<char> = original.iter().zip(sustituido).filter(|(o, s)| o != s).map(|(_, s)| *s).collect();
+    for letra in letras {
+        let cambiadas = original.iter().zip(sustituido).filter(|(o, s)| **s == letra && **o != letra).count();
+        let intactas = original.iter().filter(|o| **o == letra).count();
+        variaciones *= if intactas == 0 {
+            2.0
+        } else {
+            (1..=cambiadas.min(intactas)).map(|k| binomial(cambiadas + intactas, k)).sum()
+        };
+    }
+    variaciones
+}
+
+// Teclado QWERTY: fila, posición horizontal en cuartos de tecla y si va con mayúsculas
+const FILAS: [(&str, &str, i32); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+", 0),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|", 6),
+    ("asdfghjkl;'", "ASDFGHJKL:\"", 7),
+    ("zxcvbnm,./", "ZXCVBNM<>?", 9),
+];
+
+fn key_position(c: char) -> Option<(i32, i32, bool)> {
+    for (fila, (normal, shift, desplazamiento)) in FILAS.iter().enumerate() {
+        for (tecla, (n, s)) in normal.chars().zip(shift.chars()).enumerate() {
+            if c == n || c == s {
+                return Some((fila as i32, desplazamiento + tecla as i32 * 4, c == s && n != s));
+            }
+        }
+    }
+    None
+}
+
+// Dirección entre dos teclas vecinas, o None si no lo son
+fn key_direction(a: char, b: char) -> Option<(i32, i32)> {
+    let (fa, xa, _) = key_position(a)?;
+    let (fb, xb, _) = key_position(b)?;
+    let (df, dx) = (fb - fa, xb - xa);
+    let vecinas = (df == 0 && dx.abs() == 4) || (df.abs() == 1 && dx.abs() <= 3);
+    vecinas.then_some((df, dx.signum()))
+}
+
+fn keyboard_patterns(caracteres: &[char]) -> Vec<Patron> {
+    // Teclas y vecinas de media
+    const TECLAS: f64 = 47.0;
+    const GRADO_MEDIO: f64 = 4.6;
+    let mut patrones = Vec::new();
+    let mut i = 0;
+    while i + 2 < caracteres.len() {
+        let mut j = i;
+        let mut giros = 0;
+        let mut anterior = None;
+        while j + 1 < caracteres.len() {
+            let Some(direccion) = key_direction(caracteres[j], caracteres[j + 1]) else { break };
+            if anterior != Some(direccion) {
+                giros += 1;
+                anterior = Some(direccion);
+            }
+            j += 1;
+        }
+        let largo = j - i + 1;
+        if largo < 3 {
+            i += 1;
+            continue;
+        }
+        let mut guesses = 0.0;
+        for l in 2..=largo {
+            for t in 1..=giros.min(l - 1) {
+                guesses += binomial(l - 1, t - 1) * TECLAS * GRADO_MEDIO.powi(t as i32);
+            }
+        }
+        let con_shift = caracteres[i..=j].iter().filter(|c| key_position(**c).is_some_and(|p| p.2)).count();
+        let sin_shift = largo - con_shift;
+        if con_shift > 0 {
+            guesses *= if sin_shift == 0 {
+                2.0
+            } else {
+                (1..=con_shift.min(sin_shift)).map(|k| binomial(largo, k)).sum()
+            };
+        }
+        patrones.push(Patron { i, j, guesses, tipo: Tipo::Teclado { giros } });
+        i = j + 1;
+    }
+    patrones
+}
+
+// abc, 13579, 9876...: saltos iguales de hasta 5
+fn sequence_patterns(caracteres: &[char]) -> Vec<Patron> {
+    let mut patrones = Vec::new();
+    let mut i = 0;
+    while i + 2 < caracteres.len() {
+        let salto = caracteres[i + 1] as i64 - caracteres[i] as i64;
+        let mut j = i + 1;
+        while j + 1 < caracteres.len() && caracteres[j + 1] as i64 - caracteres[j] as i64 == salto {
+            j += 1;
+        }
+        if j - i + 1 >= 3 && salto != 0 && salto.abs() <= 5 {
+            let primero = caracteres[i];
+            let mut base = if "aAzZ019".contains(primero) {
+                4.0
+            } else if primero.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if salto < 0 {
+                base *= 2.0;
+            }
+            patrones.push(Patron { i, j, guesses: base * (j - i + 1) as f64, tipo: Tipo::Secuencia });
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    patrones
+}
+
+// aaa, abcabc...: la base repetida, cuantas más veces mejor
+fn repeat_patterns(caracteres: &[char], entradas: &HashMap<String, f64>) -> Vec<Patron> {
+    let n = caracteres.len();
+    let mut patrones = Vec::new();
+    let mut i = 0;
+    while i < n {
+        // (base, repeticiones) que cubre más caracteres
+        let mut mejor: Option<(usize, usize)> = None;
+        for base in 1..=(n - i) / 2 {
+            let mut veces = 1;
+            while i + (veces + 1) * base <= n && caracteres[i + veces * base..i + (veces + 1) * base] == caracteres[i..i + base] {
+                veces += 1;
+            }
+            if veces >= 2 && base * veces >= 3 && mejor.is_none_or(|(b, v)| base * veces > b * v) {
+                mejor = Some((base, veces));
+            }
+        }
+        match mejor {
+            Some((base, veces)) => {
+                let (guesses_base, _) = most_guessable(&caracteres[i..i + base], entradas);
+                let j = i + base * veces - 1;
+                patrones.push(Patron { i, j, guesses: guesses_base * veces as f64, tipo: Tipo::Repeticion { base } });
+                i = j + 1;
+            }
+            None => i += 1,
+        }
+    }
+    patrones
+}
+
+fn date_patterns(caracteres: &[char]) -> Vec<Patron> {
+    let referencia = chrono::Local::now().year();
+    let distancia = |ano: i32| ((ano - referencia).abs() as f64).max(20.0);
+    let n = caracteres.len();
+    let mut patrones = Vec::new();
+    for i in 0..n {
+        // Fechas sin separadores (4 a 8 dígitos) y años sueltos
+        for j in (i + 3)..n.min(i + 8) {
+            if !caracteres[i..=j].iter().all(char::is_ascii_digit) {
+                break;
+            }
+            let digitos: String = caracteres[i..=j].iter().collect();
+            if j - i == 3 {
+                let ano: i32 = digitos.parse().unwrap_or(0);
+                if (1900..=2050).contains(&ano) {
+                    patrones.push(Patron { i, j, guesses: distancia(ano), tipo: Tipo::Fecha });
+                }
+            }
+            if let Some(ano) = split_date(&digitos) {
+                patrones.push(Patron { i, j, guesses: distancia(ano) * 365.0, tipo: Tipo::Fecha });
+            }
+        }
+        // Con separadores: 1/5/85, 01-05-1985, 1985.05.01...
+        if let Some((j, ano)) = separated_date(&caracteres[i..]) {
+            patrones.push(Patron { i, j: i + j, guesses: distancia(ano) * 365.0 * 4.0, tipo: Tipo::Fecha });
+        }
+    }
+    patrones
+}
+
+// Año de la fecha más reciente que se puede leer en los dígitos, si hay alguna
+fn split_date(digitos: &str) -> Option<i32> {
+    let largo = digitos.len();
+    let mut anos = Vec::new();
+    for a in 1..largo - 1 {
+        for b in (a + 1)..largo {
+            let partes = [&digitos[..a], &digitos[a..b], &digitos[b..]];
+            if partes.iter().all(|p| p.len() <= 4) {
+                anos.extend(read_date(partes));
+            }
+        }
+    }
+    anos.into_iter().max()
+}
+
+fn separated_date(texto: &[char]) -> Option<(usize, i32)> {
+    let grupo = |desde: usize, maximo: usize| -> usize {
+        texto[desde..].iter().take(maximo).take_while(|c| c.is_ascii_digit()).count()
+    };
+    let primero = grupo(0, 4);
+    let separador = *texto.get(primero)?;
+    if primero == 0 || !"/-._ ".contains(separador) {
+        return None;
+    }
+    let segundo = grupo(primero + 1, 2);
+    if segundo == 0 || texto.get(primero + 1 + segundo) != Some(&separador) {
+        return None;
+    }
+    let inicio_tercero = primero + segundo + 2;
+    if inicio_tercero >= texto.len() {
+        return None;
+    }
+    let tercero = grupo(inicio_tercero, 4);
+    if tercero == 0 {
+        return None;
+    }
+    let trozo = |desde: usize, largo: usize| texto[desde..desde + largo].iter().collect::<String>();
+    let partes = [trozo(0, primero), trozo(primero + 1, segundo), trozo(inicio_tercero, tercero)];
+    let ano = read_date([&partes[0], &partes[1], &partes[2]]).into_iter().max()?;
+    Some((inicio_tercero + tercero - 1, ano))
+}
+
+// Años válidos leyendo las partes como día-mes-año, mes-día-año o año-mes-día
+fn read_date(partes: [&str; 3]) -> Vec<i32> {
+    let numero = |p: &str| p.parse::<i32>().ok();
+    let ano = |p: &str| -> Option<i32> {
+        let valor = numero(p)?;
+        match p.len() {
+            2 if valor > 50 => Some(1900 + valor),
+            2 => Some(2000 + valor),
+            4 if (1000..=2050).contains(&valor) => Some(valor),
+            _ => None,
+        }
+    };
+    let valida = |dia: Option<i32>, mes: Option<i32>| {
+        dia.is_some_and(|d| (1..=31).contains(&d)) && mes.is_some_and(|m| (1..=12).contains(&m))
+    };
+    let mut anos = Vec::new();
+    let [a, b, c] = partes;
+    for (dia, mes, y) in [(a, b, c), (b, a, c), (c, b, a)] {
+        if let Some(y) = ano(y) {
+            if valida(numero(dia), numero(mes)) {
+                anos.push(y);
+            }
+        }
+    }
+    anos
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|k| k as f64).product()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (1..=k).map(|i| (n - k + i) as f64 / i as f64).product()
+}
+
+fn feedback(score: u8, secuencia: &[Patron], largo: usize) -> (Option<&'static str>, Vec<&'static str>) {
+    if score >= 3 {
+        return (None, Vec::new());
+    }
+    let mut consejos = vec!["Añade una o dos palabras más; mejor si son poco comunes"];
+    // El patrón que más caracteres explica
+    let Some(principal) = secuencia.iter().filter(|p| !matches!(p.tipo, Tipo::FuerzaBruta)).max_by_key(|p| p.j - p.i) else {
+        if largo < 12 {
+            consejos.push("Usa una contraseña más larga");
+        }
+        return (None, consejos);
+    };
+    let aviso = match &principal.tipo {
+        Tipo::Diccionario { origen, rango, l33t, reves, mayusculas } => {
+            match mayusculas {
+                Mayusculas::Inicial => consejos.push("Poner en mayúscula la primera letra no ayuda mucho"),
+                Mayusculas::Todas => consejos.push("Todo en mayúsculas es casi tan fácil de adivinar como todo en minúsculas"),
+                _ => {}
+            }
+            if *reves {
+                consejos.push("Escribir las palabras al revés no las hace mucho más difíciles de adivinar");
+            }
+            if *l33t {
+                consejos.push("Sustituciones previsibles como '@' por 'a' no ayudan mucho");
+            }
+            let sola = principal.i == 0 && principal.j + 1 == largo;
+            match origen {
+                Origen::Contrasenas if *rango <= 10.0 => "Es una de las 10 contraseñas más usadas",
+                Origen::Contrasenas if *rango <= 100.0 => "Es una de las 100 contraseñas más usadas",
+                Origen::Contrasenas => "Es una contraseña muy usada",
+                Origen::Entrada => "Contiene datos de la propia entrada (app, usuario, mail...)",
+                Origen::Palabras if sola => "Una palabra sola es fácil de adivinar",
+                Origen::Palabras => "Las palabras comunes son fáciles de adivinar",
+            }
+        }
+        Tipo::Teclado { giros: 1 } => "Las filas de teclas seguidas como qwerty son fáciles de adivinar",
+        Tipo::Teclado { .. } => "Los patrones cortos de teclado son fáciles de adivinar",
+        Tipo::Secuencia => "Las secuencias como abc o 6543 son fáciles de adivinar",
+        Tipo::Repeticion { base: 1 } => "Las repeticiones como 'aaa' son fáciles de adivinar",
+        Tipo::Repeticion { .. } => "Repetir un trozo como en 'abcabc' apenas la hace más difícil que 'abc'",
+        Tipo::Fecha => "Las fechas y los años son fáciles de adivinar",
+        Tipo::FuerzaBruta => unreachable!(),
+    };
+    match principal.tipo {
+        Tipo::Teclado { .. } => consejos.push("Usa un patrón de teclado más largo y con más giros, o mejor ninguno"),
+        Tipo::Secuencia => consejos.push("Evita las secuencias"),
+        Tipo::Repeticion { .. } => consejos.push("Evita repetir palabras y caracteres"),
+        Tipo::Fecha => consejos.push("Evita fechas y años relacionados contigo"),
+        _ => {}
+    }
+    (Some(aviso), consejos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(password: &str) -> u8 {
+        estimate(password).score
+    }
+
+    #[test]
+    fn common_passwords() {
+        for password in ["123456", "password", "qwerty", "987654321", "contrasena", "contraseña"] {
+            assert_eq!(score(password), 0, "{}", password);
+        }
+        assert_eq!(estimate("123456").warning, Some("Es una de las 10 contraseñas más usadas"));
+        // Mayúscula inicial y un dígito al final apenas ayudan
+        assert_eq!(score("Contraseña1"), 1);
+        assert_eq!(score("Summer2019!"), 2);
+    }
+
+    #[test]
+    fn l33t_and_reversed() {
+        for password in ["P@ssw0rd", "p4ssw0rd", "drowssap"] {
+            assert_eq!(score(password), 0, "{}", password);
+        }
+        assert!(estimate("P@ssw0rd").suggestions.contains(&"Sustituciones previsibles como '@' por 'a' no ayudan mucho"));
+        assert!(estimate("Drowssap").suggestions.iter().any(|s| s.contains("al revés")));
+    }
+
+    #[test]
+    fn keyboard_runs() {
+        assert_eq!(score("qwertyuiop"), 0);
+        assert_eq!(score("1qaz2wsx"), 0);
+        let fila = estimate("xcvbnm,.-");
+        assert_eq!(fila.score, 1);
+        assert_eq!(fila.warning, Some("Las filas de teclas seguidas como qwerty son fáciles de adivinar"));
+    }
+
+    #[test]
+    fn sequences_and_repeats() {
+        let secuencia = estimate("abcdef");
+        assert_eq!(secuencia.score, 0);
+        assert_eq!(secuencia.warning, Some("Las secuencias como abc o 6543 son fáciles de adivinar"));
+        let repetida = estimate("aaaaaaaa");
+        assert_eq!(repetida.score, 0);
+        assert_eq!(repetida.warning, Some("Las repeticiones como 'aaa' son fáciles de adivinar"));
+        assert_eq!(score("abcabcabc"), 0);
+        // Repetir una contraseña larga cuesta poco más que la contraseña sola
+        let base = estimate("x7#Kp2$vQ9!m").guesses;
+        assert!(estimate("x7#Kp2$vQ9!mx7#Kp2$vQ9!m").guesses < base * 10.0);
+    }
+
+    #[test]
+    fn dates() {
+        for password in ["19850512", "12/05/1985"] {
+            let fecha = estimate(password);
+            assert_eq!(fecha.score, 1, "{}", password);
+            assert_eq!(fecha.warning, Some("Las fechas y los años son fáciles de adivinar"));
+        }
+        assert_eq!(score("1985"), 0);
+        assert_eq!(score("gatito2024"), 2);
+    }
+
+    #[test]
+    fn user_inputs() {
+        assert_eq!(score("anagarcia"), 3);
+        let con_datos = estimate_with_inputs("anagarcia", &["anagarcia@example.com"]);
+        assert_eq!(con_datos.score, 0);
+        assert_eq!(con_datos.warning, Some("Contiene datos de la propia entrada (app, usuario, mail...)"));
+        assert_eq!(estimate_with_inputs("github2024", &["GitHub"]).score, 1);
+    }
+
+    #[test]
+    fn strong_passwords() {
+        for password in ["correcthorsebatterystaple", "caballo-bateria-grapa-correcto", "x7#Kp2$vQ9!m"] {
+            let fuerte = estimate(password);
+            assert_eq!(fuerte.score, 4, "{}", password);
+            assert!(fuerte.warning.is_none() && fuerte.suggestions.is_empty());
+        }
+        assert_eq!(score(""), 0);
+        assert_eq!(estimate("").crack_time_label(), "menos de un segundo");
+        assert_eq!(estimate("correcthorsebatterystaple").crack_time_label(), "siglos");
+    }
+}
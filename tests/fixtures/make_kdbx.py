@@ -0,0 +1,203 @@
+#!/usr/bin/env python3
+# Genera las bases de datos KDBX 4 de prueba de src/kdbx.rs con una implementación
+# independiente de la de Vecla (solo necesita el paquete "cryptography"). Imitan la
+# estructura que escribe KeePassXC: grupo raíz con subgrupo, papelera, campos propios,
+# valores protegidos, adjuntos en la cabecera interna e historial.
+#
+#   aes-kdf.kdbx   AES-KDF + AES-256-CBC, contraseña "vecla-aes"
+#   argon2.kdbx    Argon2id + ChaCha20, contraseña "vecla-argon2" y archivo de clave argon2.keyx
+#
+# Uso: python3 tests/fixtures/make_kdbx.py
+import base64
+import gzip
+import hashlib
+import hmac
+import os
+import struct
+import uuid
+from datetime import datetime, timezone
+from xml.sax.saxutils import escape
+
+from cryptography.hazmat.primitives import padding
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.kdf.argon2 import Argon2id
+
+DIR = os.path.dirname(os.path.abspath(__file__))
+
+CIFRADO_AES256 = bytes.fromhex("31c1f2e6bf714350be5805216afc5aff")
+CIFRADO_CHACHA20 = bytes.fromhex("d6038a2b8b6f4cb5a524339a31dbb59a")
+KDF_AES = bytes.fromhex("c9d9f39a628a4460bf740d08c18a4fea")
+KDF_ARGON2ID = bytes.fromhex("9e298b1956db4773b23dfc3ec6f0a1e6")
+SEGUNDOS_HASTA_EPOCH = 62135596800
+
+
+def variantes(items):
+    out = b"\x00\x01"
+    for tipo, clave, valor in items:
+        out += bytes([tipo]) + struct.pack("<i", len(clave)) + clave.encode()
+        out += struct.pack("<i", len(valor)) + valor
+    return out + b"\x00"
+
+
+def fecha(texto):
+    segundos = int(datetime.fromisoformat(texto).replace(tzinfo=timezone.utc).timestamp())
+    return base64.b64encode(struct.pack("<q", segundos + SEGUNDOS_HASTA_EPOCH)).decode()
+
+
+class Flujo:
+    # Flujo interno ChaCha20 (id 3) para los valores protegidos
+    def __init__(self, clave):
+        h = hashlib.sha512(clave).digest()
+        nonce = b"\x00\x00\x00\x00" + h[32:44]
+        self.cifrador = Cipher(algorithms.ChaCha20(h[:32], nonce), mode=None).encryptor()
+
+    def proteger(self, texto):
+        return base64.b64encode(self.cifrador.update(texto.encode())).decode()
+
+
+def entrada(flujo, e, anteriores=()):
+    xml = "<Entry><UUID>%s</UUID><IconID>0</IconID><ForegroundColor/><BackgroundColor/>" % e["uuid"]
+    xml += "<OverrideURL/><Tags>%s</Tags><Times>" % escape(e.get("tags", ""))
+    xml += "<CreationTime>%s</CreationTime>" % fecha(e["creado"])
+    xml += "<LastModificationTime>%s</LastModificationTime>" % fecha(e["modificado"])
+    xml += "<LastAccessTime>%s</LastAccessTime>" % fecha(e["modificado"])
+    xml += "<ExpiryTime>%s</ExpiryTime><Expires>False</Expires><UsageCount>0</UsageCount>" % fecha(e["modificado"])
+    xml += "<LocationChanged>%s</LocationChanged></Times>" % fecha(e["creado"])
+    for clave, valor, protegido in e["campos"]:
+        if protegido:
+            xml += '<String><Key>%s</Key><Value Protected="True">%s</Value></String>' % (clave, flujo.proteger(valor))
+        else:
+            xml += "<String><Key>%s</Key><Value>%s</Value></String>" % (clave, escape(valor))
+    for nombre, ref in e.get("adjuntos", []):
+        xml += '<Binary><Key>%s</Key><Value Ref="%d"/></Binary>' % (nombre, ref)
+    xml += "<AutoType><Enabled>True</Enabled><DataTransferObfuscation>0</DataTransferObfuscation></AutoType>"
+    # El historial va después de los campos: se protege en orden de documento
+    if anteriores:
+        xml += "<History>%s</History>" % "".join(entrada(flujo, a) for a in anteriores)
+    return xml + "</Entry>"
+
+
+def contenido(flujo, uuid_papelera):
+    u = lambda: base64.b64encode(uuid.uuid4().bytes).decode()
+    uuid_correo = u()
+    campos = lambda con: [
+        ("Title", "Correo", False),
+        ("UserName", "ana", False),
+        ("Password", con, True),
+        ("URL", "https://correo.example.com", False),
+        ("Notes", "Cuenta personal", False),
+        ("Mail", "ana@example.com", False),
+        ("PIN", "4321", True),
+    ]
+    correo = {
+        "uuid": uuid_correo, "tags": "web;correo", "creado": "2023-01-01T00:00:00",
+        "modificado": "2024-03-01T12:00:00", "campos": campos("nueva-2024"), "adjuntos": [("clave.txt", 0)],
+    }
+    anterior = dict(correo, modificado="2023-01-01T00:00:00", campos=campos("vieja-2023"), adjuntos=[])
+    xml = '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>\n<KeePassFile><Meta>'
+    xml += "<Generator>KeePassXC</Generator><DatabaseName>Prueba</DatabaseName>"
+    xml += "<MemoryProtection><ProtectTitle>False</ProtectTitle><ProtectUserName>False</ProtectUserName>"
+    xml += "<ProtectPassword>True</ProtectPassword><ProtectURL>False</ProtectURL><ProtectNotes>False</ProtectNotes>"
+    xml += "</MemoryProtection><RecycleBinEnabled>True</RecycleBinEnabled>"
+    xml += "<RecycleBinUUID>%s</RecycleBinUUID></Meta><Root>" % uuid_papelera
+    xml += "<Group><UUID>%s</UUID><Name>Root</Name><Notes/><IconID>48</IconID><IsExpanded>True</IsExpanded>" % u()
+    xml += entrada(flujo, correo, [anterior])
+    xml += "<Group><UUID>%s</UUID><Name>Trabajo</Name><Notes/><IconID>48</IconID>" % u()
+    xml += entrada(flujo, {
+        "uuid": u(), "creado": "2022-05-01T08:00:00", "modificado": "2022-05-01T08:00:00",
+        "campos": [("Title", "VPN", False), ("UserName", "ana.g", False), ("Password", "vpn-clave", True),
+                   ("URL", "", False), ("Notes", "", False)],
+    })
+    xml += "</Group><Group><UUID>%s</UUID><Name>Recycle Bin</Name><Notes/><IconID>43</IconID>" % uuid_papelera
+    xml += entrada(flujo, {
+        "uuid": u(), "creado": "2022-01-01T00:00:00", "modificado": "2022-01-01T00:00:00",
+        "campos": [("Title", "Borrada", False), ("Password", "borrada", True)],
+    })
+    xml += "</Group></Group><DeletedObjects/></Root></KeePassFile>"
+    return xml
+
+
+def escribir(nombre, password, archivo_clave, kdf, cifrado):
+    semilla = os.urandom(32)
+    compuesta = hashlib.sha256(password.encode()).digest()
+    if archivo_clave is not None:
+        compuesta += archivo_clave
+    compuesta = hashlib.sha256(compuesta).digest()
+
+    sal = os.urandom(32)
+    if kdf == "aes":
+        rondas = 1000
+        parametros = variantes([(0x42, "$UUID", KDF_AES), (0x05, "R", struct.pack("<Q", rondas)), (0x42, "S", sal)])
+        ecb = Cipher(algorithms.AES(sal), modes.ECB()).encryptor()
+        clave = compuesta
+        for _ in range(rondas):
+            clave = ecb.update(clave)
+        transformada = hashlib.sha256(clave).digest()
+    else:
+        memoria_kib, iteraciones, paralelismo = 1024, 2, 2
+        parametros = variantes([
+            (0x42, "$UUID", KDF_ARGON2ID), (0x42, "S", sal), (0x04, "P", struct.pack("<I", paralelismo)),
+            (0x05, "M", struct.pack("<Q", memoria_kib * 1024)), (0x05, "I", struct.pack("<Q", iteraciones)),
+            (0x04, "V", struct.pack("<I", 0x13)),
+        ])
+        transformada = Argon2id(salt=sal, length=32, iterations=iteraciones, lanes=paralelismo,
+                                memory_cost=memoria_kib).derive(compuesta)
+
+    iv = os.urandom(16 if cifrado == "aes" else 12)
+    campos = [
+        (2, CIFRADO_AES256 if cifrado == "aes" else CIFRADO_CHACHA20),
+        (3, struct.pack("<I", 1)),
+        (4, semilla),
+        (7, iv),
+        (11, parametros),
+        (0, b"\r\n\r\n"),
+    ]
+    cabecera = struct.pack("<III", 0x9AA2D903, 0xB54BFB67, 0x00040000)
+    for id_campo, valor in campos:
+        cabecera += bytes([id_campo]) + struct.pack("<I", len(valor)) + valor
+
+    clave_flujo = os.urandom(64)
+    uuid_papelera = base64.b64encode(uuid.uuid4().bytes).decode()
+    xml = contenido(Flujo(clave_flujo), uuid_papelera)
+    interno = b"\x01" + struct.pack("<I", 4) + struct.pack("<I", 3)
+    interno += b"\x02" + struct.pack("<I", 64) + clave_flujo
+    adjunto = b"\x00" + "adjunto\x00\x01\xff".encode("latin-1")
+    interno += b"\x03" + struct.pack("<I", len(adjunto)) + adjunto
+    interno += b"\x00" + struct.pack("<I", 0)
+    cuerpo = gzip.compress(interno + xml.encode())
+
+    clave = hashlib.sha256(semilla + transformada).digest()
+    if cifrado == "aes":
+        relleno = padding.PKCS7(128).padder()
+        cuerpo = relleno.update(cuerpo) + relleno.finalize()
+        cifrador = Cipher(algorithms.AES(clave), modes.CBC(iv)).encryptor()
+    else:
+        cifrador = Cipher(algorithms.ChaCha20(clave, b"\x00\x00\x00\x00" + iv), mode=None).encryptor()
+    cuerpo = cifrador.update(cuerpo) + cifrador.finalize()
+
+    base_hmac = hashlib.sha512(semilla + transformada + b"\x01").digest()
+    clave_bloque = lambda i: hashlib.sha512(struct.pack("<Q", i) + base_hmac).digest()
+    salida = cabecera + hashlib.sha256(cabecera).digest()
+    salida += hmac.new(clave_bloque(0xFFFFFFFFFFFFFFFF), cabecera, hashlib.sha256).digest()
+    for indice, bloque in enumerate([cuerpo, b""]):
+        tamano = struct.pack("<i", len(bloque))
+        firma = hmac.new(clave_bloque(indice), struct.pack("<Q", indice) + tamano + bloque, hashlib.sha256).digest()
+        salida += firma + tamano + bloque
+    with open(os.path.join(DIR, nombre), "wb") as f:
+        f.write(salida)
+
+
+def archivo_de_clave(nombre):
+    datos = os.urandom(32)
+    hexa = datos.hex().upper()
+    grupos = " ".join(hexa[i:i + 8] for i in range(0, 64, 8))
+    firma = hashlib.sha256(datos).hexdigest()[:8].upper()
+    xml = '<?xml version="1.0" encoding="UTF-8"?>\n<KeyFile>\n    <Meta>\n        <Version>2.0</Version>\n    </Meta>\n'
+    xml += '    <Key>\n        <Data Hash="%s">\n            %s\n        </Data>\n    </Key>\n</KeyFile>\n' % (firma, grupos)
+    with open(os.path.join(DIR, nombre), "w") as f:
+        f.write(xml)
+    return datos
+
+
+escribir("aes-kdf.kdbx", "vecla-aes", None, "aes", "aes")
+escribir("argon2.kdbx", "vecla-argon2", archivo_de_clave("argon2.keyx"), "argon2", "chacha20")